@@ -0,0 +1,24 @@
+//! Solutions for Advent of Code 2021.
+//!
+//! Every day lives in `util::day_NN` with a `DayNN` solver, the parsed input
+//! type and the functions solving each part. `registry()` returns all solvers,
+//! use `Puzzle::solve` to run one on a puzzle input:
+//!
+//! ```
+//! use advent_of_code_2021::{registry, Part};
+//!
+//! let registry = registry();
+//! let answer = registry.get(1).unwrap().solve("199\n200\n208\n210", Part::Part1);
+//! assert_eq!(Ok("3".to_string()), answer);
+//! ```
+
+extern crate num_bigint;
+extern crate permute;
+extern crate regex;
+
+pub mod util;
+
+pub use util::error::{Result, SolveError};
+pub use util::registry::Registry;
+pub use util::solver::{Puzzle, Solver};
+pub use util::{registry, Part};
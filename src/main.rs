@@ -1,38 +1,420 @@
-extern crate core;
-extern crate regex;
-extern crate permute;
-extern crate proc_macro;
+extern crate advent_of_code_2021;
 
+use std::collections::HashMap;
 use std::io::prelude::*;
+use std::io::BufReader;
+use std::io::IsTerminal;
 use std::env;
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
 
-mod util;
+use advent_of_code_2021::{registry, Part, Puzzle, Registry};
+use advent_of_code_2021::util::bench::{self, Baseline, BenchConfig};
+use advent_of_code_2021::util::packet_computer::{self, disassembler, expression, Mode};
+use advent_of_code_2021::util::packet_computer::bits::{Format, StreamCursor};
+use advent_of_code_2021::util::packet_computer::transmissions::{self, Transmission};
+use advent_of_code_2021::util::params;
+use advent_of_code_2021::util::runner::{self, RunConfig};
+use advent_of_code_2021::util::verify::{self, Manifest, Status};
 
-use util::*;
+fn usage() -> ! {
+    eprintln!("program <day> <part> [solver] [--input FILE | --example] [--param NAME=VALUE]... [--format text|json]");
+    eprintln!("program run-all [input dir] [--workers N] [--timeout SECS] [--format text|json]");
+    eprintln!("program bench <day> <part> [solver] [--input FILE] [--warmup N] [--samples N] [--save FILE] [--baseline FILE] [--threshold PCT] [--param NAME=VALUE]...");
+    eprintln!("program verify [answers file] [--dir INPUT_DIR]");
+    eprintln!("program params <day> [solver]");
+    eprintln!("program disasm [--input FILE] [--infix] [--strict]");
+    eprintln!("program compile <expression>");
+    eprintln!("program transmissions [--input FILE] [--lines] [--strict]");
+    eprintln!("program list");
+    std::process::exit(1);
+}
 
-fn main() {
+fn fail(message:&str) -> ! {
+    eprintln!("error: {}", message);
+    std::process::exit(1);
+}
 
-    let args: Vec<String> = env::args().collect();
+/// Options that don't take a value.
+const FLAGS:[&str; 4] = ["example", "infix", "lines", "strict"];
 
-    if args.len() != 3 {
-        println!("program <day> <part>");
-        std::process::exit(1);
+/// `--param` assignments in the order they were given.
+type Assignments = Vec<(String, String)>;
+
+/// Splits arguments into positional ones, `--param name=value` assignments and
+/// other `--name value` options, flags get an empty value.
+fn split_args(args:&[String]) -> (Vec<&str>, HashMap<&str, &str>, Assignments) {
+    let mut positional = vec![];
+    let mut options = HashMap::new();
+    let mut params = vec![];
+    let mut it = args.iter();
+    while let Some(arg) = it.next() {
+        if let Some(name) = arg.strip_prefix("--") {
+            if FLAGS.contains(&name) {
+                options.insert(name, "");
+                continue;
+            }
+
+            match it.next() {
+                Some(value) if name == "param" => {
+                    params.push(params::parse_assignment(value).unwrap_or_else(|e| fail(&e.to_string())));
+                }
+                Some(value) => {
+                    options.insert(name, value.as_str());
+                }
+                None => fail(&format!("missing value for {}", arg)),
+            };
+        } else {
+            positional.push(arg.as_str());
+        }
     }
+    (positional, options, params)
+}
 
-    // Read arguments
-    let day = args[1].parse::<u8>().unwrap();
-    let part = match args[2].parse::<u8>() {
-        Ok(1) => Part::Part1,
-        Ok(2) => Part::Part2,
-        _ => panic!("illegal part arguments!")
+fn option<T:std::str::FromStr>(options:&HashMap<&str, &str>, name:&str, default:T) -> T {
+    match options.get(name) {
+        Some(value) => value.parse().unwrap_or_else(|_| fail(&format!("illegal value '{}' for --{}", value, name))),
+        None => default,
+    }
+}
+
+/// Whether `--format json` was given, the default is text.
+fn json_format(options:&HashMap<&str, &str>) -> bool {
+    match options.get("format") {
+        None | Some(&"text") => false,
+        Some(&"json") => true,
+        Some(format) => fail(&format!("unknown format '{}', expected text or json", format)),
+    }
+}
+
+fn parse_day(arg:&str) -> u8 {
+    arg.parse::<u8>().unwrap_or_else(|_| fail(&format!("illegal day argument '{}'", arg)))
+}
+
+fn parse_part(arg:&str) -> Part {
+    arg.parse::<u8>().ok()
+        .and_then(Part::from_number)
+        .unwrap_or_else(|| fail(&format!("illegal part argument '{}'", arg)))
+}
+
+/// Finds the solver for `day`, a named variant can be given instead of the default one.
+fn find_puzzle<'a>(registry:&'a Registry, day:u8, name:Option<&str>) -> &'a dyn Puzzle {
+    let puzzle = match name {
+        Some(name) => registry.find(name).filter(|puzzle| puzzle.day() == day),
+        None => registry.get(day),
     };
 
-    // Read input
+    match puzzle {
+        Some(puzzle) => puzzle,
+        None => {
+            let names:Vec<&str> = registry.variants(day).iter().map(|puzzle| puzzle.name()).collect();
+            eprintln!("No such solver for day {}, registered solvers: {:?}", day, names);
+            eprintln!("Registered days: {:?}", registry.days());
+            std::process::exit(1);
+        }
+    }
+}
+
+/// Reads the file given with `--input`, otherwise stdin unless it's a terminal,
+/// in which case `input_NN.txt` for the day is used.
+fn load_input(day:u8, options:&HashMap<&str, &str>) -> String {
+    if let Some(path) = options.get("input") {
+        return std::fs::read_to_string(path).unwrap_or_else(|e| fail(&format!("can't read {}: {}", path, e)));
+    }
+
+    if std::io::stdin().is_terminal() {
+        return runner::read_input(Path::new("."), day).unwrap_or_else(|e| fail(&e.to_string()));
+    }
+
     let mut input = String::new();
-    std::io::stdin().read_to_string(&mut input).expect("Failed to fetch input...");
+    std::io::stdin().read_to_string(&mut input).unwrap_or_else(|e| fail(&format!("can't read stdin: {}", e)));
+    input
+}
+
+/// Solves every example of the puzzle that has an answer for `part`, answers
+/// are only checked when no parameters are set.
+fn run_examples(puzzle:&dyn Puzzle, part:Part, params:&[(String, String)]) {
+    let examples:Vec<_> = puzzle.examples().into_iter()
+        .filter(|example| example.answer(part).is_some())
+        .collect();
+    if examples.is_empty() {
+        fail(&format!("no examples for day {}, {}", puzzle.day(), part));
+    }
+
+    let mut failed = false;
+    for (i, example) in examples.iter().enumerate() {
+        let answer = puzzle.solve_with(example.input, part, params).unwrap_or_else(|e| fail(&e.to_string()));
+        let expected = example.answer(part).unwrap();
+        if examples.len() > 1 {
+            print!("example {}: ", i + 1);
+        }
+        println!("{}", answer);
+
+        if params.is_empty() && answer != expected {
+            eprintln!("expected {}", expected);
+            failed = true;
+        }
+    }
+
+    if failed {
+        std::process::exit(1);
+    }
+}
+
+fn list(registry:&Registry) {
+    for puzzle in registry.iter() {
+        let status = if puzzle.is_stub() { "stub" } else { "ok" };
+        println!("{:>2}  {:<4}  {}", puzzle.day(), status, puzzle.name());
+    }
+
+    println!("implemented days: {:?}", registry.implemented_days());
+    println!("stubbed days: {:?}", registry.stubbed_days());
+}
+
+/// Decodes every transmission of a log as it is read, printing index, version
+/// sum and value of each.
+fn run_transmissions(options:&HashMap<&str, &str>) {
+    let mode = if options.contains_key("strict") { Mode::Strict } else { Mode::Lenient };
+    let input:Box<dyn BufRead> = match options.get("input") {
+        Some(path) => Box::new(BufReader::new(std::fs::File::open(path).unwrap_or_else(|e| fail(&format!("can't read {}: {}", path, e))))),
+        None => Box::new(BufReader::new(std::io::stdin())),
+    };
+    let results:Box<dyn Iterator<Item=advent_of_code_2021::Result<Transmission>>> = if options.contains_key("lines") {
+        Box::new(transmissions::transmission_lines(input, mode))
+    } else {
+        Box::new(transmissions::transmissions(StreamCursor::new(input, Format::Hex), mode))
+    };
+
+    let mut failed = false;
+    for result in results {
+        let transmission = match result {
+            Ok(transmission) => transmission,
+            Err(e) => {
+                eprintln!("error: {}", e);
+                failed = true;
+                continue;
+            }
+        };
+
+        for warning in &transmission.warnings {
+            eprintln!("warning: transmission {}: {}", transmission.index, warning);
+        }
+        match transmission.value() {
+            Ok(value) => println!("{:>6}  {:>6}  {}", transmission.index, transmission.version_sum(), value),
+            Err(e) => {
+                println!("{:>6}  {:>6}  error: {}", transmission.index, transmission.version_sum(), e);
+                failed = true;
+            }
+        }
+    }
 
-    let result = get_solution(day, part, input);
+    if failed {
+        std::process::exit(1);
+    }
+}
+
+fn list_params(registry:&Registry, args:&[&str]) {
+    if args.len() != 1 && args.len() != 2 {
+        usage();
+    }
+
+    let puzzle = find_puzzle(registry, parse_day(args[0]), args.get(1).cloned());
+    let params = puzzle.params();
+    if params.is_empty() {
+        println!("{} has no parameters", puzzle.name());
+    }
+    for param in params {
+        println!("{:<14} {:<10} {}", param.name, param.value, param.description);
+    }
+}
+
+fn run_bench(registry:&Registry, args:&[&str], options:&HashMap<&str, &str>, params:&[(String, String)]) {
+    if args.len() != 2 && args.len() != 3 {
+        usage();
+    }
+
+    let day = parse_day(args[0]);
+    let part = parse_part(args[1]);
+    let puzzle = find_puzzle(registry, day, args.get(2).cloned());
 
-    println!("{}",result);
+    let defaults = BenchConfig::default();
+    let config = BenchConfig{
+        warmup:option(options, "warmup", defaults.warmup),
+        samples:option(options, "samples", defaults.samples),
+    };
+    let threshold = option(options, "threshold", 10.0);
+
+    let input = load_input(day, options);
+    let result = bench::bench(puzzle, &input, part, params, config).unwrap_or_else(|e| fail(&e.to_string()));
+
+    println!("day {}, {}, {} samples after {} warmup runs", day, part, config.samples, config.warmup);
+    println!("parse  {}", result.parse);
+    println!("solve  {}", result.solve);
+
+    let mut regressed = false;
+    if let Some(path) = options.get("baseline") {
+        let baseline = Baseline::load(Path::new(path)).unwrap_or_else(|e| fail(&e.to_string()));
+        let comparisons = baseline.compare(&result, threshold);
+        if comparisons.is_empty() {
+            println!("no baseline for day {}, {} in {}", day, part, path);
+        }
+        for comparison in comparisons {
+            println!("{}  {:>10} -> {:>10}  {:+.1}%{}", comparison.phase,
+                     runner::format_duration(comparison.baseline), runner::format_duration(comparison.current),
+                     comparison.change, if comparison.regression { "  REGRESSION" } else { "" });
+            regressed |= comparison.regression;
+        }
+    }
+
+    if let Some(path) = options.get("save") {
+        let path = Path::new(path);
+        let mut baseline = if path.exists() {
+            Baseline::load(path).unwrap_or_else(|e| fail(&e.to_string()))
+        } else {
+            Baseline::default()
+        };
+        baseline.update(&result);
+        baseline.save(path).unwrap_or_else(|e| fail(&e.to_string()));
+    }
+
+    if regressed {
+        std::process::exit(1);
+    }
+}
+
+fn run_verify(registry:&Registry, args:&[&str], options:&HashMap<&str, &str>) {
+    let path = args.first().cloned().unwrap_or("answers.tsv");
+    let dir = options.get("dir").cloned().unwrap_or(".");
+    let manifest = Manifest::load(Path::new(path)).unwrap_or_else(|e| fail(&e.to_string()));
+
+    let checks = verify::verify(registry, &manifest, Path::new(dir));
+    let (mut passed, mut failed, mut missing) = (0, 0, 0);
+    for check in &checks {
+        let status = match &check.status {
+            Status::Pass => {
+                passed += 1;
+                "pass".to_string()
+            }
+            Status::Fail(answer) => {
+                failed += 1;
+                format!("FAIL, got '{}'", verify::escape(answer))
+            }
+            Status::Error(e) => {
+                failed += 1;
+                format!("ERROR, {}", e)
+            }
+            Status::Missing => {
+                missing += 1;
+                "missing".to_string()
+            }
+        };
+        println!("{:>3}  {}  {:<28} {:<14} {}", check.day, check.part, check.solver,
+                 check.input.as_deref().unwrap_or("-"), status);
+    }
+
+    println!("{} passed, {} failed, {} missing", passed, failed, missing);
+    if failed > 0 {
+        std::process::exit(1);
+    }
+}
+
+fn main() {
+
+    let args: Vec<String> = env::args().skip(1).collect();
+    let registry = Arc::new(registry());
+    let (args, options, params) = split_args(&args);
+
+    match args.first() {
+        Some(&"list") => {
+            list(&registry);
+            return;
+        }
+        Some(&"run-all") => {
+            let dir = args.get(1).cloned().unwrap_or(".");
+            let defaults = RunConfig::default();
+            let config = RunConfig{
+                workers:option(&options, "workers", defaults.workers),
+                timeout:options.get("timeout").map(|value| match value.parse::<f64>() {
+                    Ok(secs) if secs.is_finite() && secs > 0.0 => Duration::from_secs_f64(secs),
+                    _ => fail(&format!("illegal value '{}' for --timeout", value)),
+                }),
+            };
+            let runs = runner::run_all_parallel(registry, Path::new(dir), config);
+            if json_format(&options) {
+                runs.iter().flat_map(|run| run.to_json()).for_each(|line| println!("{}", line));
+            } else {
+                runner::print_report(&runs, 5);
+            }
+            if !runs.iter().all(|run| run.is_ok()) {
+                std::process::exit(1);
+            }
+            return;
+        }
+        Some(&"verify") => {
+            run_verify(&registry, &args[1..], &options);
+            return;
+        }
+        Some(&"params") => {
+            list_params(&registry, &args[1..]);
+            return;
+        }
+        Some(&"disasm") => {
+            let input = load_input(16, &options);
+            let mode = if options.contains_key("strict") { Mode::Strict } else { Mode::Lenient };
+            let (packet, warnings) = packet_computer::decode(input.trim(), mode).unwrap_or_else(|e| fail(&e.to_string()));
+            for warning in warnings {
+                eprintln!("warning: {}", warning);
+            }
+            if options.contains_key("infix") {
+                println!("{}", disassembler::infix(&packet));
+            } else {
+                print!("{}", disassembler::disassemble(&packet));
+            }
+            return;
+        }
+        Some(&"compile") if args.len() == 2 => {
+            println!("{}", expression::compile_hex(args[1]).unwrap_or_else(|e| fail(&e.to_string())));
+            return;
+        }
+        Some(&"transmissions") => {
+            run_transmissions(&options);
+            return;
+        }
+        Some(&"bench") => {
+            run_bench(&registry, &args[1..], &options, &params);
+            return;
+        }
+        _ => {}
+    }
+
+    if args.len() != 2 && args.len() != 3 {
+        usage();
+    }
+
+    // Read arguments
+    let day = parse_day(args[0]);
+    let part = parse_part(args[1]);
+    let puzzle = find_puzzle(&registry, day, args.get(2).cloned());
+
+    if options.contains_key("example") {
+        run_examples(puzzle, part, &params);
+        return;
+    }
+
+    let input = load_input(day, &options);
+    if json_format(&options) {
+        let run = runner::run_parts(puzzle, &input, &[part], &params);
+        run.to_json().iter().for_each(|line| println!("{}", line));
+        if !run.is_ok() {
+            std::process::exit(1);
+        }
+        return;
+    }
+
+    match puzzle.solve_with(&input, part, &params) {
+        Ok(result) => println!("{}", result),
+        Err(e) => fail(&e.to_string()),
+    }
 
 }
@@ -1,71 +1,102 @@
-mod day_01;
-mod day_02;
-mod day_03;
-mod day_04;
-mod day_05;
-mod day_06;
-mod day_07;
-mod day_08;
-mod day_09;
-mod day_10;
-mod day_11;
-mod day_12;
-mod day_13;
-mod day_14;
-mod day_15;
-mod day_16;
-mod day_17;
-mod day_18;
-mod day_19;
-mod day_20;
-mod day_21;
-mod day_22;
-mod day_23;
-mod day_24;
-mod day_25;
+pub mod day_01;
+pub mod day_02;
+pub mod day_03;
+pub mod day_04;
+pub mod day_05;
+pub mod day_06;
+pub mod day_07;
+pub mod day_08;
+pub mod day_09;
+pub mod day_10;
+pub mod day_11;
+pub mod day_12;
+pub mod day_13;
+pub mod day_14;
+pub mod day_15;
+pub mod day_16;
+pub mod day_17;
+pub mod day_18;
+pub mod day_19;
+pub mod day_20;
+pub mod day_21;
+pub mod day_22;
+pub mod day_23;
+pub mod day_24;
+pub mod day_25;
 
-mod packet_computer;
+pub mod packet_computer;
+pub mod bench;
+pub mod error;
+pub mod json;
+pub mod params;
+pub mod random;
+pub mod registry;
+pub mod runner;
+pub mod solver;
+pub mod verify;
 
-#[derive(PartialEq,Copy, Clone)]
+use std::fmt;
+use self::registry::Registry;
+
+#[derive(Debug, PartialEq,Copy, Clone)]
 pub enum Part {
     Part1,
     Part2,
 }
 
-
-
-pub fn get_solution(day:u8, part:Part, input:String) -> String {
-    match day {
-        1 => day_01::solve(input, part),
-        2 => day_02::solve(input, part),
-        3 => day_03::solve(input, part),
-        4 => day_04::solve(input, part),
-        5 => day_05::solve(input, part),
-        6 => day_06::solve(input, part),
-        7 => day_07::solve(input, part),
-        8 => day_08::solve(input, part),
-        9 => day_09::solve(input, part),
-        10 => day_10::solve(input, part),
-        11 => day_11::solve(input, part),
-        12 => day_12::solve(input, part),
-        13 => day_13::solve(input, part),
-        14 => day_14::solve(input, part),
-        15 => day_15::solve(input, part),
-        16 => day_16::solve(input, part),
-        17 => day_17::solve(input, part),
-        18 => day_18::solve(input, part),
-        19 => day_19::solve(input, part),
-        20 => day_20::solve(input, part),
-        21 => day_21::solve(input, part),
-        22 => day_22::solve(input, part),
-        23 => day_23::solve(input, part),
-        24 => day_24::solve(input, part),
-        25 => day_25::solve(input, part),
-        _ => panic!("..."),
+impl Part {
+    pub fn number(&self) -> u8 {
+        match self {
+            Part::Part1 => 1,
+            Part::Part2 => 2,
+        }
     }
 
+    pub fn from_number(number:u8) -> Option<Part> {
+        match number {
+            1 => Some(Part::Part1),
+            2 => Some(Part::Part2),
+            _ => None,
+        }
+    }
 }
 
+impl fmt::Display for Part {
+    fn fmt(&self, f:&mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Part::Part1 => write!(f, "part 1"),
+            Part::Part2 => write!(f, "part 2"),
+        }
+    }
+}
 
 
-
+pub fn registry() -> Registry {
+    let mut registry = Registry::new();
+    registry.register(day_01::Day01);
+    registry.register(day_02::Day02);
+    registry.register(day_03::Day03);
+    registry.register(day_04::Day04);
+    registry.register(day_05::Day05);
+    registry.register(day_06::Day06);
+    registry.register(day_07::Day07);
+    registry.register(day_08::Day08);
+    registry.register(day_09::Day09);
+    registry.register(day_10::Day10);
+    registry.register(day_11::Day11);
+    registry.register(day_12::Day12);
+    registry.register(day_13::Day13);
+    registry.register(day_14::Day14);
+    registry.register(day_15::Day15);
+    registry.register(day_16::Day16);
+    registry.register(day_17::Day17);
+    registry.register(day_18::Day18);
+    registry.register(day_19::Day19);
+    registry.register(day_20::Day20);
+    registry.register(day_21::Day21);
+    registry.register(day_22::Day22);
+    registry.register(day_23::Day23);
+    registry.register(day_24::Day24);
+    registry.register(day_25::Day25);
+    registry
+}
@@ -0,0 +1,243 @@
+use std::collections::BTreeMap;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+use std::time::{Duration, Instant};
+use super::error::{Result, SolveError};
+use super::runner::format_duration;
+use super::solver::Puzzle;
+use super::Part;
+
+#[derive(Debug, Clone, Copy)]
+pub struct BenchConfig {
+    pub warmup:usize,
+    pub samples:usize,
+}
+
+impl Default for BenchConfig {
+    fn default() -> BenchConfig {
+        BenchConfig{warmup:3, samples:20}
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Stats {
+    pub min:Duration,
+    pub median:Duration,
+    pub p95:Duration,
+    pub stddev:Duration,
+}
+
+impl Stats {
+    /// Summary of `samples`, which must not be empty.
+    pub fn new(samples:&[Duration]) -> Stats {
+        let mut sorted = samples.to_vec();
+        sorted.sort_unstable();
+
+        let n = sorted.len();
+        let median = if n.is_multiple_of(2) {
+            (sorted[n / 2 - 1] + sorted[n / 2]) / 2
+        } else {
+            sorted[n / 2]
+        };
+
+        // Nearest rank percentile
+        let p95 = sorted[(n * 95).div_ceil(100).max(1) - 1];
+
+        let secs:Vec<f64> = sorted.iter().map(|d| d.as_secs_f64()).collect();
+        let mean = secs.iter().sum::<f64>() / n as f64;
+        let variance = secs.iter().map(|s| (s - mean) * (s - mean)).sum::<f64>() / n as f64;
+
+        Stats{min:sorted[0], median, p95, stddev:Duration::from_secs_f64(variance.sqrt())}
+    }
+}
+
+impl fmt::Display for Stats {
+    fn fmt(&self, f:&mut fmt::Formatter) -> fmt::Result {
+        write!(f, "min {:>10}  median {:>10}  p95 {:>10}  stddev {:>10}",
+               format_duration(self.min), format_duration(self.median),
+               format_duration(self.p95), format_duration(self.stddev))
+    }
+}
+
+pub struct BenchResult {
+    pub day:u8,
+    pub part:Part,
+    pub parse:Stats,
+    pub solve:Stats,
+}
+
+/// Times parsing and solving `input` with the `params` assignments separately,
+/// after `config.warmup` untimed runs.
+pub fn bench(puzzle:&dyn Puzzle, input:&str, part:Part, params:&[(String, String)], config:BenchConfig) -> Result<BenchResult> {
+    if config.samples == 0 {
+        return Err(SolveError::new("at least one sample is needed"));
+    }
+
+    for _ in 0..config.warmup {
+        puzzle.solve_with(input, part, params)?;
+    }
+
+    let mut parse_samples = vec![];
+    let mut solve_samples = vec![];
+    for _ in 0..config.samples {
+        let start = Instant::now();
+        let parsed = puzzle.parse_input(input)?;
+        parse_samples.push(start.elapsed());
+
+        let start = Instant::now();
+        puzzle.solve_input_with(parsed.as_ref(), part, params)?;
+        solve_samples.push(start.elapsed());
+    }
+
+    Ok(BenchResult{
+        day:puzzle.day(),
+        part,
+        parse:Stats::new(&parse_samples),
+        solve:Stats::new(&solve_samples),
+    })
+}
+
+/// Median timings from an earlier run, stored as `<day> <part> <phase> <median ns>` lines.
+#[derive(Debug, Default, PartialEq)]
+pub struct Baseline {
+    medians:BTreeMap<(u8, u8, String), u128>,
+}
+
+/// Change of one phase compared to the baseline.
+pub struct Comparison {
+    pub phase:&'static str,
+    pub baseline:Duration,
+    pub current:Duration,
+    pub change:f64,
+    pub regression:bool,
+}
+
+impl Baseline {
+    pub fn load(path:&Path) -> Result<Baseline> {
+        let text = fs::read_to_string(path).map_err(|e| {
+            SolveError::new(&format!("can't read {}: {}", path.display(), e))
+        })?;
+        Baseline::parse(&text)
+    }
+
+    pub fn parse(text:&str) -> Result<Baseline> {
+        let mut medians = BTreeMap::new();
+        for (i, line) in text.lines().enumerate().filter(|(_, line)| !line.trim().is_empty()) {
+            let fields:Vec<&str> = line.split_whitespace().collect();
+            let entry = match fields.as_slice() {
+                [day, part, phase, median] => day.parse().ok()
+                    .and_then(|day| part.parse().ok().map(|part| (day, part)))
+                    .and_then(|(day, part)| median.parse().ok().map(|median| ((day, part, phase.to_string()), median))),
+                _ => None,
+            };
+
+            match entry {
+                Some((key, median)) => medians.insert(key, median),
+                None => return Err(SolveError::on_line(i + 1, "expected '<day> <part> <phase> <median ns>'")),
+            };
+        }
+        Ok(Baseline{medians})
+    }
+
+    pub fn save(&self, path:&Path) -> Result<()> {
+        fs::write(path, self.to_string()).map_err(|e| {
+            SolveError::new(&format!("can't write {}: {}", path.display(), e))
+        })
+    }
+
+    /// Adds or replaces the medians of `result`.
+    pub fn update(&mut self, result:&BenchResult) {
+        let part = result.part.number();
+        self.medians.insert((result.day, part, "parse".to_string()), result.parse.median.as_nanos());
+        self.medians.insert((result.day, part, "solve".to_string()), result.solve.median.as_nanos());
+    }
+
+    /// Compares the medians of `result`, a phase regressed if it is more than
+    /// `threshold` percent slower. Phases missing in the baseline are skipped.
+    pub fn compare(&self, result:&BenchResult, threshold:f64) -> Vec<Comparison> {
+        let part = result.part.number();
+        [("parse", result.parse.median), ("solve", result.solve.median)].iter()
+            .filter_map(|&(phase, current)| {
+                let baseline = *self.medians.get(&(result.day, part, phase.to_string()))?;
+                let baseline = Duration::from_nanos(baseline as u64);
+                let change = if baseline.as_nanos() > 0 {
+                    (current.as_secs_f64() / baseline.as_secs_f64() - 1.0) * 100.0
+                } else {
+                    0.0
+                };
+                Some(Comparison{phase, baseline, current, change, regression:change > threshold})
+            })
+            .collect()
+    }
+}
+
+impl fmt::Display for Baseline {
+    fn fmt(&self, f:&mut fmt::Formatter) -> fmt::Result {
+        for ((day, part, phase), median) in &self.medians {
+            writeln!(f, "{} {} {} {}", day, part, phase, median)?;
+        }
+        Ok(())
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use util::registry;
+
+    fn result(parse:u64, solve:u64) -> BenchResult {
+        let stats = |micros| Stats::new(&[Duration::from_micros(micros)]);
+        BenchResult{day:15, part:Part::Part2, parse:stats(parse), solve:stats(solve)}
+    }
+
+    #[test]
+    fn test_stats() {
+        let samples:Vec<Duration> = (1..=20).rev().map(Duration::from_millis).collect();
+        let stats = Stats::new(&samples);
+
+        assert_eq!(Duration::from_millis(1), stats.min);
+        assert_eq!(Duration::from_micros(10500), stats.median);
+        assert_eq!(Duration::from_millis(19), stats.p95);
+        assert_eq!(5766, stats.stddev.as_micros());
+
+        let stats = Stats::new(&[Duration::from_millis(3)]);
+        assert_eq!(Duration::from_millis(3), stats.median);
+        assert_eq!(Duration::from_millis(3), stats.p95);
+        assert_eq!(Duration::default(), stats.stddev);
+    }
+
+    #[test]
+    fn test_bench() {
+        let registry = registry();
+        let config = BenchConfig{warmup:1, samples:5};
+
+        let result = bench(registry.get(1).unwrap(), "199\n200\n208\n210", Part::Part1, &[], config).unwrap();
+        assert_eq!((1, Part::Part1), (result.day, result.part));
+        assert!(result.parse.min <= result.parse.median && result.parse.median <= result.parse.p95);
+
+        assert!(bench(registry.get(1).unwrap(), "x", Part::Part1, &[], config).is_err());
+    }
+
+    #[test]
+    fn test_baseline() {
+        let mut baseline = Baseline::default();
+        baseline.update(&result(100, 2000));
+        assert_eq!("15 2 parse 100000\n15 2 solve 2000000\n", baseline.to_string());
+        assert_eq!(Ok(&baseline), Baseline::parse(&baseline.to_string()).as_ref());
+
+        let comparisons = baseline.compare(&result(105, 2500), 10.0);
+        assert_eq!(2, comparisons.len());
+        assert!(!comparisons[0].regression);
+        assert!(comparisons[1].regression);
+        assert_eq!(25.0, comparisons[1].change.round());
+
+        let mut other = result(100, 100);
+        other.part = Part::Part1;
+        assert!(baseline.compare(&other, 10.0).is_empty());
+
+        let error = Baseline::parse("15 2 parse 100\n15 x solve 3").unwrap_err();
+        assert_eq!(Some(2), error.line);
+    }
+}
@@ -1,18 +1,44 @@
-use super::Part;
+use super::error::{parse_field, Result};
+use super::params::NoParams;
+use super::solver::{Example, Solver};
 
-pub fn solve(input : String, part: Part) -> String {
+pub struct Day01;
 
-    let numbers:Vec<u32> = input.lines()
-        .map(|line| line.parse().unwrap())
-        .collect();
+impl Solver for Day01 {
+    type Input = Vec<u32>;
 
-    match part {
-        Part::Part1 => part1(numbers),
-        Part::Part2 => part2(numbers)
+    type Params = NoParams;
+
+    fn name(&self) -> &'static str {
+        "Sonar Sweep"
+    }
+
+    fn day(&self) -> u8 {
+        1
+    }
+
+    fn examples(&self) -> Vec<Example> {
+        vec![Example{input:EXAMPLE, part1:Some("7"), part2:Some("5")}]
+    }
+
+    fn parse(&self, input:&str) -> Result<Vec<u32>> {
+        input.lines()
+            .enumerate()
+            .map(|(i, line)| parse_field(i + 1, line, line))
+            .collect()
+    }
+
+    fn part1(&self, numbers:&Vec<u32>, _:&NoParams) -> Result<String> {
+        Ok(part1(numbers))
+    }
+
+    fn part2(&self, numbers:&Vec<u32>, _:&NoParams) -> Result<String> {
+        Ok(part2(numbers))
     }
 }
 
-fn part1(list:Vec<u32>) -> String {
+/// Number of measurements larger than the previous one.
+pub fn part1(list:&[u32]) -> String {
 
     list.iter().
         fold((u32::MAX,0), |(last, cnt), curr|
@@ -26,7 +52,8 @@ fn part1(list:Vec<u32>) -> String {
 }
 
 
-fn part2(list:Vec<u32>) -> String {
+/// Number of three-measurement sliding window sums larger than the previous one.
+pub fn part2(list:&[u32]) -> String {
     let mut cnt = 0;
 
     for i in 3..list.len() {
@@ -42,17 +69,7 @@ fn part2(list:Vec<u32>) -> String {
 }
 
 
-#[cfg(test)]
-mod tests {
-    // Note this useful idiom: importing names from outer (for mod tests) scope.
-    use super::*;
-    use util::Part::{Part1, Part2};
-
-
-    #[test]
-    fn test1() {
-
-        let input = "199
+const EXAMPLE:&str = "199
 200
 208
 210
@@ -63,38 +80,37 @@ mod tests {
 260
 263";
 
-        assert_eq!("7", solve(input.to_string(), Part1));
+
+#[cfg(test)]
+mod tests {
+    // Note this useful idiom: importing names from outer (for mod tests) scope.
+    use super::*;
+    use util::solver::Puzzle;
+    use util::Part::{Part1, Part2};
+
+
+    #[test]
+    fn test1() {
+        assert_eq!("7", Day01.solve(EXAMPLE, Part1).unwrap());
     }
 
     #[test]
     fn test_part1() {
         let input = include_str!("../../input_01.txt");
 
-        assert_eq!("1715", solve(input.to_string(), Part1));
+        assert_eq!("1715", Day01.solve(input, Part1).unwrap());
     }
 
     #[test]
     fn test2() {
-
-        let input = "199
-200
-208
-210
-200
-207
-240
-269
-260
-263";
-
-        assert_eq!("5", solve(input.to_string(), Part2));
+        assert_eq!("5", Day01.solve(EXAMPLE, Part2).unwrap());
     }
 
     #[test]
     fn test_part2() {
         let input = include_str!("../../input_01.txt");
 
-        assert_eq!("1739", solve(input.to_string(), Part2));
+        assert_eq!("1739", Day01.solve(input, Part2).unwrap());
     }
 
 }
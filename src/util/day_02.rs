@@ -1,91 +1,122 @@
-use super::Part;
+use super::error::{missing, parse_field, Result, SolveError};
+use super::params::NoParams;
+use super::solver::{Example, Solver};
 
-pub fn solve(input : String, part: Part) -> String {
+pub struct Day02;
 
-    let numbers:Vec<&str> = input.lines()
-        .collect();
+impl Solver for Day02 {
+    type Input = Vec<(i32, i32)>;
 
-    match part {
-        Part::Part1 => part1(numbers),
-        Part::Part2 => part2(numbers)
+    type Params = NoParams;
+
+    fn name(&self) -> &'static str {
+        "Dive!"
+    }
+
+    fn day(&self) -> u8 {
+        2
+    }
+
+    fn examples(&self) -> Vec<Example> {
+        vec![Example{input:EXAMPLE, part1:Some("150"), part2:Some("900")}]
+    }
+
+    fn parse(&self, input:&str) -> Result<Vec<(i32, i32)>> {
+        input.lines()
+            .enumerate()
+            .map(|(i, line)| map_to_pos(i + 1, line))
+            .collect()
+    }
+
+    fn part1(&self, moves:&Vec<(i32, i32)>, _:&NoParams) -> Result<String> {
+        Ok(part1(moves))
+    }
+
+    fn part2(&self, moves:&Vec<(i32, i32)>, _:&NoParams) -> Result<String> {
+        Ok(part2(moves))
     }
 }
 
-fn map_to_pos(line:&str) -> (i32, i32) {
+fn map_to_pos(line_no:usize, line:&str) -> Result<(i32, i32)> {
     let mut it = line.split(' ');
     let cmd = it.next().unwrap();
-    let value = it.next().unwrap().parse().unwrap();
+    let value = match it.next() {
+        Some(value) => parse_field(line_no, line, value)?,
+        None => return Err(missing(line_no, line, "distance")),
+    };
 
     match cmd {
-        "forward" => (value, 0),
-        "up" => (0, -value),
-        "down" => (0, value),
-        _ => panic!("invalid value"),
+        "forward" => Ok((value, 0)),
+        "up" => Ok((0, -value)),
+        "down" => Ok((0, value)),
+        _ => Err(SolveError::at(line_no, 1, &format!("invalid command '{}'", cmd))),
     }
 }
 
-fn part1(list:Vec<&str>) -> String {
-    let position = list.iter()
-        .map( |&line| map_to_pos(line))
-        .fold((0,0), |(x_acc,y_acc), (x,y)| (x_acc+x, y_acc+y));
+/// Product of the final horizontal position and depth.
+pub fn part1(moves:&[(i32,i32)]) -> String {
+    let position = moves.iter()
+        .fold((0,0), |(x_acc,y_acc), &(x,y)| (x_acc+x, y_acc+y));
 
     (position.0 * position.1).to_string()
 }
 
 
-fn part2(list:Vec<&str>) -> String {
-    let position = list.iter()
-        .map( |&line| map_to_pos(line))
-        .fold((0,0,0), |(x_acc,y_acc,aim_acc), (x,y)| (x_acc+x, y_acc+aim_acc*x,aim_acc + y));
+/// Product of the final position and depth when `down` and `up` change the aim.
+pub fn part2(moves:&[(i32,i32)]) -> String {
+    let position = moves.iter()
+        .fold((0,0,0), |(x_acc,y_acc,aim_acc), &(x,y)| (x_acc+x, y_acc+aim_acc*x,aim_acc + y));
 
     (position.0 * position.1).to_string()
 }
 
 
+const EXAMPLE:&str = "forward 5
+down 5
+forward 8
+up 3
+down 8
+forward 2";
+
+
 #[cfg(test)]
 mod tests {
     // Note this useful idiom: importing names from outer (for mod tests) scope.
     use super::*;
+    use util::solver::Puzzle;
     use util::Part::{Part1, Part2};
 
 
     #[test]
     fn test1() {
-
-        let input = "forward 5
-down 5
-forward 8
-up 3
-down 8
-forward 2";
-
-        assert_eq!("150", solve(input.to_string(), Part1));
+        assert_eq!("150", Day02.solve(EXAMPLE, Part1).unwrap());
     }
 
     #[test]
     fn test_part1() {
         let input = include_str!("../../input_02.txt");
 
-        assert_eq!("1383564", solve(input.to_string(), Part1));
+        assert_eq!("1383564", Day02.solve(input, Part1).unwrap());
     }
 
     #[test]
     fn test2() {
-
-        let input = "forward 5
-down 5
-forward 8
-up 3
-down 8
-forward 2";
-
-        assert_eq!("900", solve(input.to_string(), Part2));
+        assert_eq!("900", Day02.solve(EXAMPLE, Part2).unwrap());
     }
 
     #[test]
     fn test_part2() {
         let input = include_str!("../../input_02.txt");
 
-        assert_eq!("1488311643", solve(input.to_string(), Part2));
+        assert_eq!("1488311643", Day02.solve(input, Part2).unwrap());
+    }
+
+    #[test]
+    fn test_invalid_input() {
+        let error = Day02.solve("forward 5\ndown x3", Part1).unwrap_err();
+        assert_eq!("day 2, part 1, line 2, column 6: invalid value 'x3'", error.to_string());
+
+        let error = Day02.solve("sideways 5", Part2).unwrap_err();
+        assert_eq!((Some(1), Some(1)), (error.line, error.column));
     }
 }
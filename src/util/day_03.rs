@@ -1,14 +1,40 @@
 use std::cmp::Ordering;
-use super::Part;
+use super::error::{check_grid, Result, SolveError};
+use super::params::NoParams;
+use super::solver::{Example, Solver};
 
-pub fn solve(input : String, part: Part) -> String {
+pub struct Day03;
 
-    let lines:Vec<&str> = input.lines()
-        .collect();
+impl Solver for Day03 {
+    type Input = Vec<String>;
 
-    match part {
-        Part::Part1 => part1(lines),
-        Part::Part2 => part2(lines)
+    type Params = NoParams;
+
+    fn name(&self) -> &'static str {
+        "Binary Diagnostic"
+    }
+
+    fn day(&self) -> u8 {
+        3
+    }
+
+    fn examples(&self) -> Vec<Example> {
+        vec![Example{input:EXAMPLE, part1:Some("198"), part2:Some("230")}]
+    }
+
+    fn parse(&self, input:&str) -> Result<Vec<String>> {
+        let lines:Vec<&str> = input.lines().collect();
+        check_grid(&lines, |ch| ch == '0' || ch == '1')?;
+
+        Ok(lines.iter().map(|line| line.to_string()).collect())
+    }
+
+    fn part1(&self, lines:&Vec<String>, _:&NoParams) -> Result<String> {
+        Ok(part1(lines.iter().map(|line| line.as_str()).collect()))
+    }
+
+    fn part2(&self, lines:&Vec<String>, _:&NoParams) -> Result<String> {
+        part2(lines.iter().map(|line| line.as_str()).collect())
     }
 }
 
@@ -16,7 +42,8 @@ fn count_ones(lines:&[&str], pos:usize) -> usize {
     lines.iter().filter( |&line| *line.as_bytes().get(pos).unwrap() == 0x31 ).count()
 }
 
-fn part1(lines:Vec<&str>) -> String {
+/// Power consumption, gamma rate times epsilon rate.
+pub fn part1(lines:Vec<&str>) -> String {
     let width = lines.first().unwrap().len();
     let no_lines = lines.len();
 
@@ -54,7 +81,7 @@ fn filter_value(lines:&mut Vec<&str>, pos:usize, filter_ones:bool) {
     }
 }
 
-fn reduce(mut lines: Vec<&str>, filter_ones:bool) -> u32 {
+fn reduce(mut lines: Vec<&str>, filter_ones:bool) -> Result<u32> {
     let width = lines.first().unwrap().len();
     let mut i = 0;
 
@@ -71,28 +98,22 @@ fn reduce(mut lines: Vec<&str>, filter_ones:bool) -> u32 {
         i +=1;
     }
 
-    fold_to_value(lines.first().unwrap())
+    // Every line goes when all of them have the bit the filter drops
+    lines.first()
+        .map(|line| fold_to_value(line))
+        .ok_or_else(|| SolveError::new(&format!("no number left after filtering bit {}", i - 1)))
 }
 
-fn part2(lines:Vec<&str>) -> String {
-    let oxygen_gen_rating = reduce(lines.clone(), true);
-    let c02_scrubber_rating = reduce(lines, false);
+/// Life support rating, oxygen generator rating times CO2 scrubber rating.
+pub fn part2(lines:Vec<&str>) -> Result<String> {
+    let oxygen_gen_rating = reduce(lines.clone(), true)?;
+    let c02_scrubber_rating = reduce(lines, false)?;
 
-    (oxygen_gen_rating * c02_scrubber_rating).to_string()
+    Ok((oxygen_gen_rating * c02_scrubber_rating).to_string())
 }
 
 
-#[cfg(test)]
-mod tests {
-    // Note this useful idiom: importing names from outer (for mod tests) scope.
-    use super::*;
-    use util::Part::{Part1, Part2};
-
-
-    #[test]
-    fn test1() {
-
-        let input = "00100
+const EXAMPLE:&str = "00100
 11110
 10110
 10111
@@ -105,39 +126,43 @@ mod tests {
 00010
 01010";
 
-        assert_eq!("198", solve(input.to_string(), Part1));
+
+#[cfg(test)]
+mod tests {
+    // Note this useful idiom: importing names from outer (for mod tests) scope.
+    use super::*;
+    use util::solver::Puzzle;
+    use util::Part::{Part1, Part2};
+
+
+    #[test]
+    fn test1() {
+        assert_eq!("198", Day03.solve(EXAMPLE, Part1).unwrap());
     }
 
     #[test]
     fn test_part1() {
         let input = include_str!("../../input_03.txt");
 
-        assert_eq!("3009600", solve(input.to_string(), Part1));
+        assert_eq!("3009600", Day03.solve(input, Part1).unwrap());
     }
 
     #[test]
     fn test2() {
-
-        let input = "00100
-11110
-10110
-10111
-10101
-01111
-00111
-11100
-10000
-11001
-00010
-01010";
-        assert_eq!("230", solve(input.to_string(), Part2));
+        assert_eq!("230", Day03.solve(EXAMPLE, Part2).unwrap());
     }
 
     #[test]
     fn test_part2() {
         let input = include_str!("../../input_03.txt");
 
-        assert_eq!("6940518", solve(input.to_string(), Part2));
+        assert_eq!("6940518", Day03.solve(input, Part2).unwrap());
+    }
+
+    #[test]
+    fn test_filtered_out() {
+        let error = Day03.solve("10\n11", Part2).unwrap_err();
+        assert_eq!("no number left after filtering bit 0", error.message);
     }
 
 }
@@ -1,16 +1,43 @@
 use std::collections::{HashSet};
-use super::Part;
+use super::error::{parse_field, Result, SolveError};
+use super::params::NoParams;
+use super::solver::{Example, Solver};
 
-pub fn solve(input : String, part: Part) -> String {
+pub struct Day04;
 
-    match part {
-        Part::Part1 => part1(input.as_str()),
-        Part::Part2 => part2(input.as_str())
+impl Solver for Day04 {
+    type Input = (Vec<u32>, Vec<Board>);
+
+    type Params = NoParams;
+
+    fn name(&self) -> &'static str {
+        "Giant Squid"
+    }
+
+    fn day(&self) -> u8 {
+        4
+    }
+
+    fn examples(&self) -> Vec<Example> {
+        vec![Example{input:EXAMPLE, part1:Some("4512"), part2:Some("1924")}]
+    }
+
+    fn parse(&self, input:&str) -> Result<(Vec<u32>, Vec<Board>)> {
+        parse_game_context(input)
+    }
+
+    fn part1(&self, game:&(Vec<u32>, Vec<Board>), _:&NoParams) -> Result<String> {
+        part1(&game.0, game.1.clone())
+    }
+
+    fn part2(&self, game:&(Vec<u32>, Vec<Board>), _:&NoParams) -> Result<String> {
+        part2(&game.0, game.1.clone())
     }
 }
 
-#[derive(Debug)]
-struct Board {
+/// A 5x5 bingo board.
+#[derive(Debug, Clone)]
+pub struct Board {
     board_numbers:HashSet<u32>,
     rows_and_cols:Vec<HashSet<u32>>
 }
@@ -49,41 +76,61 @@ impl Board {
     }
 }
 
-fn parse_game_context(input:&str) -> (Vec<u32>, Vec<Board>) {
+/// Parses the drawn numbers followed by the 5x5 boards.
+pub fn parse_game_context(input:&str) -> Result<(Vec<u32>, Vec<Board>)> {
     let mut it = input.split("\n\n");
 
-    let numbers  = it.next().unwrap().split(',')
-        .map(|item| item.parse().unwrap())
-        .collect();
+    let first = it.next().unwrap();
+    let numbers = first.split(',')
+        .map(|item| parse_field(1, first, item))
+        .collect::<Result<Vec<u32>>>()?;
 
-    let boards = it.map( |item| Board::new(item.split(|c| c == '\n' || c == ' ')
-        .filter(|s| !s.is_empty())
-        .map(|s| s.parse().unwrap())
-        .collect())).collect();
+    // Keep track of line numbers for error reporting
+    let mut line_no = first.lines().count() + 2;
+    let mut boards = vec![];
 
-    (numbers, boards)
-}
+    for item in it.filter(|item| !item.trim().is_empty()) {
+        let mut board_numbers = vec![];
+        for (i, line) in item.lines().enumerate() {
+            for s in line.split(' ').filter(|s| !s.is_empty()) {
+                board_numbers.push(parse_field(line_no + i, line, s)?);
+            }
+        }
 
-fn part1(input:&str) -> String {
+        if board_numbers.len() != 25 {
+            let message = format!("expected 25 numbers on board, found {}", board_numbers.len());
+            return Err(SolveError::on_line(line_no, &message));
+        }
 
-    let (numbers, mut boards) = parse_game_context(input);
+        boards.push(Board::new(board_numbers));
+        line_no += item.lines().count() + 1;
+    }
+
+    if boards.is_empty() {
+        return Err(SolveError::new("no boards in input"));
+    }
+
+    Ok((numbers, boards))
+}
+
+/// Score of the first board to win.
+pub fn part1(numbers:&[u32], mut boards:Vec<Board>) -> Result<String> {
 
     for number in numbers.iter() {
         // Draw number
         boards.iter_mut().for_each(|b| {b.draw_number(*number)});
 
         if let Some(winning_board) = boards.iter().find(|board| board.has_bingo()) {
-            return (winning_board.get_remaining_sum() * *number).to_string()
+            return Ok((winning_board.get_remaining_sum() * *number).to_string())
         }
     }
 
-    panic!("No solution")
+    Err(SolveError::new("no board wins"))
 }
 
 
-fn part2(input:&str) -> String {
-
-    let (numbers, mut boards) = parse_game_context(input);
+/// Score of the last board to win.
+pub fn part2(numbers:&[u32], mut boards:Vec<Board>) -> Result<String> {
 
     for number in numbers.iter() {
         // Draw number
@@ -103,29 +150,22 @@ fn part2(input:&str) -> String {
             }
         } else {
             // Only one board remaining, play it until it has bingo...
-            let remaining_board = boards.first().unwrap();
+            let remaining_board = match boards.first() {
+                Some(board) => board,
+                None => break,
+            };
 
             if remaining_board.has_bingo() {
-                return (remaining_board.get_remaining_sum() * *number).to_string();
+                return Ok((remaining_board.get_remaining_sum() * *number).to_string());
             }
         }
     }
 
-    panic!("No solution")
+    Err(SolveError::new("last board never wins"))
 }
 
 
-#[cfg(test)]
-mod tests {
-    // Note this useful idiom: importing names from outer (for mod tests) scope.
-    use super::*;
-    use util::Part::{Part1, Part2};
-
-
-    #[test]
-    fn test1() {
-
-        let input = "7,4,9,5,11,17,23,2,0,14,21,24,10,16,13,6,15,25,12,22,18,20,8,19,3,26,1
+const EXAMPLE:&str = "7,4,9,5,11,17,23,2,0,14,21,24,10,16,13,6,15,25,12,22,18,20,8,19,3,26,1
 
 22 13 17 11  0
  8  2 23  4 24
@@ -145,46 +185,37 @@ mod tests {
 22 11 13  6  5
  2  0 12  3  7";
 
-        assert_eq!("4512", solve(input.to_string(), Part1));
+
+#[cfg(test)]
+mod tests {
+    // Note this useful idiom: importing names from outer (for mod tests) scope.
+    use super::*;
+    use util::solver::Puzzle;
+    use util::Part::{Part1, Part2};
+
+
+    #[test]
+    fn test1() {
+        assert_eq!("4512", Day04.solve(EXAMPLE, Part1).unwrap());
     }
 
     #[test]
     fn test_part1() {
         let input = include_str!("../../input_04.txt");
 
-        assert_eq!("33348", solve(input.to_string(), Part1));
+        assert_eq!("33348", Day04.solve(input, Part1).unwrap());
     }
 
     #[test]
     fn test2() {
-
-        let input = "7,4,9,5,11,17,23,2,0,14,21,24,10,16,13,6,15,25,12,22,18,20,8,19,3,26,1
-
-22 13 17 11  0
- 8  2 23  4 24
-21  9 14 16  7
- 6 10  3 18  5
- 1 12 20 15 19
-
- 3 15  0  2 22
- 9 18 13 17  5
-19  8  7 25 23
-20 11 10 24  4
-14 21 16 12  6
-
-14 21 17 24  4
-10 16 15  9 19
-18  8 23 26 20
-22 11 13  6  5
- 2  0 12  3  7";
-        assert_eq!("1924", solve(input.to_string(), Part2));
+        assert_eq!("1924", Day04.solve(EXAMPLE, Part2).unwrap());
     }
 
     #[test]
     fn test_part2() {
         let input = include_str!("../../input_04.txt");
 
-        assert_eq!("8112", solve(input.to_string(), Part2));
+        assert_eq!("8112", Day04.solve(input, Part2).unwrap());
     }
 
 }
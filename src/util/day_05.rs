@@ -1,37 +1,86 @@
 use std::cmp::Ordering;
-use super::Part;
+use super::error::{parse_field, Result, SolveError};
+use super::params::NoParams;
+use super::solver::{Example, Solver};
 
-pub fn solve(input : String, part: Part) -> String {
+pub struct Day05;
 
-    let lines:Vec<&str> = input.lines()
-        .collect();
+impl Solver for Day05 {
+    type Input = Vec<[i32; 4]>;
 
-    match part {
-        Part::Part1 => part1(lines),
-        Part::Part2 => part2(lines)
+    type Params = NoParams;
+
+    fn name(&self) -> &'static str {
+        "Hydrothermal Venture"
+    }
+
+    fn day(&self) -> u8 {
+        5
+    }
+
+    fn examples(&self) -> Vec<Example> {
+        vec![Example{input:EXAMPLE, part1:Some("5"), part2:Some("12")}]
+    }
+
+    fn parse(&self, input:&str) -> Result<Vec<[i32; 4]>> {
+        let lines = input.lines()
+            .enumerate()
+            .map(|(i, line)| parse_line(i + 1, line))
+            .collect::<Result<Vec<[i32; 4]>>>()?;
+
+        if lines.is_empty() {
+            return Err(SolveError::new("empty input"));
+        }
+
+        Ok(lines)
+    }
+
+    fn part1(&self, lines:&Vec<[i32; 4]>, _:&NoParams) -> Result<String> {
+        Ok(calculate_grid(lines, false).to_string())
+    }
+
+    fn part2(&self, lines:&Vec<[i32; 4]>, _:&NoParams) -> Result<String> {
+        Ok(calculate_grid(lines, true).to_string())
     }
 }
 
-fn calculate_grid(lines:Vec<&str>, diagonal:bool) -> usize {
+/// Largest coordinate, the grid has a point for every position up to it.
+const MAX_COORDINATE:i32 = 4999;
 
-    let coordinates:Vec<i32> = lines.iter()
-        .map(|line| line.split(|c:char | !c.is_ascii_digit()))
-        .flatten()
+/// Horizontal, vertical or diagonal line from `x1,y1` to `x2,y2`.
+fn parse_line(line_no:usize, line:&str) -> Result<[i32; 4]> {
+    if let Some(pos) = line.find(|c:char| !c.is_ascii_digit() && !", ->".contains(c)) {
+        return Err(SolveError::at(line_no, pos + 1, "unexpected character"));
+    }
+
+    let coordinates = line.split(|c:char | !c.is_ascii_digit())
         .filter(|s| !s.is_empty())
-        .map(|s| s.parse::<i32>().unwrap())
-        .collect();
+        .map(|s| parse_field(line_no, line, s))
+        .collect::<Result<Vec<i32>>>()?;
+
+    if coordinates.len() != 4 {
+        return Err(SolveError::on_line(line_no, "expected 'x1,y1 -> x2,y2'"));
+    }
+    if let Some(coordinate) = coordinates.iter().find(|&&coordinate| coordinate > MAX_COORDINATE) {
+        return Err(SolveError::on_line(line_no, &format!("coordinate {} is larger than {}", coordinate, MAX_COORDINATE)));
+    }
+
+    let [x1, y1, x2, y2] = [coordinates[0], coordinates[1], coordinates[2], coordinates[3]];
+    if x1 != x2 && y1 != y2 && (x2 - x1).abs() != (y2 - y1).abs() {
+        return Err(SolveError::on_line(line_no, "line is not horizontal, vertical or at 45 degrees"));
+    }
+    Ok([x1, y1, x2, y2])
+}
 
-    let size = *coordinates.iter().max().unwrap() as usize + 1;
+/// Number of points where at least two vent lines overlap, diagonal lines are
+/// only included when `diagonal` is set.
+pub fn calculate_grid(lines:&[[i32; 4]], diagonal:bool) -> usize {
 
-    let mut grid = Vec::new();
-    (0..size*size).into_iter().for_each(|_| grid.push(0));
+    let size = *lines.iter().flatten().max().unwrap() as usize + 1;
 
-    for chunk in coordinates.chunks(4) {
+    let mut grid = vec![0; size*size];
 
-        let x1 = chunk[0];
-        let y1 = chunk[1];
-        let x2 = chunk[2];
-        let y2 = chunk[3];
+    for &[x1, y1, x2, y2] in lines {
 
         // Check if diagonal
         if (x1 != x2 && y1 != y2) && !diagonal {
@@ -64,66 +113,59 @@ fn calculate_grid(lines:Vec<&str>, diagonal:bool) -> usize {
     grid.iter().filter(|&point| *point > 1 ).count()
 }
 
-fn part1(lines:Vec<&str>) -> String {
-    calculate_grid(lines, false).to_string()
-}
 
-fn part2(lines:Vec<&str>) -> String {
-    calculate_grid(lines, true).to_string()
-}
+const EXAMPLE:&str = "0,9 -> 5,9
+8,0 -> 0,8
+9,4 -> 3,4
+2,2 -> 2,1
+7,0 -> 7,4
+6,4 -> 2,0
+0,9 -> 2,9
+3,4 -> 1,4
+0,0 -> 8,8
+5,5 -> 8,2";
 
 
 #[cfg(test)]
 mod tests {
     // Note this useful idiom: importing names from outer (for mod tests) scope.
     use super::*;
+    use util::solver::Puzzle;
     use util::Part::{Part1, Part2};
 
 
     #[test]
     fn test1() {
-
-        let input = "0,9 -> 5,9
-8,0 -> 0,8
-9,4 -> 3,4
-2,2 -> 2,1
-7,0 -> 7,4
-6,4 -> 2,0
-0,9 -> 2,9
-3,4 -> 1,4
-0,0 -> 8,8
-5,5 -> 8,2";
-
-        assert_eq!("5", solve(input.to_string(), Part1));
+        assert_eq!("5", Day05.solve(EXAMPLE, Part1).unwrap());
     }
 
     #[test]
     fn test_part1() {
         let input = include_str!("../../input_05.txt");
 
-        assert_eq!("6710", solve(input.to_string(), Part1));
+        assert_eq!("6710", Day05.solve(input, Part1).unwrap());
     }
 
     #[test]
     fn test2() {
+        assert_eq!("12", Day05.solve(EXAMPLE, Part2).unwrap());
+    }
 
-        let input = "0,9 -> 5,9
-8,0 -> 0,8
-9,4 -> 3,4
-2,2 -> 2,1
-7,0 -> 7,4
-6,4 -> 2,0
-0,9 -> 2,9
-3,4 -> 1,4
-0,0 -> 8,8
-5,5 -> 8,2";
-        assert_eq!("12", solve(input.to_string(), Part2));
+    #[test]
+    fn test_parse() {
+        let error = Day05.parse("0,9 -> 5,9\n0,9 -> 5,3").unwrap_err();
+        assert_eq!((Some(2), "line is not horizontal, vertical or at 45 degrees".to_string()), (error.line, error.message));
+
+        let error = Day05.parse("0,0 -> 1000000000,1000000000").unwrap_err();
+        assert_eq!((Some(1), "coordinate 1000000000 is larger than 4999".to_string()), (error.line, error.message));
+        assert!(Day05.parse("0,0 -> 99999999999,0").is_err());
+        assert!(Day05.parse("0,4999 -> 4999,0").is_ok());
     }
 
     #[test]
     fn test_part2() {
         let input = include_str!("../../input_05.txt");
 
-        assert_eq!("20121", solve(input.to_string(), Part2));
+        assert_eq!("20121", Day05.solve(input, Part2).unwrap());
     }
 }
@@ -1,78 +1,140 @@
-use std::collections::HashMap;
-use super::Part;
+use std::collections::VecDeque;
+use super::error::{parse_field, Result, SolveError};
+use super::params::{parse_value, per_part, unknown, Param, Params};
+use super::solver::{Example, Solver};
 
-pub fn solve(input : String, part: Part) -> String {
-    let numbers = input.split(',')
-        .map(|s|s.parse().unwrap())
-        .collect();
+pub struct Day06;
 
-    match part {
-        Part::Part1 => part1(numbers),
-        Part::Part2 => part2(numbers)
+impl Solver for Day06 {
+    type Input = Vec<u32>;
+
+    type Params = Day06Params;
+
+    fn name(&self) -> &'static str {
+        "Lanternfish"
+    }
+
+    fn day(&self) -> u8 {
+        6
+    }
+
+    fn examples(&self) -> Vec<Example> {
+        vec![Example{input:EXAMPLE, part1:Some("5934"), part2:Some("26984457539")}]
+    }
+
+    fn parse(&self, input:&str) -> Result<Vec<u32>> {
+        let line = input.trim();
+        line.split(',')
+            .map(|s| parse_field(1, line, s))
+            .collect()
     }
-}
 
+    fn part1(&self, numbers:&Vec<u32>, params:&Day06Params) -> Result<String> {
+        calculate_lanterns(numbers, params.days.unwrap_or(80))
+    }
 
-fn calc_lanterns(age:u32, turns:u32, results: &mut HashMap<(u32, u32), i64>) -> i64 {
-    if turns == 0 || turns <= age {
-        1
-    } else if results.contains_key(&(age, turns)) {
-        return *results.get(&(age,turns)).unwrap();
-    } else {
-        let next_turn = turns - age - 1;
-        let count = calc_lanterns(6, next_turn, results) + calc_lanterns(8, next_turn, results);
-        results.insert((age, turns), count);
-        count
+    fn part2(&self, numbers:&Vec<u32>, params:&Day06Params) -> Result<String> {
+        calculate_lanterns(numbers, params.days.unwrap_or(256))
     }
 }
 
-fn calculate_lanterns(numbers:Vec<u32>, turns:u32) -> String {
-    let mut result_map= HashMap::new();
-    numbers.iter()
-        .map( |n| calc_lanterns(*n, turns, &mut result_map))
-        .sum::<i64>()
-        .to_string()
+#[derive(Debug, Clone, Default)]
+pub struct Day06Params {
+    /// Days to simulate, 80 for part 1 and 256 for part 2 when not set.
+    pub days:Option<u32>,
 }
 
-fn part1(numbers:Vec<u32>) -> String {
-    calculate_lanterns(numbers, 80)
+impl Params for Day06Params {
+    fn list(&self) -> Vec<Param> {
+        vec![Param{name:"days", value:per_part(&self.days, 80, 256), description:"days to simulate"}]
+    }
+
+    fn set(&mut self, name:&str, value:&str) -> Result<()> {
+        match name {
+            "days" => self.days = Some(parse_value(name, value)?),
+            _ => return Err(unknown(name, self)),
+        }
+        Ok(())
+    }
 }
 
-fn part2(numbers:Vec<u32>) -> String {
-    calculate_lanterns(numbers, 256)
+
+/// Number of lanternfish after `turns` days, an error once that no longer
+/// fits in 128 bits.
+pub fn calculate_lanterns(numbers:&[u32], turns:u32) -> Result<String> {
+    let too_many = |day:u32| SolveError::new(&format!("number of lanternfish overflows on day {}", day));
+
+    // Number of fish for each timer value
+    let mut timers:VecDeque<u128> = VecDeque::new();
+    for &age in numbers {
+        let age = age as usize;
+        if timers.len() <= age {
+            timers.resize(age + 1, 0);
+        }
+        timers[age] += 1;
+    }
+
+    for day in 1..=turns {
+        let spawning = timers.pop_front().unwrap_or(0);
+        if timers.len() < 9 {
+            timers.resize(9, 0);
+        }
+        timers[6] = timers[6].checked_add(spawning).ok_or_else(|| too_many(day))?;
+        timers[8] = timers[8].checked_add(spawning).ok_or_else(|| too_many(day))?;
+    }
+
+    timers.iter()
+        .try_fold(0u128, |sum, &count| sum.checked_add(count))
+        .map(|sum| sum.to_string())
+        .ok_or_else(|| too_many(turns))
 }
 
 
+const EXAMPLE:&str = "3,4,3,1,2";
+
+
 #[cfg(test)]
 mod tests {
     // Note this useful idiom: importing names from outer (for mod tests) scope.
     use super::*;
+    use util::solver::Puzzle;
     use util::Part::{Part1, Part2};
 
 
     #[test]
     fn test1() {
-        let input = "3,4,3,1,2";
-        assert_eq!("5934", solve(input.to_string(), Part1));
+        assert_eq!("5934", Day06.solve(EXAMPLE, Part1).unwrap());
     }
 
 
     #[test]
     fn test_part1() {
         let input = include_str!("../../input_06.txt");
-        assert_eq!("360761", solve(input.to_string(), Part1));
+        assert_eq!("360761", Day06.solve(input, Part1).unwrap());
+    }
+
+    #[test]
+    fn test_params() {
+        let days = [("days".to_string(), "18".to_string())];
+        assert_eq!("26", Day06.solve_with(EXAMPLE, Part1, &days).unwrap());
+        assert_eq!("26", Day06.solve_with(EXAMPLE, Part2, &days).unwrap());
+        assert!(Day06.solve_with(EXAMPLE, Part1, &[("steps".to_string(), "18".to_string())]).is_err());
+
+        assert_eq!("5", Day06.solve_with(EXAMPLE, Part1, &[("days".to_string(), "0".to_string())]).unwrap());
+        assert_eq!("2", Day06.solve_with("9", Part1, &[("days".to_string(), "10".to_string())]).unwrap());
+        let error = Day06.solve_with(EXAMPLE, Part1, &[("days".to_string(), "1000000".to_string())]).unwrap_err();
+        assert!(error.message.starts_with("number of lanternfish overflows"));
     }
 
     #[test]
     fn test2() {
-        let input = "3,4,3,1,2";
-        assert_eq!("26984457539", solve(input.to_string(), Part2));
+        assert_eq!("26984457539", Day06.solve(EXAMPLE, Part2).unwrap());
     }
 
     #[test]
     fn test_part2() {
         let input = include_str!("../../input_06.txt");
-        assert_eq!("1632779838045", solve(input.to_string(), Part2));
+        assert_eq!("1632779838045", Day06.solve(input, Part2).unwrap());
     }
 
 }
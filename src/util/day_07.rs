@@ -1,30 +1,53 @@
 use std::cmp::{max, min};
-use super::Part;
+use super::error::{parse_field, Result};
+use super::params::NoParams;
+use super::solver::{Example, Solver};
 
-pub fn solve(input : String, part: Part) -> String {
+pub struct Day07;
 
-    let lines:Vec<usize> = input.split(',')
-        .map(|s|s.parse().unwrap())
-        .collect();
+impl Solver for Day07 {
+    type Input = Vec<usize>;
 
-    match part {
-        Part::Part1 => part1(lines),
-        Part::Part2 => part2(lines)
+    type Params = NoParams;
+
+    fn name(&self) -> &'static str {
+        "The Treachery of Whales"
+    }
+
+    fn day(&self) -> u8 {
+        7
+    }
+
+    fn examples(&self) -> Vec<Example> {
+        vec![Example{input:EXAMPLE, part1:Some("37"), part2:Some("168")}]
+    }
+
+    fn parse(&self, input:&str) -> Result<Vec<usize>> {
+        let line = input.trim();
+        line.split(',')
+            .map(|s| parse_field(1, line, s))
+            .collect()
+    }
+
+    fn part1(&self, numbers:&Vec<usize>, _:&NoParams) -> Result<String> {
+        Ok(part1(numbers))
+    }
+
+    fn part2(&self, numbers:&Vec<usize>, _:&NoParams) -> Result<String> {
+        Ok(part2(numbers))
     }
 }
 
 
 fn calc_pos(numbers:&[usize]) -> Vec<usize> {
     let max = numbers.iter().max().copied().unwrap();
-    let mut deltas = Vec::with_capacity(max as usize);
-    (0..=max).into_iter().for_each(|_| deltas.push(0));
-    numbers.iter().for_each(|num| *deltas.get_mut(*num as usize).unwrap() += 1);
+    let mut deltas = vec![0; max + 1];
+    numbers.iter().for_each(|num| *deltas.get_mut(*num).unwrap() += 1);
     deltas
 }
 
 fn calc(positions:&[usize],  pos: usize, part_two:bool) -> usize {
     let mut fuel= 0;
-    let mut index = pos;
 
     for i in 0..positions.len() {
         let dist = max(pos,i) - min(pos, i);
@@ -35,16 +58,15 @@ fn calc(positions:&[usize],  pos: usize, part_two:bool) -> usize {
             let fuel_cost = (dist * (dist + 1)) / 2;
             fuel += fuel_cost * positions.get(i).unwrap();
         }
-        index = (index + 1) % positions.len();
     }
     fuel
 }
 
 
-fn part1(numbers:Vec<usize>) -> String {
-    let deltas = calc_pos(&numbers);
-    let min_fuel= (0..deltas.len()).
-        into_iter()
+/// Fuel needed to align all crabs when each step costs one fuel.
+pub fn part1(numbers:&[usize]) -> String {
+    let deltas = calc_pos(numbers);
+    let min_fuel= (0..deltas.len())
         .map(|pos | calc(&deltas, pos, false))
         .min().unwrap();
 
@@ -52,45 +74,49 @@ fn part1(numbers:Vec<usize>) -> String {
 }
 
 
-fn part2(numbers:Vec<usize>) -> String {
-    let deltas = calc_pos(&numbers);
-    let min_fuel= (0..deltas.len()).into_iter()
+/// Fuel needed to align all crabs when each step costs one more than the last.
+pub fn part2(numbers:&[usize]) -> String {
+    let deltas = calc_pos(numbers);
+    let min_fuel= (0..deltas.len())
         .map(|pos | calc(&deltas, pos, true))
         .min()
         .unwrap();
 
-    min_fuel.to_string()}
+    min_fuel.to_string()
+}
+
+
+const EXAMPLE:&str = "16,1,2,0,4,2,7,1,2,14";
 
 
 #[cfg(test)]
 mod tests {
     // Note this useful idiom: importing names from outer (for mod tests) scope.
     use super::*;
+    use util::solver::Puzzle;
     use util::Part::{Part1, Part2};
 
 
     #[test]
     fn test1() {
-        let input = "16,1,2,0,4,2,7,1,2,14";
-        assert_eq!("37", solve(input.to_string(), Part1));
+        assert_eq!("37", Day07.solve(EXAMPLE, Part1).unwrap());
     }
 
     #[test]
     fn test_part1() {
         let input = include_str!("../../input_07.txt");
-        assert_eq!("356958", solve(input.to_string(), Part1));
+        assert_eq!("356958", Day07.solve(input, Part1).unwrap());
     }
 
     #[test]
     fn test2() {
-        let input = "16,1,2,0,4,2,7,1,2,14";
-        assert_eq!("168", solve(input.to_string(), Part2));
+        assert_eq!("168", Day07.solve(EXAMPLE, Part2).unwrap());
     }
 
     #[test]
     fn test_part2() {
         let input = include_str!("../../input_07.txt");
-        assert_eq!("105461913", solve(input.to_string(), Part2));
+        assert_eq!("105461913", Day07.solve(input, Part2).unwrap());
     }
 
 }
@@ -1,19 +1,69 @@
-use super::Part;
+use super::error::{column, Result, SolveError};
+use super::params::NoParams;
+use super::solver::{Example, Solver};
 
+pub struct Day08;
 
-pub fn solve(input : String, part: Part) -> String {
+impl Solver for Day08 {
+    type Input = Vec<Entry>;
 
-    let lines:Vec<&str> = input.lines()
-        .collect();
+    type Params = NoParams;
+
+    fn name(&self) -> &'static str {
+        "Seven Segment Search"
+    }
+
+    fn day(&self) -> u8 {
+        8
+    }
+
+    fn examples(&self) -> Vec<Example> {
+        vec![Example{input:EXAMPLE, part1:Some("26"), part2:Some("61229")}]
+    }
+
+    fn parse(&self, input:&str) -> Result<Vec<Entry>> {
+        let lines:Vec<&str> = input.lines().collect();
+        let mut entries = vec![];
+        let mut i = 0;
+
+        while i < lines.len() {
+            let line = lines[i];
+            let (signals, mut output) = match line.split_once('|') {
+                Some(fields) => fields,
+                None => return Err(SolveError::on_line(i + 1, "missing '|' delimiter")),
+            };
+            let signals = parse_patterns(i + 1, line, signals, 10)?;
+
+            // Entries may be wrapped after the delimiter
+            let mut output_line = line;
+            if output.trim().is_empty() && i + 1 < lines.len() {
+                i += 1;
+                output = lines[i];
+                output_line = lines[i];
+            }
+            let output = parse_patterns(i + 1, output_line, output, 4)?;
+
+            entries.push((signals, output));
+            i += 1;
+        }
+
+        Ok(entries)
+    }
 
-    match part {
-        Part::Part1 => part1(lines),
-        Part::Part2 => part2(lines)
+    fn part1(&self, entries:&Vec<Entry>, _:&NoParams) -> Result<String> {
+        Ok(part1(entries))
+    }
+
+    fn part2(&self, entries:&Vec<Entry>, _:&NoParams) -> Result<String> {
+        part2(entries)
     }
 }
 
+/// Unique signal patterns and output value digits of one display.
+pub type Entry = (Vec<Vec<char>>, Vec<Vec<char>>);
+
 fn translate_digit(digit:&[char], translation:&[char]) -> Vec<char> {
-    let chars = vec!['a','b','c','d','e','f','g'];
+    let chars = ['a','b','c','d','e','f','g'];
     let mut result = vec![];
     for ch in digit {
         let pos = translation.iter().enumerate().find(|&(_,c)| *c == *ch).unwrap().0;
@@ -25,21 +75,21 @@ fn translate_digit(digit:&[char], translation:&[char]) -> Vec<char> {
 
 }
 
-fn from_lcd_digit(digit:&[char], translation:&[char]) -> u32 {
+fn from_lcd_digit(digit:&[char], translation:&[char]) -> Option<u32> {
     let digit:String = translate_digit(digit, translation).iter().copied().collect();
 
     match digit.as_str() {
-        "abcefg" => 0,
-        "cf" => 1,
-        "acdeg" => 2,
-        "acdfg" => 3,
-        "bcdf" => 4,
-        "abdfg" => 5,
-        "abdefg" => 6,
-        "acf" => 7,
-        "abcdefg" => 8,
-        "abcdfg" => 9,
-        _ => panic!(),
+        "abcefg" => Some(0),
+        "cf" => Some(1),
+        "acdeg" => Some(2),
+        "acdfg" => Some(3),
+        "bcdf" => Some(4),
+        "abdfg" => Some(5),
+        "abdefg" => Some(6),
+        "acf" => Some(7),
+        "abcdefg" => Some(8),
+        "abcdfg" => Some(9),
+        _ => None,
     }
 }
 
@@ -59,7 +109,7 @@ fn get_lcd_digit(digit:u32, translation:&[char]) -> Vec<char> {
         _ => panic!(".."),
     };
 
-    let indexes:Vec<usize> = digit.chars().map(|ch| (ch as usize - 'a' as usize) as usize).collect();
+    let indexes:Vec<usize> = digit.chars().map(|ch| ch as usize - 'a' as usize).collect();
     let mut translated_digit:Vec<char> = indexes.iter()
         .map( |index| translation.get( *index).unwrap())
         .copied()
@@ -80,43 +130,42 @@ fn generate_permutation(translation:&[char]) -> Vec<Vec<char>>{
 
 
 
-fn parse(line:&str) -> (Vec<Vec<char>>, Vec<Vec<char>>) {
-    let mut it  = line.split('|');
-    let signals = it.next().unwrap().split(' ')
+fn parse_patterns(line_no:usize, line:&str, field:&str, expected:usize) -> Result<Vec<Vec<char>>> {
+    let patterns:Vec<&str> = field.split(' ')
         .filter(|s| !s.is_empty())
-        .map(|s| {
-            let mut sorted:Vec<char> = s.chars().collect();
-            sorted.sort_unstable();
-            sorted
-        })
         .collect();
 
-    let read_numbers = it.next().unwrap().split(' ')
-        .filter(|s| !s.is_empty())
+    if patterns.len() != expected {
+        let message = format!("expected {} patterns, found {}", expected, patterns.len());
+        return Err(SolveError::at(line_no, column(line, field), &message));
+    }
+
+    patterns.iter()
         .map(|s| {
+            if let Some(pos) = s.find(|ch| !('a'..='g').contains(&ch)) {
+                return Err(SolveError::at(line_no, column(line, s) + pos, "invalid segment"));
+            }
             let mut sorted:Vec<char> = s.chars().collect();
             sorted.sort_unstable();
-            sorted
+            Ok(sorted)
         })
-        .collect();
-
-    (signals, read_numbers)
+        .collect()
 }
 
-fn part1(lines:Vec<&str>) -> String {
+/// Number of times 1, 4, 7 or 8 appear in the output values.
+pub fn part1(entries:&[Entry]) -> String {
 
-    let count = lines.iter()
-        .map(|s| s.split('|').last().unwrap())
-        .map(|s| s.split(' ')).flatten()
-        .filter(|s| !s.is_empty())
-        .filter(|s| vec![2,3,4,7].contains(&(s.len() as i32)))
+    let count = entries.iter()
+        .flat_map(|(_, output)| output.iter())
+        .filter(|digit| [2,3,4,7].contains(&digit.len()))
         .count();
 
     count.to_string()
 }
 
 
-fn part2(lines:Vec<&str>) -> String {
+/// Sum of all decoded output values.
+pub fn part2(entries:&[Entry]) -> Result<String> {
     let chars:Vec<char> = "abcdefg".chars().collect();
     let mut sum = 0;
     let mut permutation_context = vec![];
@@ -126,30 +175,50 @@ fn part2(lines:Vec<&str>) -> String {
         permutation_context.push((permutation, lcd_digits));
     }
 
-    for line in lines {
-        let (signal_pattern, output) = parse(line);
+    for (index, (signal_pattern, output)) in entries.iter().enumerate() {
+        let mut found = false;
         for permutation in permutation_context.iter() {
             let (translation, lcd_digits) = permutation;
             if signal_pattern.iter().all(|signal| lcd_digits.contains(signal)) {
                 // Found permutation..
-                let res: u32 = output.iter()
-                    .map(|digit| from_lcd_digit( digit, &translation))
-                    .fold(0, |acc, next| acc * 10 + next);
-
-                sum += res;
+                let res: Option<u32> = output.iter()
+                    .map(|digit| from_lcd_digit( digit, translation))
+                    .try_fold(0, |acc, next| next.map(|next| acc * 10 + next));
+
+                if let Some(res) = res {
+                    sum += res;
+                    found = true;
+                }
             }
         }
+
+        if !found {
+            return Err(SolveError::new(&format!("no wiring matches entry {}", index + 1)));
+        }
     }
 
 
-    sum.to_string()
+    Ok(sum.to_string())
 }
 
 
+const EXAMPLE:&str = "be cfbegad cbdgef fgaecd cgeb fdcge agebfd fecdb fabcd edb | fdgacbe cefdb cefbgd gcbe
+edbfga begcd cbg gc gcadebf fbgde acbgfd abcde gfcbed gfec | fcgedb cgb dgebacf gc
+fgaebd cg bdaec gdafb agbcfd gdcbef bgcad gfac gcb cdgabef | cg cg fdcagb cbg
+fbegcd cbd adcefb dageb afcb bc aefdc ecdab fgdeca fcdbega | efabcd cedba gadfec cb
+aecbfdg fbg gf bafeg dbefa fcge gcbea fcaegb dgceab fcbdga | gecf egdcabf bgf bfgea
+fgeab ca afcebg bdacfeg cfaedg gcfdb baec bfadeg bafgc acf | gebdcfa ecba ca fadegcb
+dbcfg fgd bdegcaf fgec aegbdf ecdfab fbedc dacgb gdcebf gf | cefg dcbef fcge gbcadfe
+bdfegc cbegaf gecbf dfcage bdacg ed bedf ced adcbefg gebcd | ed bcgafe cdgba cbgef
+egadfb cdbfeg cegd fecab cgb gbdefca cg fgcdab egfdb bfceg | gbdfcae bgc cg cgb
+gcafb gcf dcaebfg ecagb gf abcdeg gaef cafbge fdbac fegbdc | fgae cfgab fg bagce";
+
+
 #[cfg(test)]
 mod tests {
     // Note this useful idiom: importing names from outer (for mod tests) scope.
     use super::*;
+    use util::solver::Puzzle;
     use util::Part::{Part1, Part2};
 
 
@@ -177,37 +246,26 @@ gbdfcae bgc cg cgb
 gcafb gcf dcaebfg ecagb gf abcdeg gaef cafbge fdbac fegbdc |
 fgae cfgab fg bagce";
 
-        assert_eq!("26", solve(input.to_string(), Part1));
+        assert_eq!("26", Day08.solve(input, Part1).unwrap());
     }
 
     #[test]
     fn test_part1() {
         let input = include_str!("../../input_08.txt");
 
-        assert_eq!("355", solve(input.to_string(), Part1));
+        assert_eq!("355", Day08.solve(input, Part1).unwrap());
     }
 
     #[test]
     fn test2() {
-
-        let input = "be cfbegad cbdgef fgaecd cgeb fdcge agebfd fecdb fabcd edb | fdgacbe cefdb cefbgd gcbe
-edbfga begcd cbg gc gcadebf fbgde acbgfd abcde gfcbed gfec | fcgedb cgb dgebacf gc
-fgaebd cg bdaec gdafb agbcfd gdcbef bgcad gfac gcb cdgabef | cg cg fdcagb cbg
-fbegcd cbd adcefb dageb afcb bc aefdc ecdab fgdeca fcdbega | efabcd cedba gadfec cb
-aecbfdg fbg gf bafeg dbefa fcge gcbea fcaegb dgceab fcbdga | gecf egdcabf bgf bfgea
-fgeab ca afcebg bdacfeg cfaedg gcfdb baec bfadeg bafgc acf | gebdcfa ecba ca fadegcb
-dbcfg fgd bdegcaf fgec aegbdf ecdfab fbedc dacgb gdcebf gf | cefg dcbef fcge gbcadfe
-bdfegc cbegaf gecbf dfcage bdacg ed bedf ced adcbefg gebcd | ed bcgafe cdgba cbgef
-egadfb cdbfeg cegd fecab cgb gbdefca cg fgcdab egfdb bfceg | gbdfcae bgc cg cgb
-gcafb gcf dcaebfg ecagb gf abcdeg gaef cafbge fdbac fegbdc | fgae cfgab fg bagce";
-        assert_eq!("61229", solve(input.to_string(), Part2));
+        assert_eq!("61229", Day08.solve(EXAMPLE, Part2).unwrap());
     }
 
     #[test]
     fn test_part2() {
         let input = include_str!("../../input_08.txt");
 
-        assert_eq!("983030", solve(input.to_string(), Part2));
+        assert_eq!("983030", Day08.solve(input, Part2).unwrap());
     }
 
 }
@@ -1,14 +1,40 @@
 use std::collections::HashSet;
-use super::Part;
+use super::error::{check_grid, Result};
+use super::params::NoParams;
+use super::solver::{Example, Solver};
 
-pub fn solve(input : String, part: Part) -> String {
+pub struct Day09;
 
-    let lines:Vec<&str> = input.lines()
-        .collect();
+impl Solver for Day09 {
+    type Input = Vec<Vec<i32>>;
+
+    type Params = NoParams;
+
+    fn name(&self) -> &'static str {
+        "Smoke Basin"
+    }
 
-    match part {
-        Part::Part1 => part1(lines),
-        Part::Part2 => part2(lines)
+    fn day(&self) -> u8 {
+        9
+    }
+
+    fn examples(&self) -> Vec<Example> {
+        vec![Example{input:EXAMPLE, part1:Some("15"), part2:Some("1134")}]
+    }
+
+    fn parse(&self, input:&str) -> Result<Vec<Vec<i32>>> {
+        let lines:Vec<&str> = input.lines().collect();
+        check_grid(&lines, |ch| ch.is_ascii_digit())?;
+
+        Ok(parse(lines))
+    }
+
+    fn part1(&self, grid:&Vec<Vec<i32>>, _:&NoParams) -> Result<String> {
+        Ok(part1(grid))
+    }
+
+    fn part2(&self, grid:&Vec<Vec<i32>>, _:&NoParams) -> Result<String> {
+        Ok(part2(grid))
     }
 }
 
@@ -26,7 +52,7 @@ fn is_low_point(x:i32,y:i32,grid:&[Vec<i32>]) -> bool {
     let width = grid.first().unwrap().len() as i32;
     let current_height = grid.get(y as usize).unwrap().get(x as usize).unwrap();
 
-    let positions:Vec<(i32,i32)> = vec![(x-1,y),(x+1,y),(x,y-1),(x,y+1)]
+    let positions:Vec<(i32,i32)> = [(x-1,y),(x+1,y),(x,y-1),(x,y+1)]
         .iter()
         .filter(|(x,y)| *x >= 0 && *x < width && *y >= 0 && *y < height )
         .copied()
@@ -42,20 +68,18 @@ fn get_low_points(grid:&[Vec<i32>]) -> Vec<(i32,i32,i32)>{
     let width = grid.first().unwrap().len() as i32;
 
     let positions:Vec<(i32,i32,i32)> = (0..height)
-        .into_iter()
-        .map(|y| (0..width).into_iter()
+        .flat_map(|y| (0..width)
             .map(move |x| (x,y, *grid.get(y as usize).unwrap().get(x as usize).unwrap())))
-        .flatten()
         .collect();
 
-    positions.iter().filter(|&(x,y,_)| is_low_point(*x,*y,&grid)).copied().collect()
+    positions.iter().filter(|&(x,y,_)| is_low_point(*x,*y,grid)).copied().collect()
 }
 
 fn get_adjacent((x1,y1):&(i32,i32), grid:&[Vec<i32>]) -> Vec<(i32,i32,i32)> {
     let height = grid.len() as i32;
     let width = grid.first().unwrap().len() as i32;
 
-    vec![(*x1-1,*y1),(*x1+1,*y1),(*x1,*y1-1),(*x1,*y1+1)].iter()
+    [(*x1-1,*y1),(*x1+1,*y1),(*x1,*y1-1),(*x1,*y1+1)].iter()
         .filter(|(x,y)| *x >= 0 && *x < width && *y >= 0 && *y < height )
         .map( |(x,y)| (*x,*y, *grid.get(*y as usize).unwrap().get(*x as usize).unwrap()))
         .collect()
@@ -66,12 +90,11 @@ fn get_basin_size((x_low,y_low,height_low):&(i32,i32,i32), grid:&[Vec<i32>]) ->
     let mut eval = vec![(*x_low,*y_low, *height_low)];
 
 
-    while !eval.is_empty() {
-        let (x,y, current_height) = eval.pop().unwrap();
+    while let Some((x,y, current_height)) = eval.pop() {
 
         basin.insert( (x,y, current_height));
 
-        let adjacent:Vec<(i32,i32,i32)> = get_adjacent(&(x,y), &grid ).iter()
+        let adjacent:Vec<(i32,i32,i32)> = get_adjacent(&(x,y), grid ).iter()
             .filter(|item| !basin.contains(item))
             .filter(|&(_,_,height)| *height != 9 && *height > current_height)
             .copied()
@@ -87,9 +110,9 @@ fn get_basin_size((x_low,y_low,height_low):&(i32,i32,i32), grid:&[Vec<i32>]) ->
 
 }
 
-fn part1(lines:Vec<&str>) -> String {
-    let grid= parse(lines);
-    let low_points = get_low_points(&grid);
+/// Sum of the risk levels of all low points.
+pub fn part1(grid:&[Vec<i32>]) -> String {
+    let low_points = get_low_points(grid);
 
     let risk_level:i32 = low_points.iter()
         .map(|&(_,_,low_point)| low_point + 1)
@@ -99,63 +122,58 @@ fn part1(lines:Vec<&str>) -> String {
 }
 
 
-fn part2(lines:Vec<&str>) -> String {
-    let grid = parse(lines);
-    let low_points = get_low_points(&grid);
+/// Product of the sizes of the three largest basins.
+pub fn part2(grid:&[Vec<i32>]) -> String {
+    let low_points = get_low_points(grid);
 
-    let mut basin_sizes:Vec<usize> = low_points.iter().map(|p| get_basin_size(p, &grid)).collect();
+    let mut basin_sizes:Vec<usize> = low_points.iter().map(|p| get_basin_size(p, grid)).collect();
     basin_sizes.sort_by(|a,b| b.cmp(a));
 
     // Multiply 3 biggest basins
-    (0..3).into_iter()
-        .map(|i| basin_sizes.get(i as usize).unwrap())
-        .fold(1,|acc, item| *item as usize * acc)
+    basin_sizes.iter()
+        .take(3)
+        .product::<usize>()
         .to_string()
 }
 
 
+const EXAMPLE:&str = "2199943210
+3987894921
+9856789892
+8767896789
+9899965678";
+
+
 #[cfg(test)]
 mod tests {
     // Note this useful idiom: importing names from outer (for mod tests) scope.
     use super::*;
+    use util::solver::Puzzle;
     use util::Part::{Part1, Part2};
 
 
     #[test]
     fn test1() {
-
-        let input = "2199943210
-3987894921
-9856789892
-8767896789
-9899965678";
-
-        assert_eq!("15", solve(input.to_string(), Part1));
+        assert_eq!("15", Day09.solve(EXAMPLE, Part1).unwrap());
     }
 
     #[test]
     fn test_part1() {
         let input = include_str!("../../input_09.txt");
 
-        assert_eq!("458", solve(input.to_string(), Part1));
+        assert_eq!("458", Day09.solve(input, Part1).unwrap());
     }
 
     #[test]
     fn test2() {
-
-        let input = "2199943210
-3987894921
-9856789892
-8767896789
-9899965678";
-        assert_eq!("1134", solve(input.to_string(), Part2));
+        assert_eq!("1134", Day09.solve(EXAMPLE, Part2).unwrap());
     }
 
     #[test]
     fn test_part2() {
         let input = include_str!("../../input_09.txt");
 
-        assert_eq!("1391940", solve(input.to_string(), Part2));
+        assert_eq!("1391940", Day09.solve(input, Part2).unwrap());
     }
 
 }
@@ -1,14 +1,45 @@
 use std::collections::VecDeque;
-use super::Part;
+use super::error::{Result, SolveError};
+use super::params::NoParams;
+use super::solver::{Example, Solver};
 
-pub fn solve(input : String, part: Part) -> String {
+pub struct Day10;
 
-    let lines:Vec<&str> = input.lines()
-        .collect();
+impl Solver for Day10 {
+    type Input = Vec<String>;
+
+    type Params = NoParams;
+
+    fn name(&self) -> &'static str {
+        "Syntax Scoring"
+    }
+
+    fn day(&self) -> u8 {
+        10
+    }
+
+    fn examples(&self) -> Vec<Example> {
+        vec![Example{input:EXAMPLE, part1:Some("26397"), part2:Some("288957")}]
+    }
+
+    fn parse(&self, input:&str) -> Result<Vec<String>> {
+        input.lines()
+            .enumerate()
+            .map(|(i, line)| {
+                match line.find(|ch| !is_open(ch) && !is_close(ch)) {
+                    Some(pos) => Err(SolveError::at(i + 1, pos + 1, "expected a bracket")),
+                    None => Ok(line.to_string()),
+                }
+            })
+            .collect()
+    }
 
-    match part {
-        Part::Part1 => part1(lines),
-        Part::Part2 => part2(lines)
+    fn part1(&self, lines:&Vec<String>, _:&NoParams) -> Result<String> {
+        Ok(part1(lines))
+    }
+
+    fn part2(&self, lines:&Vec<String>, _:&NoParams) -> Result<String> {
+        part2(lines)
     }
 }
 
@@ -61,7 +92,8 @@ fn check_line(line:&str) -> (VecDeque<char>, Option<char>) {
     (stack, None)
 }
 
-fn part1(lines:Vec<&str>) -> String {
+/// Syntax error score of the corrupted lines.
+pub fn part1(lines:&[String]) -> String {
     let result: Vec<(VecDeque<char>, Option<char>)>= lines.iter().map(|elem| check_line(elem)).collect();
 
     let sum:usize = result.iter().filter(|(_, ch)| ch.is_some() )
@@ -77,7 +109,8 @@ fn part1(lines:Vec<&str>) -> String {
 }
 
 
-fn part2(lines:Vec<&str>) -> String {
+/// Middle completion score of the incomplete lines.
+pub fn part2(lines:&[String]) -> Result<String> {
     let rest_list:Vec<VecDeque<char>> = lines.iter()
         .map(|elem| check_line(elem))
         .filter(|(_, ch)| ch.is_none())
@@ -101,21 +134,13 @@ fn part2(lines:Vec<&str>) -> String {
              })).collect();
 
     missing.sort_unstable();
-    missing.get( missing.len() / 2).unwrap().to_string()
+    missing.get(missing.len() / 2)
+        .map(|score| score.to_string())
+        .ok_or_else(|| SolveError::new("no incomplete lines"))
 }
 
 
-#[cfg(test)]
-mod tests {
-    // Note this useful idiom: importing names from outer (for mod tests) scope.
-    use super::*;
-    use util::Part::{Part1, Part2};
-
-
-    #[test]
-    fn test1() {
-
-        let input = "[({(<(())[]>[[{[]{<()<>>
+const EXAMPLE:&str = "[({(<(())[]>[[{[]{<()<>>
 [(()[<>])]({[<{<<[]>>(
 {([(<{}[<>[]}>{[]{[(<()>
 (((({<>}<{<{<>}{[]{[]{}
@@ -126,37 +151,44 @@ mod tests {
 <{([([[(<>()){}]>(<<{{
 <{([{{}}[<[[[<>{}]]]>[]]";
 
-        assert_eq!("26397", solve(input.to_string(), Part1));
+
+#[cfg(test)]
+mod tests {
+    // Note this useful idiom: importing names from outer (for mod tests) scope.
+    use super::*;
+    use util::solver::Puzzle;
+    use util::Part::{Part1, Part2};
+
+
+    #[test]
+    fn test1() {
+        assert_eq!("26397", Day10.solve(EXAMPLE, Part1).unwrap());
     }
 
     #[test]
     fn test_part1() {
         let input = include_str!("../../input_10.txt");
 
-        assert_eq!("469755", solve(input.to_string(), Part1));
+        assert_eq!("469755", Day10.solve(input, Part1).unwrap());
     }
 
     #[test]
     fn test2() {
-
-        let input = "[({(<(())[]>[[{[]{<()<>>
-[(()[<>])]({[<{<<[]>>(
-{([(<{}[<>[]}>{[]{[(<()>
-(((({<>}<{<{<>}{[]{[]{}
-[[<[([]))<([[{}[[()]]]
-[{[{({}]{}}([{[{{{}}([]
-{<[[]]>}<{[{[{[]{()[[[]
-[<(<(<(<{}))><([]([]()
-<{([([[(<>()){}]>(<<{{
-<{([{{}}[<[[[<>{}]]]>[]]";
-        assert_eq!("288957", solve(input.to_string(), Part2));
+        assert_eq!("288957", Day10.solve(EXAMPLE, Part2).unwrap());
     }
 
     #[test]
     fn test_part2() {
         let input = include_str!("../../input_10.txt");
 
-        assert_eq!("2762335572", solve(input.to_string(), Part2));
+        assert_eq!("2762335572", Day10.solve(input, Part2).unwrap());
+    }
+
+    #[test]
+    fn test_no_incomplete() {
+        assert!(Day10.solve("(]", Part2).is_err());
+        assert!(Day10.solve("", Part2).is_err());
+        assert_eq!("0", Day10.solve("", Part1).unwrap());
     }
 
 }
@@ -1,14 +1,66 @@
 use std::collections::{HashMap, VecDeque};
-use super::Part;
+use super::error::{check_grid, Result};
+use super::params::{parse_value, unknown, Param, Params};
+use super::solver::{Example, Solver};
 
-pub fn solve(input : String, part: Part) -> String {
+pub struct Day11;
 
-    let lines:Vec<&str> = input.lines()
-        .collect();
+impl Solver for Day11 {
+    type Input = HashMap<(i32, i32), i32>;
 
-    match part {
-        Part::Part1 => part1(lines),
-        Part::Part2 => part2(lines)
+    type Params = Day11Params;
+
+    fn name(&self) -> &'static str {
+        "Dumbo Octopus"
+    }
+
+    fn day(&self) -> u8 {
+        11
+    }
+
+    fn examples(&self) -> Vec<Example> {
+        vec![Example{input:EXAMPLE, part1:Some("1656"), part2:Some("195")}]
+    }
+
+    fn parse(&self, input:&str) -> Result<HashMap<(i32, i32), i32>> {
+        let lines:Vec<&str> = input.lines().collect();
+        check_grid(&lines, |ch| ch.is_ascii_digit())?;
+
+        Ok(parse(lines))
+    }
+
+    fn part1(&self, grid:&HashMap<(i32, i32), i32>, params:&Day11Params) -> Result<String> {
+        Ok(part1(grid.clone(), params.steps))
+    }
+
+    fn part2(&self, grid:&HashMap<(i32, i32), i32>, _:&Day11Params) -> Result<String> {
+        Ok(part2(grid.clone()))
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Day11Params {
+    /// Steps to count flashes for in part 1.
+    pub steps:usize,
+}
+
+impl Default for Day11Params {
+    fn default() -> Day11Params {
+        Day11Params{steps:100}
+    }
+}
+
+impl Params for Day11Params {
+    fn list(&self) -> Vec<Param> {
+        vec![Param{name:"steps", value:self.steps.to_string(), description:"steps to count flashes for in part 1"}]
+    }
+
+    fn set(&mut self, name:&str, value:&str) -> Result<()> {
+        match name {
+            "steps" => self.steps = parse_value(name, value)?,
+            _ => return Err(unknown(name, self)),
+        }
+        Ok(())
     }
 }
 
@@ -21,17 +73,16 @@ fn do_step(grid:&mut HashMap<(i32,i32),i32>) -> usize {
 
     for y in 0..=max_y {
         for x in 0..=max_x {
-            increments.push_back((x as i32,y as i32));
+            increments.push_back((x,y));
         }
     }
 
-    while !increments.is_empty() {
-        let (x,y) = increments.pop_front().unwrap();
+    while let Some((x,y)) = increments.pop_front() {
         let octupus = grid.get_mut(&(x,y)).unwrap();
         *octupus += 1;
         if *octupus == 10 {
             // Add increment for adjacent
-            let adjacent = vec![(x-1, y), (x-1, y-1), (x-1, y+1), (x, y-1), (x, y+1), (x+1, y-1), (x+1, y), (x+1, y+1)];
+            let adjacent = [(x-1, y), (x-1, y-1), (x-1, y+1), (x, y-1), (x, y+1), (x+1, y-1), (x+1, y), (x+1, y+1)];
 
             adjacent.iter().filter(|&pos| grid.contains_key(pos))
                 .for_each(|pos| increments.push_back(*pos));
@@ -40,7 +91,7 @@ fn do_step(grid:&mut HashMap<(i32,i32),i32>) -> usize {
 
     for y in 0..=max_y {
         for x in 0..=max_x {
-            let octupus = grid.get_mut(&(x as i32,y as i32)).unwrap();
+            let octupus = grid.get_mut(&(x,y)).unwrap();
             if *octupus > 9 {
                 *octupus = 0;
                 flashes += 1;
@@ -65,15 +116,15 @@ fn parse(lines:Vec<&str>) -> HashMap<(i32,i32),i32> {
     map
 }
 
-fn part1(lines:Vec<&str>) -> String {
-    let mut grid = parse(lines);
-    let total:usize = (0..100).into_iter().map(|_| do_step(&mut grid)).sum();
+/// Number of flashes after `steps` steps.
+pub fn part1(mut grid:HashMap<(i32,i32),i32>, steps:usize) -> String {
+    let total:usize = (0..steps).map(|_| do_step(&mut grid)).sum();
     total.to_string()
 }
 
 
-fn part2(lines:Vec<&str>) -> String {
-    let mut grid = parse(lines);
+/// First step during which all octopuses flash.
+pub fn part2(mut grid:HashMap<(i32,i32),i32>) -> String {
     let mut step = 1;
 
     while do_step(&mut grid) != grid.len() {
@@ -84,18 +135,7 @@ fn part2(lines:Vec<&str>) -> String {
 }
 
 
-#[cfg(test)]
-mod tests {
-    // Note this useful idiom: importing names from outer (for mod tests) scope.
-    use super::*;
-    use util::Part::{Part1, Part2};
-
-
-
-    #[test]
-    fn test1() {
-
-        let input = "5483143223
+const EXAMPLE:&str = "5483143223
 2745854711
 5264556173
 6141336146
@@ -106,38 +146,43 @@ mod tests {
 4846848554
 5283751526";
 
-        assert_eq!("1656", solve(input.to_string(), Part1));
+
+#[cfg(test)]
+mod tests {
+    // Note this useful idiom: importing names from outer (for mod tests) scope.
+    use super::*;
+    use util::solver::Puzzle;
+    use util::Part::{Part1, Part2};
+
+
+
+    #[test]
+    fn test1() {
+        assert_eq!("1656", Day11.solve(EXAMPLE, Part1).unwrap());
     }
 
     #[test]
     fn test_part1() {
         let input = include_str!("../../input_11.txt");
 
-        assert_eq!("1615", solve(input.to_string(), Part1));
+        assert_eq!("1615", Day11.solve(input, Part1).unwrap());
     }
 
     #[test]
-    fn test2() {
-
-        let input = "5483143223
-2745854711
-5264556173
-6141336146
-6357385478
-4167524645
-2176841721
-6882881134
-4846848554
-5283751526";
+    fn test_params() {
+        assert_eq!("204", Day11.solve_with(EXAMPLE, Part1, &[("steps".to_string(), "10".to_string())]).unwrap());
+    }
 
-        assert_eq!("195", solve(input.to_string(), Part2));
+    #[test]
+    fn test2() {
+        assert_eq!("195", Day11.solve(EXAMPLE, Part2).unwrap());
     }
 
     #[test]
     fn test_part2() {
         let input = include_str!("../../input_11.txt");
 
-        assert_eq!("249", solve(input.to_string(), Part2));
+        assert_eq!("249", Day11.solve(input, Part2).unwrap());
     }
 
 }
@@ -1,20 +1,53 @@
 use std::collections::HashMap;
-use super::Part;
+use super::error::{Result, SolveError};
+use super::params::NoParams;
+use super::solver::{Example, Solver};
 
-pub fn solve(input : String, part: Part) -> String {
+pub struct Day12;
 
-    let lines:Vec<&str> = input.lines()
-        .collect();
+impl Solver for Day12 {
+    type Input = Graph;
 
-    match part {
-        Part::Part1 => part1(lines),
-        Part::Part2 => part2(lines)
+    type Params = NoParams;
+
+    fn name(&self) -> &'static str {
+        "Passage Pathing"
+    }
+
+    fn day(&self) -> u8 {
+        12
     }
 
+    fn examples(&self) -> Vec<Example> {
+        vec![Example{input:EXAMPLE, part1:Some("10"), part2:Some("36")}]
+    }
+
+    fn parse(&self, input:&str) -> Result<Graph> {
+        let edges = input.lines()
+            .enumerate()
+            .map(|(i, line)| Edge::new(i + 1, line))
+            .collect::<Result<Vec<Edge>>>()?;
+
+        let graph = Graph::new(edges);
+        if !graph.edges.contains_key("start") {
+            return Err(SolveError::new("no path from 'start'"));
+        }
+
+        Ok(graph)
+    }
+
+    fn part1(&self, graph:&Graph, _:&NoParams) -> Result<String> {
+        Ok(part1(graph))
+    }
+
+    fn part2(&self, graph:&Graph, _:&NoParams) -> Result<String> {
+        Ok(part2(graph))
+    }
 }
 
+/// Cave system, edges are stored in both directions.
 #[derive(Debug)]
-struct Graph {
+pub struct Graph {
     edges:HashMap<String,Vec<String>>
 }
 
@@ -30,19 +63,11 @@ impl Graph {
         let mut graph = Graph{edges:HashMap::new()};
         edges.iter().for_each(|edge| {
             // Insert from -> to
-            if !graph.edges.contains_key(edge.from.as_str())  {
-                graph.edges.insert(edge.from.clone(), vec![edge.to.clone()]);
-            } else {
-                graph.edges.get_mut(edge.from.as_str()).unwrap().push(edge.to.to_string());
-            }
+            graph.edges.entry(edge.from.clone()).or_default().push(edge.to.clone());
 
             let reverse_edge = edge.revert();
             if !reverse_edge.to.as_str().eq("start") {
-                if !graph.edges.contains_key(reverse_edge.from.as_str())  {
-                    graph.edges.insert(reverse_edge.from.clone(), vec![reverse_edge.to]);
-                } else {
-                    graph.edges.get_mut(reverse_edge.from.as_str()).unwrap().push(reverse_edge.to);
-                }
+                graph.edges.entry(reverse_edge.from).or_default().push(reverse_edge.to);
             }
 
 
@@ -59,7 +84,10 @@ impl Graph {
     }
 
     fn next_part1(&self,current_node:&str, visited:&HashMap<&str,u32>) -> Vec<&str> {
-        let edges = self.edges.get(&current_node.to_string()).unwrap();
+        let edges = match self.edges.get(current_node) {
+            Some(edges) => edges,
+            None => return vec![],
+        };
         let candidates : Vec<&str> = edges.iter()
             .filter(|node| node.as_str().ne("start") && !visited.contains_key(&node.as_str()))
             .map(|node| node.as_str())
@@ -68,7 +96,10 @@ impl Graph {
     }
 
     fn next_part2(&self,current_node:&str, visited:&HashMap<&str,u32>) -> Vec<&str> {
-        let edges = self.edges.get(&current_node.to_string()).unwrap();
+        let edges = match self.edges.get(current_node) {
+            Some(edges) => edges,
+            None => return vec![],
+        };
         let used_double_visit = visited.contains_key(current_node) || visited.iter().any(|(_, cnt)| *cnt > 1);
 
         if used_double_visit {
@@ -87,11 +118,18 @@ impl Graph {
 }
 
 impl Edge {
-    fn new(line:&str) -> Edge {
+    fn new(line_no:usize, line:&str) -> Result<Edge> {
         let mut it = line.split('-');
         let from = it.next().unwrap();
-        let to = it.next().unwrap();
-        Edge{from:from.to_string(),to:to.to_string()}
+        let to = match it.next() {
+            Some(to) => to,
+            None => return Err(SolveError::on_line(line_no, "expected 'from-to'")),
+        };
+
+        if from.is_empty() || to.is_empty() || it.next().is_some() {
+            return Err(SolveError::on_line(line_no, "expected 'from-to'"));
+        }
+        Ok(Edge{from:from.to_string(),to:to.to_string()})
     }
 
     fn revert(&self) -> Edge {
@@ -120,12 +158,7 @@ fn find_paths(graph:&Graph, current_node:&str, visited:HashMap<&str,u32>, path:V
             let mut next_visited = visited.clone();
 
             if small_cave {
-                if next_visited.contains_key(current_node) {
-                    let cnt = next_visited.get_mut(current_node).unwrap();
-                    *cnt += 1;
-                } else {
-                    next_visited.insert(current_node, 1);
-                }
+                *next_visited.entry(current_node).or_insert(0) += 1;
             }
 
             // Next step
@@ -134,47 +167,44 @@ fn find_paths(graph:&Graph, current_node:&str, visited:HashMap<&str,u32>, path:V
     }
 }
 
-fn part1(lines:Vec<&str>) -> String {
-    let edges:Vec<Edge> = lines.iter().map(|line| Edge::new(line)).collect();
-    let graph = Graph::new(edges);
-
+/// Number of paths visiting small caves at most once.
+pub fn part1(graph:&Graph) -> String {
     let mut solutions = vec![];
-    find_paths(&graph, "start", HashMap::new(), vec![], &mut solutions, true);
+    find_paths(graph, "start", HashMap::new(), vec![], &mut solutions, true);
 
     solutions.len().to_string()
 }
 
 
-fn part2(lines:Vec<&str>) -> String {
-    let edges:Vec<Edge> = lines.iter().map(|line| Edge::new(line)).collect();
-    let graph = Graph::new(edges);
-
+/// Number of paths where a single small cave may be visited twice.
+pub fn part2(graph:&Graph) -> String {
     let mut solutions = vec![];
-    find_paths(&graph, "start", HashMap::new(), vec![], &mut solutions, false);
+    find_paths(graph, "start", HashMap::new(), vec![], &mut solutions, false);
 
     solutions.len().to_string()
 }
 
 
+const EXAMPLE:&str = "start-A
+start-b
+A-c
+A-b
+b-d
+A-end
+b-end";
+
+
 #[cfg(test)]
 mod tests {
     // Note this useful idiom: importing names from outer (for mod tests) scope.
     use super::*;
+    use util::solver::Puzzle;
     use util::Part::{Part1, Part2};
 
 
     #[test]
     fn test1() {
-
-        let input = "start-A
-start-b
-A-c
-A-b
-b-d
-A-end
-b-end";
-
-        assert_eq!("10", solve(input.to_string(), Part1));
+        assert_eq!("10", Day12.solve(EXAMPLE, Part1).unwrap());
     }
 
     #[test]
@@ -191,7 +221,7 @@ kj-sa
 kj-HN
 kj-dc";
 
-        assert_eq!("19", solve(input.to_string(), Part1));
+        assert_eq!("19", Day12.solve(input, Part1).unwrap());
     }
 
     #[test]
@@ -216,7 +246,7 @@ zg-he
 pj-fs
 start-RW";
 
-        assert_eq!("226", solve(input.to_string(), Part1));
+        assert_eq!("226", Day12.solve(input, Part1).unwrap());
     }
 
 
@@ -224,20 +254,12 @@ start-RW";
     fn test_part1() {
         let input = include_str!("../../input_12.txt");
 
-        assert_eq!("4691", solve(input.to_string(), Part1));
+        assert_eq!("4691", Day12.solve(input, Part1).unwrap());
     }
 
     #[test]
     fn test2() {
-
-        let input = "start-A
-start-b
-A-c
-A-b
-b-d
-A-end
-b-end";
-        assert_eq!("36", solve(input.to_string(), Part2));
+        assert_eq!("36", Day12.solve(EXAMPLE, Part2).unwrap());
     }
 
     #[test]
@@ -254,7 +276,7 @@ kj-sa
 kj-HN
 kj-dc";
 
-        assert_eq!("103", solve(input.to_string(), Part2));
+        assert_eq!("103", Day12.solve(input, Part2).unwrap());
     }
 
     #[test]
@@ -279,14 +301,14 @@ zg-he
 pj-fs
 start-RW";
 
-        assert_eq!("3509", solve(input.to_string(), Part2));
+        assert_eq!("3509", Day12.solve(input, Part2).unwrap());
     }
 
     #[test]
     fn test_part2() {
         let input = include_str!("../../input_12.txt");
 
-        assert_eq!("140718", solve(input.to_string(), Part2));
+        assert_eq!("140718", Day12.solve(input, Part2).unwrap());
     }
 
 }
@@ -1,39 +1,88 @@
 use std::collections::{HashSet};
-use super::Part;
+use super::error::{missing, parse_field, Result, SolveError};
+use super::params::NoParams;
+use super::solver::{Example, Solver};
 
-pub fn solve(input : String, part: Part) -> String {
+pub struct Day13;
 
-    let lines:Vec<&str> = input.split("\n\n")
-        .collect();
+impl Solver for Day13 {
+    type Input = Paper;
 
-    match part {
-        Part::Part1 => part1(lines),
-        Part::Part2 => part2(lines)
+    type Params = NoParams;
+
+    fn name(&self) -> &'static str {
+        "Transparent Origami"
+    }
+
+    fn day(&self) -> u8 {
+        13
+    }
+
+    fn examples(&self) -> Vec<Example> {
+        vec![Example{input:EXAMPLE, part1:Some("17"), part2:Some("#####\n#   #\n#   #\n#   #\n#####\n")}]
+    }
+
+    fn parse(&self, input:&str) -> Result<Paper> {
+        parse_paper(input.split("\n\n").collect())
+    }
+
+    fn part1(&self, paper:&Paper, _:&NoParams) -> Result<String> {
+        Ok(part1(paper.0.clone(), &paper.1))
+    }
+
+    fn part2(&self, paper:&Paper, _:&NoParams) -> Result<String> {
+        Ok(part2(paper.0.clone(), &paper.1))
     }
 }
 
+/// Dots on the paper and the fold instructions, a fold along x has y set to 0.
+pub type Paper = (HashSet<(usize,usize)>, Vec<(usize,usize)>);
 
-fn parse_paper(lines:Vec<&str>) -> (HashSet<(usize,usize)>, Vec<(usize,usize)>) {
+fn parse_paper(lines:Vec<&str>) -> Result<Paper> {
     let mut grid = HashSet::new();
     let mut it = lines.iter();
-    it.next().unwrap().lines().for_each(|line| {
+    let dots = it.next().unwrap();
+    for (i, line) in dots.lines().enumerate() {
         let mut col = line.split(',');
-        let x:usize = col.next().unwrap().parse().unwrap();
-        let y:usize = col.next().unwrap().parse().unwrap();
+        let x:usize = parse_field(i + 1, line, col.next().unwrap())?;
+        let y:usize = match col.next() {
+            Some(y) => parse_field(i + 1, line, y)?,
+            None => return Err(missing(i + 1, line, "y coordinate")),
+        };
         grid.insert((x,y));
-    });
+    }
+
+    if grid.is_empty() {
+        return Err(SolveError::new("no dots in input"));
+    }
+
+    let line_offset = dots.lines().count() + 1;
+    let folds = match it.next() {
+        Some(folds) if !folds.trim().is_empty() => folds,
+        _ => return Err(SolveError::new("no fold instructions in input")),
+    };
 
     let mut fold_instr = vec![];
-    it.next().unwrap().lines().for_each(|line|{
-        let fold_param:usize = line.split('=').last().unwrap().parse().unwrap();
-        if line.contains("x") {
-            fold_instr.push((fold_param, 0));
-        } else {
-            fold_instr.push((0, fold_param));
+    for (i, line) in folds.lines().enumerate() {
+        let line_no = line_offset + i + 1;
+        let (axis, value) = match line.strip_prefix("fold along ").and_then(|fold| fold.split_once('=')) {
+            Some(fold) => fold,
+            None => return Err(SolveError::on_line(line_no, "expected 'fold along x=N' or 'fold along y=N'")),
+        };
+
+        let fold_param:usize = parse_field(line_no, line, value)?;
+        if fold_param == 0 {
+            return Err(SolveError::at(line_no, line.len(), "can't fold along 0"));
         }
-    });
 
-    (grid, fold_instr)
+        match axis {
+            "x" => fold_instr.push((fold_param, 0)),
+            "y" => fold_instr.push((0, fold_param)),
+            _ => return Err(SolveError::at(line_no, line.len() - value.len() - 1, "invalid fold axis")),
+        }
+    }
+
+    Ok((grid, fold_instr))
 }
 
 
@@ -90,8 +139,8 @@ fn fold_y(grid:&mut HashSet<(usize,usize)>, fold_y:usize) {
 }
 
 
-fn part1(lines:Vec<&str>) -> String {
-    let (mut grid, fold_instr) = parse_paper(lines);
+/// Number of dots visible after the first fold.
+pub fn part1(mut grid:HashSet<(usize,usize)>, fold_instr:&[(usize,usize)]) -> String {
 
     let (fold_x_pos, fold_y_pos) = fold_instr.first().unwrap();
     match fold_x_pos {
@@ -103,8 +152,8 @@ fn part1(lines:Vec<&str>) -> String {
 }
 
 
-fn part2(lines:Vec<&str>) -> String {
-    let (mut grid, fold_instr) = parse_paper(lines);
+/// The code shown after all folds, as rows of `#`.
+pub fn part2(mut grid:HashSet<(usize,usize)>, fold_instr:&[(usize,usize)]) -> String {
 
     fold_instr.iter().for_each(|(fold_x_pos, fold_y_pos)|{
         match fold_x_pos {
@@ -117,17 +166,7 @@ fn part2(lines:Vec<&str>) -> String {
 }
 
 
-#[cfg(test)]
-mod tests {
-    // Note this useful idiom: importing names from outer (for mod tests) scope.
-    use super::*;
-    use util::Part::{Part1, Part2};
-
-
-    #[test]
-    fn test1() {
-
-        let input = "6,10
+const EXAMPLE:&str = "6,10
 0,14
 9,10
 0,3
@@ -149,46 +188,35 @@ mod tests {
 fold along y=7
 fold along x=5";
 
-        assert_eq!("17", solve(input.to_string(), Part1));
+
+#[cfg(test)]
+mod tests {
+    // Note this useful idiom: importing names from outer (for mod tests) scope.
+    use super::*;
+    use util::solver::Puzzle;
+    use util::Part::{Part1, Part2};
+
+
+    #[test]
+    fn test1() {
+        assert_eq!("17", Day13.solve(EXAMPLE, Part1).unwrap());
     }
 
 
     #[test]
     fn test_part1() {
         let input = include_str!("../../input_13.txt");
-        assert_eq!("695", solve(input.to_string(), Part1));
+        assert_eq!("695", Day13.solve(input, Part1).unwrap());
     }
 
     #[test]
     fn test2() {
-
-        let input = "6,10
-0,14
-9,10
-0,3
-10,4
-4,11
-6,0
-6,12
-4,1
-0,13
-10,12
-3,4
-3,0
-8,4
-1,10
-2,14
-8,10
-9,0
-
-fold along y=7
-fold along x=5";
         let result = "#####
 #   #
 #   #
 #   #
 #####\n";
-        assert_eq!(result, solve(input.to_string(), Part2));
+        assert_eq!(result, Day13.solve(EXAMPLE, Part2).unwrap());
     }
 
     #[test]
@@ -200,7 +228,7 @@ fold along x=5";
 # ##    #  #   # ## #    #  # ###     #
 #  # #  # #    #  # #    #  # #    #  #
  ###  ##  ####  ### ####  ##  #     ## \n";
-        assert_eq!(result, solve(input.to_string(), Part2));
+        assert_eq!(result, Day13.solve(input, Part2).unwrap());
     }
 
 }
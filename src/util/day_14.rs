@@ -1,117 +1,143 @@
 use std::collections::{HashMap, VecDeque};
-use super::Part;
+use super::error::{Result, SolveError};
+use super::params::{parse_value, per_part, unknown, Param, Params};
+use super::solver::{Example, Solver};
 
-pub fn solve(input : String, part: Part) -> String {
+pub struct Day14;
 
-    let lines:Vec<&str> = input.split("\n\n")
-        .collect();
+impl Solver for Day14 {
+    type Input = (VecDeque<char>, Vec<Rule>);
 
-    match part {
-        Part::Part1 => part1(lines),
-        Part::Part2 => part2(lines)
+    type Params = Day14Params;
+
+    fn name(&self) -> &'static str {
+        "Extended Polymerization"
     }
-}
 
+    fn day(&self) -> u8 {
+        14
+    }
 
-fn parse(lines:Vec<&str>) -> (VecDeque<char>, Vec<([char;2], char)>) {
-    let mut it = lines.iter();
-    let template:VecDeque<char> = it.next().unwrap().chars().collect();
+    fn examples(&self) -> Vec<Example> {
+        vec![Example{input:EXAMPLE, part1:Some("1588"), part2:Some("2188189693529")}]
+    }
 
-    let rules:Vec<([char;2], char)> = it.next().unwrap().lines().map(|line|{
-        let mut columns = line.split(" -> ");
-        let chars = columns.next().unwrap().as_bytes();
-        let pair:[char;2] = [*chars.get(0).unwrap() as char, *chars.get(1).unwrap() as char];
-        let insertion:char = *columns.next().unwrap().as_bytes().first().unwrap() as char;
+    fn parse(&self, input:&str) -> Result<(VecDeque<char>, Vec<Rule>)> {
+        parse(input.split("\n\n").collect())
+    }
 
-        (pair.clone(), insertion)
-    }).collect();
+    fn part1(&self, polymer:&(VecDeque<char>, Vec<Rule>), params:&Day14Params) -> Result<String> {
+        difference(&polymer.0, &polymer.1, params.steps.unwrap_or(10))
+    }
 
-    (template, rules)
+    fn part2(&self, polymer:&(VecDeque<char>, Vec<Rule>), params:&Day14Params) -> Result<String> {
+        difference(&polymer.0, &polymer.1, params.steps.unwrap_or(40))
+    }
 }
 
-fn get_rule(a:char, b:char, rules:&Vec<([char;2], char)>) -> char {
-    *rules.iter().find( | (chars, _) | chars[0] == a && chars[1] == b )
-        .map(|(_,ch)| ch)
-        .unwrap()
+#[derive(Debug, Clone, Default)]
+pub struct Day14Params {
+    /// Insertion steps, 10 for part 1 and 40 for part 2 when not set.
+    pub steps:Option<usize>,
 }
 
+impl Params for Day14Params {
+    fn list(&self) -> Vec<Param> {
+        vec![Param{name:"steps", value:per_part(&self.steps, 10, 40), description:"pair insertion steps"}]
+    }
 
-fn count_chars(first:char, next:char, cache:&mut HashMap<(char,char,usize),[u128;30] >, rules:&Vec<([char;2], char)>, step:usize) -> [u128;30] {
-
-    let key = (first, next, step);
-    if cache.contains_key(&key) {
-        return *cache.get(&key).unwrap();
+    fn set(&mut self, name:&str, value:&str) -> Result<()> {
+        match name {
+            "steps" => self.steps = Some(parse_value(name, value)?),
+            _ => return Err(unknown(name, self)),
+        }
+        Ok(())
     }
+}
+
+/// Pair insertion rule, `AB -> C`.
+pub type Rule = ([char;2], char);
 
-    let middle = get_rule(first, next, rules);
+fn parse(lines:Vec<&str>) -> Result<(VecDeque<char>, Vec<Rule>)> {
+    let mut it = lines.iter();
+    let template:VecDeque<char> = it.next().unwrap().trim_end().chars().collect();
 
-    if step > 1 {
-        let mut count1 = count_chars(first,middle, cache,rules, step - 1);
-        let count2 = count_chars(middle,next, cache, rules, step - 1);
+    if template.len() < 2 {
+        return Err(SolveError::on_line(1, "template needs at least two elements"));
+    }
+    if let Some(pos) = template.iter().position(|ch| !ch.is_ascii_uppercase()) {
+        return Err(SolveError::at(1, pos + 1, "elements must be 'A' to 'Z'"));
+    }
 
-        // Cache result
-        for i in 0..count2.len() {
-            count1[i] += count2[i];
+    let rules = match it.next() {
+        Some(rules) => rules,
+        None => return Err(SolveError::new("no insertion rules in input")),
+    };
+
+    let rules = rules.lines().enumerate().map(|(i, line)|{
+        let chars:Vec<char> = line.chars().collect();
+        match chars.as_slice() {
+            [a, b, ' ', '-', '>', ' ', insertion]
+                if [a, b, insertion].iter().all(|ch| ch.is_ascii_uppercase()) => Ok(([*a, *b], *insertion)),
+            _ => Err(SolveError::on_line(i + 3, "expected rule 'AB -> C'")),
         }
-        cache.insert(key, count1);
+    }).collect::<Result<Vec<Rule>>>()?;
 
-        return count1;
-    } else {
-        let mut count:[u128;30] = [0;30];
-        count[first as usize - 'A' as usize] += 1;
-        count[middle as usize - 'A' as usize] += 1;
+    Ok((template, rules))
+}
 
-        return count;
-    }
+fn get_rule(a:char, b:char, rules:&[Rule]) -> Result<char> {
+    rules.iter().find( | (chars, _) | chars[0] == a && chars[1] == b )
+        .map(|(_,ch)| *ch)
+        .ok_or_else(|| SolveError::new(&format!("no insertion rule for '{}{}'", a, b)))
 }
 
-fn calculate_polymer(lines:Vec<&str>, steps:usize) -> (u128, u128) {
-    let (mut polymer, rules) = parse(lines);
 
-    let mut count:[u128;30] = [0;30];
-    let mut cache = HashMap::new();
+/// Quantities of the most and least common elements after `steps` insertion
+/// steps, an error once those no longer fit in 128 bits.
+pub fn calculate_polymer(template:&VecDeque<char>, rules:&[Rule], steps:usize) -> Result<(u128, u128)> {
+    let too_long = |step:usize| SolveError::new(&format!("polymer length overflows at step {}", step));
+
+    // Only the number of each pair matters, not where it is
+    let mut pairs:HashMap<(char,char), u128> = HashMap::new();
+    for (first, next) in template.iter().zip(template.iter().skip(1)) {
+        *pairs.entry((*first, *next)).or_insert(0) += 1;
+    }
 
-    for _ in 0..polymer.len() - 1 {
-        let first  = polymer.pop_front().unwrap();
-        let second = *polymer.front().unwrap();
-        let c = count_chars(first, second, &mut cache, &rules,  steps );
-        for i in 0..c.len() {
-            count[i] += c[i];
+    for step in 1..=steps {
+        let mut next_pairs = HashMap::new();
+        for (&(first, next), &n) in pairs.iter() {
+            let middle = get_rule(first, next, rules)?;
+            for pair in [(first, middle), (middle, next)].iter() {
+                let count = next_pairs.entry(*pair).or_insert(0u128);
+                *count = count.checked_add(n).ok_or_else(|| too_long(step))?;
+            }
         }
+        pairs = next_pairs;
     }
 
-    // Add last char
-    let last_char = *polymer.back().unwrap();
+    // Every element but the last one starts a pair
+    let mut count:[u128;26] = [0;26];
+    for (&(first, _), &n) in pairs.iter() {
+        let element = &mut count[first as usize - 'A' as usize];
+        *element = element.checked_add(n).ok_or_else(|| too_long(steps))?;
+    }
+    let last_char = *template.back().unwrap();
     count[last_char as usize - 'A' as usize] += 1;
 
     let min = *count.iter().filter(|&n| *n > 0).min().unwrap();
     let max = *count.iter().max().unwrap();
 
-    (max, min)
+    Ok((max, min))
 }
 
-fn part1(lines:Vec<&str>) -> String {
-    let (max, min) = calculate_polymer(lines, 10);
-    (max - min).to_string()
+fn difference(template:&VecDeque<char>, rules:&[Rule], steps:usize) -> Result<String> {
+    let (max, min) = calculate_polymer(template, rules, steps)?;
+    Ok((max - min).to_string())
 }
 
-fn part2(lines:Vec<&str>) -> String {
-   let (max, min) = calculate_polymer(lines, 40);
-    (max - min).to_string()
-}
-
-
-#[cfg(test)]
-mod tests {
-    // Note this useful idiom: importing names from outer (for mod tests) scope.
-    use super::*;
-    use util::Part::{Part1, Part2};
-
-
-    #[test]
-    fn test1() {
 
-        let input = "NNCB
+const EXAMPLE:&str = "NNCB
 
 CH -> B
 HH -> N
@@ -130,45 +156,47 @@ BC -> B
 CC -> N
 CN -> C";
 
-        assert_eq!("1588", solve(input.to_string(), Part1));
+
+#[cfg(test)]
+mod tests {
+    // Note this useful idiom: importing names from outer (for mod tests) scope.
+    use super::*;
+    use util::solver::Puzzle;
+    use util::Part::{Part1, Part2};
+
+
+    #[test]
+    fn test1() {
+        assert_eq!("1588", Day14.solve(EXAMPLE, Part1).unwrap());
     }
 
     #[test]
     fn test_part1() {
         let input = include_str!("../../input_14.txt");
 
-        assert_eq!("2712", solve(input.to_string(), Part1));
+        assert_eq!("2712", Day14.solve(input, Part1).unwrap());
     }
 
     #[test]
     fn test2() {
-
-        let input = "NNCB
-
-CH -> B
-HH -> N
-CB -> H
-NH -> C
-HB -> C
-HC -> B
-HN -> C
-NN -> C
-BH -> H
-NC -> B
-NB -> B
-BN -> B
-BB -> N
-BC -> B
-CC -> N
-CN -> C";
-        assert_eq!("2188189693529", solve(input.to_string(), Part2));
+        assert_eq!("2188189693529", Day14.solve(EXAMPLE, Part2).unwrap());
     }
 
     #[test]
     fn test_part2() {
         let input = include_str!("../../input_14.txt");
 
-        assert_eq!("8336623059567", solve(input.to_string(), Part2));
+        assert_eq!("8336623059567", Day14.solve(input, Part2).unwrap());
+    }
+
+    #[test]
+    fn test_steps() {
+        let steps = |n:usize| [("steps".to_string(), n.to_string())];
+        assert_eq!("0", Day14.solve_with("NN\n\nNN -> C", Part1, &steps(0)).unwrap());
+        assert_eq!("1", Day14.solve_with("NN\n\nNN -> C", Part1, &steps(1)).unwrap());
+
+        let error = Day14.solve_with(EXAMPLE, Part1, &steps(100000)).unwrap_err();
+        assert!(error.message.starts_with("polymer length overflows"));
     }
 
 }
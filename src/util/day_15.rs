@@ -1,19 +1,87 @@
 use std::collections::{BinaryHeap};
 use std::cmp::{Ordering};
-use super::Part;
+use super::error::{check_grid, Result, SolveError};
+use super::params::{parse_value, unknown, Param, Params};
+use super::solver::{Example, Solver};
 
-pub fn solve(input : String, part: Part) -> String {
+pub struct Day15;
 
-    let lines:Vec<&str> = input.lines()
-        .collect();
+impl Solver for Day15 {
+    type Input = Grid;
 
-    match part {
-        Part::Part1 => part1(lines),
-        Part::Part2 => part2(lines)
+    type Params = Day15Params;
+
+    fn name(&self) -> &'static str {
+        "Chiton"
+    }
+
+    fn day(&self) -> u8 {
+        15
+    }
+
+    fn examples(&self) -> Vec<Example> {
+        vec![Example{input:EXAMPLE, part1:Some("40"), part2:Some("315")}]
+    }
+
+    fn parse(&self, input:&str) -> Result<Grid> {
+        let lines:Vec<&str> = input.lines().collect();
+        check_grid(&lines, |ch| ('1'..='9').contains(&ch))?;
+
+        Ok(Grid::new(lines))
+    }
+
+    fn part1(&self, grid:&Grid, _:&Day15Params) -> Result<String> {
+        find_path(grid).map(|cost| cost.to_string())
+    }
+
+    fn part2(&self, grid:&Grid, params:&Day15Params) -> Result<String> {
+        let cells = (grid.height * grid.width).checked_mul(params.tiles * params.tiles);
+        if cells.is_none_or(|cells| cells > MAX_CELLS) {
+            let message = format!("a map of {} by {} tiles of {}x{} is too large", params.tiles, params.tiles, grid.width, grid.height);
+            return Err(SolveError::new(&message));
+        }
+        find_path(&grid.tile(params.tiles)).map(|cost| cost.to_string())
     }
 }
 
-struct Grid {
+/// Most tiles in each direction.
+const MAX_TILES:usize = 1000;
+
+/// Most positions of the full map in part 2, each takes 9 bytes while searching.
+const MAX_CELLS:usize = 25_000_000;
+
+#[derive(Debug, Clone)]
+pub struct Day15Params {
+    /// Times the map is repeated in each direction in part 2.
+    pub tiles:usize,
+}
+
+impl Default for Day15Params {
+    fn default() -> Day15Params {
+        Day15Params{tiles:5}
+    }
+}
+
+impl Params for Day15Params {
+    fn list(&self) -> Vec<Param> {
+        vec![Param{name:"tiles", value:self.tiles.to_string(), description:"map repetitions in each direction in part 2"}]
+    }
+
+    fn set(&mut self, name:&str, value:&str) -> Result<()> {
+        match name {
+            "tiles" => self.tiles = parse_value(name, value)?,
+            _ => return Err(unknown(name, self)),
+        }
+
+        if self.tiles == 0 || self.tiles > MAX_TILES {
+            return Err(SolveError::new(&format!("tiles must be between 1 and {}", MAX_TILES)));
+        }
+        Ok(())
+    }
+}
+
+/// Risk level map, one digit per position.
+pub struct Grid {
     data:Vec<Vec<u8>>,
     height:usize,
     width:usize,
@@ -21,27 +89,36 @@ struct Grid {
 
 
 impl Grid {
-    fn new(lines:Vec<&str>, mult:usize) -> Grid {
+    fn new(lines:Vec<&str>) -> Grid {
+        let data:Vec<Vec<u8>> = lines.iter()
+            .map(|line| line.bytes().map(|b| b - 0x30).collect())
+            .collect();
+        let height = data.len();
+        let width = data.first().unwrap().len();
+
+        Grid{data,height,width}
+    }
+
+    /// The full map, `mult` tiles in each direction with increasing risk.
+    pub fn tile(&self, mult:usize) -> Grid {
         let mut grid = vec![];
-        let height = lines.len();
-        let width = lines.first().unwrap().len();
 
-        for y in 0..lines.len() * mult {
-            let line = lines.get(y % height).unwrap();
-            let tile_no_y = y / height;
+        for y in 0..self.height * mult {
+            let row = self.data.get(y % self.height).unwrap();
+            let tile_no_y = y / self.height;
 
             let mut v = vec![];
-            for x in 0..line.len() * mult {
-                let cost = line.as_bytes()[x % width] - 0x30;
-                let tile_no_x = x / width;
+            for x in 0..self.width * mult {
+                let cost = row[x % self.width];
+                let tile_no_x = x / self.width;
                 let risk_incr = tile_no_y + tile_no_x;
-                let risk = 1 + (risk_incr as u8 + cost - 1) % 9;
-                v.push(risk);
+                let risk = 1 + (risk_incr + cost as usize - 1) % 9;
+                v.push(risk as u8);
             }
             grid.push(v);
         }
 
-        Grid{data:grid,height:height*mult,width:width*mult }
+        Grid{data:grid,height:self.height*mult,width:self.width*mult }
     }
 
     fn get_cost(&self, x:usize, y:usize) -> usize {
@@ -92,24 +169,24 @@ impl PartialOrd for Node {
 
 
 
-fn find_path(grid:&Grid) -> usize {
+/// Lowest total risk of any path from the top left to the bottom right.
+pub fn find_path(grid:&Grid) -> Result<usize> {
     let end_x = grid.width - 1;
     let end_y = grid.height - 1;
 
     let start_node = Node{cost:0,x:0,y:0};
-    let mut visited = vec![std::usize::MAX; grid.width * grid.height];
+    let mut visited = vec![usize::MAX; grid.width * grid.height];
     let mut pq = BinaryHeap::new();
 
     pq.push(start_node);
 
-    while !pq.is_empty() {
-        let current_node = pq.pop().unwrap();
+    while let Some(current_node) = pq.pop() {
         let current_cost = current_node.cost;
         let x = current_node.x;
         let y = current_node.y;
 
         if x == end_x && y == end_y {
-            return current_cost;
+            return Ok(current_cost);
         }
 
         let prev_visit = visited.get_mut(y * grid.width + x).unwrap();
@@ -135,79 +212,72 @@ fn find_path(grid:&Grid) -> usize {
         }
     }
 
-    panic!("No solution...")
+    Err(SolveError::new("no path to the bottom right corner"))
 }
 
 
-
-fn part1(lines:Vec<&str>) -> String {
-    let grid = Grid::new(lines, 1);
-    let result = find_path(&grid);
-
-    result.to_string()
-}
-
-fn part2(lines:Vec<&str>) -> String {
-    let grid = Grid::new(lines, 5);
-
-    let result = find_path(&grid);
-
-    result.to_string()
-}
+const EXAMPLE:&str = "1163751742
+1381373672
+2136511328
+3694931569
+7463417111
+1319128137
+1359912421
+3125421639
+1293138521
+2311944581";
 
 
 #[cfg(test)]
 mod tests {
     // Note this useful idiom: importing names from outer (for mod tests) scope.
     use super::*;
+    use util::solver::Puzzle;
     use util::Part::{Part1, Part2};
 
 
     #[test]
     fn test1() {
+        assert_eq!("40", Day15.solve(EXAMPLE, Part1).unwrap());
+    }
 
-        let input = "1163751742
-1381373672
-2136511328
-3694931569
-7463417111
-1319128137
-1359912421
-3125421639
-1293138521
-2311944581";
+    #[test]
+    fn test_part1() {
+        let input = include_str!("../../input_15.txt");
 
-        assert_eq!("40", solve(input.to_string(), Part1));
+        assert_eq!("769", Day15.solve(input, Part1).unwrap());
     }
 
     #[test]
-    fn test_part1() {
+    fn test_params() {
+        assert_eq!("40", Day15.solve_with(EXAMPLE, Part2, &[("tiles".to_string(), "1".to_string())]).unwrap());
+        assert!(Day15.solve_with(EXAMPLE, Part2, &[("tiles".to_string(), "0".to_string())]).is_err());
+        assert!(Day15.solve_with(EXAMPLE, Part2, &[("tiles".to_string(), "1000000".to_string())]).is_err());
+
         let input = include_str!("../../input_15.txt");
+        let error = Day15.solve_with(input, Part2, &[("tiles".to_string(), "1000".to_string())]).unwrap_err();
+        assert_eq!("a map of 1000 by 1000 tiles of 100x100 is too large", error.message);
+    }
 
-        assert_eq!("769", solve(input.to_string(), Part1));
+    #[test]
+    fn test_tile() {
+        // The risk increase of the last tile is past what fits in a u8
+        let grid = Day15.parse(EXAMPLE).unwrap().tile(130);
+        assert_eq!((1300, 1300), (grid.width, grid.height));
+        assert_eq!(1 + (258 + 1 - 1) % 9, grid.get_cost(1299, 1299));
+        assert_eq!(1 + (129 + 1 - 1) % 9, grid.get_cost(1290, 0));
     }
 
     #[test]
     fn test2() {
-
-        let input = "1163751742
-1381373672
-2136511328
-3694931569
-7463417111
-1319128137
-1359912421
-3125421639
-1293138521
-2311944581";
-        assert_eq!("315", solve(input.to_string(), Part2));
+        assert_eq!("315", Day15.solve(EXAMPLE, Part2).unwrap());
     }
 
     #[test]
     fn test_part2() {
         let input = include_str!("../../input_15.txt");
 
-        assert_eq!("2963", solve(input.to_string(), Part2));
+        assert_eq!("2963", Day15.solve(input, Part2).unwrap());
     }
 
 }
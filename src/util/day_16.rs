@@ -1,27 +1,60 @@
-use super::Part;
-use super::packet_computer::PacketComputer;
+use super::packet_computer;
+use super::error::Result;
+use super::params::NoParams;
+use super::solver::{Example, Solver};
 
+pub struct Day16;
 
-pub fn solve(input : String, part: Part) -> String {
+impl Solver for Day16 {
+    type Input = String;
 
-    match part {
-        Part::Part1 => part1(input.as_str()),
-        Part::Part2 => part2(input.as_str())
+    type Params = NoParams;
+
+    fn name(&self) -> &'static str {
+        "Packet Decoder"
+    }
+
+    fn day(&self) -> u8 {
+        16
+    }
+
+    fn examples(&self) -> Vec<Example> {
+        let part1 = [("D2FE28", "6"), ("38006F45291200", "9"), ("EE00D40C823060", "14"),
+            ("8A004A801A8002F478", "16"), ("620080001611562C8802118E34", "12"),
+            ("C0015000016115A2E0802F182340", "23"), ("A0016C880162017C3686B18A3D4780", "31")];
+        let part2 = [("C200B40A82", "3"), ("04005AC33890", "54"), ("880086C3E88112", "7"),
+            ("CE00C43D881120", "9"), ("D8005AC2A8F0", "1"), ("F600BC2D8F", "0"),
+            ("9C005AC2F8F0", "0"), ("9C0141080250320F1802104A08", "1")];
+
+        part1.iter().map(|&(input, answer)| Example{input, part1:Some(answer), part2:None})
+            .chain(part2.iter().map(|&(input, answer)| Example{input, part1:None, part2:Some(answer)}))
+            .collect()
+    }
+
+    fn parse(&self, input:&str) -> Result<String> {
+        Ok(input.trim().to_string())
+    }
+
+    fn part1(&self, transmission:&String, _:&NoParams) -> Result<String> {
+        part1(transmission)
+    }
+
+    fn part2(&self, transmission:&String, _:&NoParams) -> Result<String> {
+        part2(transmission)
     }
 }
 
 
 
-fn part1(line:&str) -> String {
-    let mut packet_computer = PacketComputer::new(line);
-    packet_computer.run();
-    packet_computer.version_sum.to_string()
+/// Sum of the versions of all packets in the transmission.
+pub fn part1(line:&str) -> Result<String> {
+    Ok(packet_computer::parse(line)?.version_sum().to_string())
 }
 
 
-fn part2(line:&str) -> String {
-    let mut packet_computer = PacketComputer::new(line);
-    packet_computer.run().to_string()
+/// Value of the outermost packet.
+pub fn part2(line:&str) -> Result<String> {
+    Ok(packet_computer::parse(line)?.evaluate_big()?.to_string())
 }
 
 
@@ -29,6 +62,7 @@ fn part2(line:&str) -> String {
 mod tests {
     // Note this useful idiom: importing names from outer (for mod tests) scope.
     use super::*;
+    use util::solver::Puzzle;
     use util::Part::{Part1, Part2};
 
 
@@ -42,13 +76,13 @@ mod tests {
         let input6 = "C0015000016115A2E0802F182340";
         let input7 = "A0016C880162017C3686B18A3D4780";
 
-        assert_eq!("6", solve(input1.to_string(), Part1));
-        assert_eq!("9", solve(input2.to_string(), Part1));
-        assert_eq!("14", solve(input3.to_string(), Part1));
-        assert_eq!("16", solve(input4.to_string(), Part1));
-        assert_eq!("12", solve(input5.to_string(), Part1));
-        assert_eq!("23", solve(input6.to_string(), Part1));
-        assert_eq!("31", solve(input7.to_string(), Part1));
+        assert_eq!("6", Day16.solve(input1, Part1).unwrap());
+        assert_eq!("9", Day16.solve(input2, Part1).unwrap());
+        assert_eq!("14", Day16.solve(input3, Part1).unwrap());
+        assert_eq!("16", Day16.solve(input4, Part1).unwrap());
+        assert_eq!("12", Day16.solve(input5, Part1).unwrap());
+        assert_eq!("23", Day16.solve(input6, Part1).unwrap());
+        assert_eq!("31", Day16.solve(input7, Part1).unwrap());
     }
 
 
@@ -56,7 +90,7 @@ mod tests {
     fn test_part1() {
         let input = include_str!("../../input_16.txt");
 
-        assert_eq!("901", solve(input.to_string(), Part1));
+        assert_eq!("901", Day16.solve(input, Part1).unwrap());
     }
 
     #[test]
@@ -71,14 +105,14 @@ mod tests {
         let input7 = "9C005AC2F8F0";
         let input8 = "9C0141080250320F1802104A08";
 
-        assert_eq!("3", solve(input1.to_string(), Part2));
-        assert_eq!("54", solve(input2.to_string(), Part2));
-        assert_eq!("7", solve(input3.to_string(), Part2));
-        assert_eq!("9", solve(input4.to_string(), Part2));
-        assert_eq!("1", solve(input5.to_string(), Part2));
-        assert_eq!("0", solve(input6.to_string(), Part2));
-        assert_eq!("0", solve(input7.to_string(), Part2));
-        assert_eq!("1", solve(input8.to_string(), Part2));
+        assert_eq!("3", Day16.solve(input1, Part2).unwrap());
+        assert_eq!("54", Day16.solve(input2, Part2).unwrap());
+        assert_eq!("7", Day16.solve(input3, Part2).unwrap());
+        assert_eq!("9", Day16.solve(input4, Part2).unwrap());
+        assert_eq!("1", Day16.solve(input5, Part2).unwrap());
+        assert_eq!("0", Day16.solve(input6, Part2).unwrap());
+        assert_eq!("0", Day16.solve(input7, Part2).unwrap());
+        assert_eq!("1", Day16.solve(input8, Part2).unwrap());
 
     }
 
@@ -86,7 +120,15 @@ mod tests {
     fn test_part2() {
         let input = include_str!("../../input_16.txt");
 
-        assert_eq!("110434737925", solve(input.to_string(), Part2));
+        assert_eq!("110434737925", Day16.solve(input, Part2).unwrap());
     }
 
+    #[test]
+    fn test_invalid_input() {
+        let error = Day16.solve("8A0G4A", Part1).unwrap_err();
+        assert_eq!((Some(1), Some(4)), (error.line, error.column));
+
+        assert!(Day16.solve("D2FE", Part1).is_err());
+        assert!(Day16.solve("C200B40A82", Part2).is_ok());
+    }
 }
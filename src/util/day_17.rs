@@ -1,12 +1,38 @@
 use std::cmp::{max};
 use regex::Regex;
-use super::Part;
+use super::error::{parse_field, Result, SolveError};
+use super::params::NoParams;
+use super::solver::{Example, Solver};
 
-pub fn solve(input : String, part: Part) -> String {
+pub struct Day17;
 
-    match part {
-        Part::Part1 => part1(input.as_str()),
-        Part::Part2 => part2(input.as_str())
+impl Solver for Day17 {
+    type Input = Area;
+
+    type Params = NoParams;
+
+    fn name(&self) -> &'static str {
+        "Trick Shot"
+    }
+
+    fn day(&self) -> u8 {
+        17
+    }
+
+    fn examples(&self) -> Vec<Example> {
+        vec![Example{input:EXAMPLE, part1:Some("45"), part2:Some("112")}]
+    }
+
+    fn parse(&self, input:&str) -> Result<Area> {
+        Area::parse(input.trim())
+    }
+
+    fn part1(&self, area:&Area, _:&NoParams) -> Result<String> {
+        part1(area)
+    }
+
+    fn part2(&self, area:&Area, _:&NoParams) -> Result<String> {
+        Ok(part2(area))
     }
 }
 
@@ -18,8 +44,9 @@ struct Trajectory {
     y:i32,
 }
 
+/// Target area, inclusive on all sides.
 #[derive(Debug)]
-struct Area {
+pub struct Area {
     x1:i32,
     y1:i32,
     x2:i32,
@@ -27,19 +54,17 @@ struct Area {
 }
 
 impl Area {
-    fn parse(line:&str) -> Area {
+    fn parse(line:&str) -> Result<Area> {
         let re = Regex::new(r"target area: x=(\d*)..(\d*), y=.(\d*)...(\d*)").unwrap();
-        for cap in re.captures_iter(line) {
-            let (x1,y1,x2,y2) =
-                (cap[1].parse().unwrap(),
-                    cap[3].parse::<i32>().unwrap() * -1,
-                    cap[2].parse().unwrap(),
-                    cap[4].parse::<i32>().unwrap() * -1);
-            return Area::new(x1,y1,x2,y2)
-        }
-        panic!("...");
+        let cap = match re.captures(line) {
+            Some(cap) => cap,
+            None => return Err(SolveError::on_line(1, "expected 'target area: x=A..B, y=-C..-D'")),
+        };
+        let field = |n| parse_field::<i32>(1, line, cap.get(n).unwrap().as_str());
+        let (x1,y1,x2,y2) = (field(1)?, -field(3)?, field(2)?, -field(4)?);
+        Ok(Area::new(x1,y1,x2,y2))
     }
-    fn new(x1:i32,y1:i32, x2:i32,y2:i32) -> Area {
+    pub fn new(x1:i32,y1:i32, x2:i32,y2:i32) -> Area {
         Area{x1,y1,x2,y2}
     }
 }
@@ -92,14 +117,13 @@ impl Trajectory {
     }
 }
 
-fn get_max(line:&str) -> Vec<i32> {
-    let area = Area::parse(line);
+fn get_max(area:&Area) -> Vec<i32> {
     let mut ys = vec![];
 
     for x_v in 1..=area.x2 {
         for y_v in area.y1..200 {
             let mut t = Trajectory::new(x_v, y_v);
-            if let Some(y) = t.fire(&area) {
+            if let Some(y) = t.fire(area) {
                 ys.push(y);
             }
         }
@@ -107,50 +131,54 @@ fn get_max(line:&str) -> Vec<i32> {
     ys
 }
 
-fn part1(line:&str) -> String {
-    get_max(line).iter().max().unwrap().to_string()
+/// Highest y position reached by a trajectory that ends in the target area.
+pub fn part1(area:&Area) -> Result<String> {
+    match get_max(area).iter().max() {
+        Some(y) => Ok(y.to_string()),
+        None => Err(SolveError::new("no trajectory reaches the target area")),
+    }
 }
 
 
-fn part2(line:&str) -> String {
-    get_max(line).len().to_string()
+/// Number of initial velocities that end in the target area.
+pub fn part2(area:&Area) -> String {
+    get_max(area).len().to_string()
 }
 
 
+const EXAMPLE:&str = "target area: x=20..30, y=-10..-5";
+
+
 #[cfg(test)]
 mod tests {
     // Note this useful idiom: importing names from outer (for mod tests) scope.
     use super::*;
+    use util::solver::Puzzle;
     use util::Part::{Part1, Part2};
 
 
     #[test]
     fn test1() {
-
-        let input = "target area: x=20..30, y=-10..-5";
-
-        assert_eq!("45", solve(input.to_string(), Part1));
+        assert_eq!("45", Day17.solve(EXAMPLE, Part1).unwrap());
     }
 
     #[test]
     fn test_part1() {
         let input = include_str!("../../input_17.txt");
 
-        assert_eq!("9180", solve(input.to_string(), Part1));
+        assert_eq!("9180", Day17.solve(input, Part1).unwrap());
     }
 
     #[test]
     fn test2() {
-
-        let input = "target area: x=20..30, y=-10..-5";
-        assert_eq!("112", solve(input.to_string(), Part2));
+        assert_eq!("112", Day17.solve(EXAMPLE, Part2).unwrap());
     }
 
     #[test]
     fn test_part2() {
         let input = include_str!("../../input_17.txt");
 
-        assert_eq!("3767", solve(input.to_string(), Part2));
+        assert_eq!("3767", Day17.solve(input, Part2).unwrap());
     }
 
 }
@@ -1,16 +1,50 @@
 use util::day_18::Element::{COMMA, EndBracket, NUMBER, StartBracket};
-use super::Part;
+use super::error::{Result, SolveError};
+use super::params::NoParams;
+use super::solver::{Example, Solver};
 
-pub fn solve(input : String, part: Part) -> String {
+pub struct Day18;
 
-    let lines:Vec<&str> = input.lines()
-        .collect();
+impl Solver for Day18 {
+    type Input = Vec<SnailNumber>;
 
-    match part {
-        Part::Part1 => part1(lines),
-        Part::Part2 => part2(lines)
+    type Params = NoParams;
+
+    fn name(&self) -> &'static str {
+        "Snailfish"
+    }
+
+    fn day(&self) -> u8 {
+        18
+    }
+
+    fn examples(&self) -> Vec<Example> {
+        vec![Example{input:EXAMPLE, part1:Some("4140"), part2:Some("3993")}]
+    }
+
+    fn parse(&self, input:&str) -> Result<Vec<SnailNumber>> {
+        let numbers = input.lines()
+            .enumerate()
+            .map(|(i, line)| SnailNumber::parse(i + 1, line))
+            .collect::<Result<Vec<SnailNumber>>>()?;
+
+        if numbers.is_empty() {
+            return Err(SolveError::new("empty input"));
+        }
+
+        Ok(numbers)
+    }
+
+    fn part1(&self, numbers:&Vec<SnailNumber>, _:&NoParams) -> Result<String> {
+        Ok(part1(numbers))
+    }
+
+    fn part2(&self, numbers:&Vec<SnailNumber>, _:&NoParams) -> Result<String> {
+        part2(numbers)
     }
 }
+
+#[allow(clippy::upper_case_acronyms)]
 #[derive(Debug, PartialEq, Copy, Clone)]
 enum Element {
     StartBracket,
@@ -19,8 +53,9 @@ enum Element {
     NUMBER(u32),
 }
 
+/// A snailfish number stored as a flat list of brackets and regular numbers.
 #[derive(Debug)]
-struct SnailNumber {
+pub struct SnailNumber {
     elements:Vec<Element>,
 }
 
@@ -31,12 +66,20 @@ impl SnailNumber {
         SnailNumber{elements}
     }
 
-    fn parse(line:&str) -> SnailNumber {
-        let mut it = line.chars().into_iter();
+    /// A reduced snailfish number, so pairs nest at most 4 deep and regular
+    /// numbers are at most 9.
+    pub fn parse(line_no:usize, line:&str) -> Result<SnailNumber> {
+        let chars:Vec<char> = line.chars().collect();
+        match SnailNumber::validate(&chars, 0, 1) {
+            Ok(end) if end == chars.len() => {},
+            Ok(end) => return Err(SolveError::at(line_no, end + 1, "invalid snailfish number")),
+            Err((pos, message)) => return Err(SolveError::at(line_no, pos + 1, message)),
+        }
+
+        let mut it = line.chars();
         let mut element = it.next();
         let mut elements = vec![];
-        while element.is_some() {
-            let mut ch = element.unwrap();
+        while let Some(ch) = element {
             let mut number_str= String::new();
             if ch == '[' {
                 elements.push(StartBracket);
@@ -44,18 +87,48 @@ impl SnailNumber {
                 elements.push(EndBracket);
             } else if ch == ',' {
                 elements.push(COMMA);
-            } else if ch.is_digit(10) {
-                while ch.is_digit(10) {
-                    number_str.push(ch);
+            } else if ch.is_ascii_digit() {
+                while let Some(digit) = element.filter(|ch| ch.is_ascii_digit()) {
+                    number_str.push(digit);
                     element = it.next();
-                    ch = element.unwrap();
                 }
-                elements.push(NUMBER(number_str.parse::<u32>().unwrap()));
+                match number_str.parse::<u32>() {
+                    Ok(number) => elements.push(NUMBER(number)),
+                    Err(_) => return Err(SolveError::on_line(line_no, "number too large")),
+                }
                 continue;
             }
             element = it.next();
         }
-        SnailNumber{elements}
+        Ok(SnailNumber{elements})
+    }
+
+    // Returns the position after the element starting at pos, or the position of the error.
+    // A pair at pos is nested in depth - 1 pairs.
+    fn validate(chars:&[char], pos:usize, depth:usize) -> std::result::Result<usize, (usize, &'static str)> {
+        let invalid = |pos| Err((pos, "invalid snailfish number"));
+        match chars.get(pos) {
+            Some('[') if depth > 4 => Err((pos, "pair nested in more than 4 pairs")),
+            Some('[') => {
+                let pos = SnailNumber::validate(chars, pos + 1, depth + 1)?;
+                if chars.get(pos) != Some(&',') {
+                    return invalid(pos);
+                }
+                let pos = SnailNumber::validate(chars, pos + 1, depth + 1)?;
+                if chars.get(pos) != Some(&']') {
+                    return invalid(pos);
+                }
+                Ok(pos + 1)
+            }
+            Some(ch) if ch.is_ascii_digit() => {
+                let digits = chars[pos..].iter().take_while(|ch| ch.is_ascii_digit()).count();
+                if digits > 1 {
+                    return Err((pos, "regular number larger than 9"));
+                }
+                Ok(pos + digits)
+            }
+            _ => invalid(pos),
+        }
     }
 
     fn get_number(&self, index:usize) -> Option<u32> {
@@ -122,7 +195,8 @@ impl SnailNumber {
         }
     }
 
-    fn add(&mut self, other: &mut SnailNumber) {
+    /// Appends `other`, the result is not reduced.
+    pub fn add(&mut self, other: &mut SnailNumber) {
 
         if self.elements.is_empty() {
             while !other.elements.is_empty() {
@@ -151,7 +225,8 @@ impl SnailNumber {
         self.elements.remove(0)
     }
 
-    fn reduce(&mut self) {
+    /// Explodes and splits until neither applies.
+    pub fn reduce(&mut self) {
         loop {
             let mut cnt = 0;
             while self.explode() {
@@ -185,13 +260,10 @@ impl SnailNumber {
 
     fn get_split_index(&self) -> Option<usize> {
         for index in 0..self.elements.len() {
-            match *self.elements.get(index).unwrap() {
-                NUMBER(num) => {
-                    if num > 9 {
-                        return Some(index);
-                    }
+            if let NUMBER(num) = *self.elements.get(index).unwrap() {
+                if num > 9 {
+                    return Some(index);
                 }
-                _ => {}
             }
         }
         None
@@ -213,13 +285,13 @@ impl SnailNumber {
         None
     }
 
-    fn magnitude(&self) -> u32 {
+    pub fn magnitude(&self) -> u32 {
         if self.elements.len() == 1 {
-            return self.get_number(0).unwrap();
+            self.get_number(0).unwrap()
         } else if self.elements.len() == 5 {
             let left = self.get_number(1).unwrap();
             let right = self.get_number(3).unwrap();
-            return 3 * left + 2 * right;
+            3 * left + 2 * right
         } else {
             let mut depth = 0;
             for i in 0..self.elements.len() {
@@ -253,9 +325,10 @@ impl SnailNumber {
     }
 }
 
-fn part1(lines:Vec<&str>) -> String {
-    lines.iter()
-        .map(|line| SnailNumber::parse(line))
+/// Magnitude of the sum of all numbers.
+pub fn part1(numbers:&[SnailNumber]) -> String {
+    numbers.iter()
+        .map(SnailNumber::from)
         .fold( SnailNumber{elements:vec![]}, |mut acc, mut elem| {
             acc.add(&mut elem);
             acc.reduce();
@@ -265,10 +338,8 @@ fn part1(lines:Vec<&str>) -> String {
 }
 
 
-fn part2(lines:Vec<&str>) -> String {
-    let numbers:Vec<SnailNumber> = lines.iter().map(|line| SnailNumber::parse(line))
-        .collect();
-
+/// Largest magnitude of the sum of any two different numbers.
+pub fn part2(numbers:&[SnailNumber]) -> Result<String> {
     let mut magnitudes = vec![];
 
     for i in 0..numbers.len() {
@@ -285,20 +356,14 @@ fn part2(lines:Vec<&str>) -> String {
         }
     }
 
-    magnitudes.iter().max().unwrap().to_string()
+    match magnitudes.iter().max() {
+        Some(magnitude) => Ok(magnitude.to_string()),
+        None => Err(SolveError::new("need at least two numbers")),
+    }
 }
 
 
-#[cfg(test)]
-mod tests {
-    // Note this useful idiom: importing names from outer (for mod tests) scope.
-    use super::*;
-    use util::Part::{Part1, Part2};
-
-    #[test]
-    fn test1() {
-
-        let input = "[[[0,[5,8]],[[1,7],[9,6]]],[[4,[1,2]],[[1,4],2]]]
+const EXAMPLE:&str = "[[[0,[5,8]],[[1,7],[9,6]]],[[4,[1,2]],[[1,4],2]]]
 [[[5,[2,8]],4],[5,[[9,9],0]]]
 [6,[[[6,2],[5,6]],[[7,6],[4,7]]]]
 [[[6,[0,7]],[0,9]],[4,[9,[9,0]]]]
@@ -309,37 +374,49 @@ mod tests {
 [[2,[[7,7],7]],[[5,8],[[9,3],[0,2]]]]
 [[[[5,2],5],[8,[3,7]]],[[5,[7,5]],[4,4]]]";
 
-        assert_eq!("4140", solve(input.to_string(), Part1));
+
+#[cfg(test)]
+mod tests {
+    // Note this useful idiom: importing names from outer (for mod tests) scope.
+    use super::*;
+    use util::solver::Puzzle;
+    use util::Part::{Part1, Part2};
+
+    #[test]
+    fn test1() {
+        assert_eq!("4140", Day18.solve(EXAMPLE, Part1).unwrap());
     }
 
     #[test]
     fn test_part1() {
         let input = include_str!("../../input_18.txt");
 
-        assert_eq!("3981", solve(input.to_string(), Part1));
+        assert_eq!("3981", Day18.solve(input, Part1).unwrap());
     }
 
     #[test]
     fn test2() {
+        assert_eq!("3993", Day18.solve(EXAMPLE, Part2).unwrap());
+    }
 
-        let input = "[[[0,[5,8]],[[1,7],[9,6]]],[[4,[1,2]],[[1,4],2]]]
-[[[5,[2,8]],4],[5,[[9,9],0]]]
-[6,[[[6,2],[5,6]],[[7,6],[4,7]]]]
-[[[6,[0,7]],[0,9]],[4,[9,[9,0]]]]
-[[[7,[6,4]],[3,[1,3]]],[[[5,5],1],9]]
-[[6,[[7,3],[3,2]]],[[[3,8],[5,7]],4]]
-[[[[5,4],[7,7]],8],[[8,3],8]]
-[[9,3],[[9,9],[6,[4,9]]]]
-[[2,[[7,7],7]],[[5,8],[[9,3],[0,2]]]]
-[[[[5,2],5],[8,[3,7]]],[[5,[7,5]],[4,4]]]";
-        assert_eq!("3993", solve(input.to_string(), Part2));
+    #[test]
+    fn test_parse() {
+        let error = |line| {
+            let error = SnailNumber::parse(1, line).unwrap_err();
+            (error.column.unwrap(), error.message)
+        };
+        assert_eq!((5, "invalid snailfish number".to_string()), error("[1,2"));
+        assert_eq!((5, "pair nested in more than 4 pairs".to_string()), error("[[[[[[1,2],3],4],5],6],7]"));
+        assert_eq!((2, "regular number larger than 9".to_string()), error("[4000000000,1]"));
+        assert_eq!((5, "pair nested in more than 4 pairs".to_string()), error(&"[".repeat(1_000_000)));
+        assert!(SnailNumber::parse(1, "[[[[1,2],3],4],5]").is_ok());
     }
 
     #[test]
     fn test_part2() {
         let input = include_str!("../../input_18.txt");
 
-        assert_eq!("4687", solve(input.to_string(), Part2));
+        assert_eq!("4687", Day18.solve(input, Part2).unwrap());
     }
 
 }
@@ -1,156 +1,762 @@
-use permute::permute;
-use super::Part;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::hash::Hash;
+use std::sync::Mutex;
+use super::error::{missing, parse_field, Result, SolveError};
+use super::params::{parse_value, unknown, Param, Params};
+use super::solver::{Example, Solver};
 
-pub fn solve(input : String, part: Part) -> String {
+pub struct Day19;
 
-    let lines:Vec<&str> = input.split("\n\n")
-        .collect();
+impl Solver for Day19 {
+    type Input = Report;
 
-    match part {
-        Part::Part1 => part1(lines),
-        Part::Part2 => part2(lines)
+    type Params = Day19Params;
+
+    fn name(&self) -> &'static str {
+        "Beacon Scanner"
+    }
+
+    fn day(&self) -> u8 {
+        19
+    }
+
+    fn examples(&self) -> Vec<Example> {
+        vec![Example{input:EXAMPLE, part1:Some("79"), part2:Some("3621")}]
+    }
+
+    fn parse(&self, input:&str) -> Result<Report> {
+        let mut line_no = 1;
+        let mut scanners = vec![];
+        for (i, elem) in input.split("\n\n").enumerate() {
+            if elem.trim().is_empty() {
+                continue;
+            }
+            scanners.push(Scanner::new(elem, i).map_err(|e| e.offset_lines(line_no - 1))?);
+            line_no += elem.lines().count() + 1;
+        }
+
+        Ok(Report::new(scanners))
+    }
+
+    fn part1(&self, report:&Report, params:&Day19Params) -> Result<String> {
+        report.with_alignment(params, |alignment| part1(alignment, params))
+    }
+
+    fn part2(&self, report:&Report, params:&Day19Params) -> Result<String> {
+        report.with_alignment(params, part2)
     }
 }
 
-#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
-struct Pos {
+#[derive(Debug, Clone, PartialEq)]
+pub struct Day19Params {
+    /// Beacons two scanners must have in common to be matched.
+    pub min_overlap:usize,
+    /// How far apart, along each axis, two readings of the same beacon may
+    /// be, at most the range of a scanner.
+    pub tolerance:i32,
+}
+
+impl Default for Day19Params {
+    fn default() -> Day19Params {
+        Day19Params{min_overlap:12, tolerance:0}
+    }
+}
+
+impl Params for Day19Params {
+    fn list(&self) -> Vec<Param> {
+        vec![
+            Param{name:"min_overlap", value:self.min_overlap.to_string(), description:"beacons two scanners must share"},
+            Param{name:"tolerance", value:self.tolerance.to_string(), description:"units a beacon reading may be off"},
+        ]
+    }
+
+    fn set(&mut self, name:&str, value:&str) -> Result<()> {
+        let invalid = || SolveError::new(&format!("invalid value '{}' for parameter {}", value, name));
+        match name {
+            "min_overlap" => self.min_overlap = Some(parse_value(name, value)?).filter(|&overlap| overlap >= 2).ok_or_else(invalid)?,
+            "tolerance" => self.tolerance = Some(parse_value(name, value)?).filter(|tolerance| (0..=RANGE).contains(tolerance)).ok_or_else(invalid)?,
+            _ => return Err(unknown(name, self)),
+        }
+        Ok(())
+    }
+}
+
+/// Position of a beacon or scanner.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash, Default, PartialOrd, Ord)]
+pub struct Pos {
     x:i32,
     y:i32,
     z:i32,
 }
 
-#[derive(Debug)]
-struct Scanner {
+/// Beacons detected by one scanner, relative to the scanner.
+#[derive(Debug, Clone)]
+pub struct Scanner {
     number:usize,
     beacons:Vec<Pos>,
-    permutation:usize,
+    /// Turns the scanner to match scanner 0.
+    rotation:Rotation,
+    /// How often each squared distance between two beacons occurs.
+    fingerprint:HashMap<i64, usize>,
 }
 
+/// How far a scanner sees along each axis.
+const RANGE:i32 = 1000;
+
+/// One of the 24 ways a scanner can be turned, as an integer matrix with
+/// determinant 1 so mirror images are left out.
+///
+/// ```
+/// use advent_of_code_2021::util::day_19::{Pos, Rotation};
+///
+/// let pos = Pos::new(1, 2, 3);
+/// let rotation = Rotation::all()[5];
+/// assert_eq!(pos, rotation.inverse().apply(&rotation.apply(&pos)));
+/// assert_eq!([[1, 0, 0], [0, 1, 0], [0, 0, 1]], Rotation::identity().matrix());
+/// ```
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct Rotation {
+    m:[[i32; 3]; 3],
+}
 
-impl Pos {
+impl Rotation {
+    pub fn identity() -> Rotation {
+        Rotation{m:[[1, 0, 0], [0, 1, 0], [0, 0, 1]]}
+    }
 
-    fn get_permutations(&self) -> Vec<Pos>{
-        let initial_state = vec![self.x, self.y, self.z];
-        let states = permute(initial_state);
-        let directions: Vec<(i32, i32, i32)> = vec![(1, 1, 1), (1, 1, -1), (1, -1, 1), (1, -1, -1), (-1, 1, 1), (-1, 1, -1), (-1, -1, 1), (-1, -1, -1)];
+    /// All 24 rotations, the identity first.
+    pub fn all() -> Vec<Rotation> {
+        let axes = [[0, 1, 2], [0, 2, 1], [1, 0, 2], [1, 2, 0], [2, 0, 1], [2, 1, 0]];
+        let signs = [(1, 1, 1), (1, 1, -1), (1, -1, 1), (1, -1, -1), (-1, 1, 1), (-1, 1, -1), (-1, -1, 1), (-1, -1, -1)];
+
+        let mut rotations = vec![];
+        for axes in axes.iter() {
+            for &(x, y, z) in signs.iter() {
+                let mut m = [[0; 3]; 3];
+                for (row, sign) in [x, y, z].iter().enumerate() {
+                    m[row][axes[row]] = *sign;
+                }
+                let rotation = Rotation{m};
+                if rotation.determinant() == 1 {
+                    rotations.push(rotation);
+                }
+            }
+        }
+        rotations
+    }
 
-        let mut permutations = vec![];
-        for state in states.iter().map(|s| s.as_slice()) {
-            for i in 0..directions.len() {
-                let (x,y,z) = directions.get(i).unwrap();
-                permutations.push(Pos{x:*x*state[0], y:*y*state[1], z:*z*state[2]})
+    /// Rows of the matrix, turning multiplies it with a column vector.
+    pub fn matrix(&self) -> [[i32; 3]; 3] {
+        self.m
+    }
+
+    /// Turns by `other` first and then by `self`.
+    pub fn compose(&self, other:&Rotation) -> Rotation {
+        let mut m = [[0; 3]; 3];
+        for (row, values) in m.iter_mut().enumerate() {
+            for (col, value) in values.iter_mut().enumerate() {
+                *value = (0..3).map(|k| self.m[row][k] * other.m[k][col]).sum();
             }
         }
+        Rotation{m}
+    }
 
-        permutations
+    /// The transpose, which undoes a rotation.
+    pub fn inverse(&self) -> Rotation {
+        let mut m = [[0; 3]; 3];
+        for (row, values) in m.iter_mut().enumerate() {
+            for (col, value) in values.iter_mut().enumerate() {
+                *value = self.m[col][row];
+            }
+        }
+        Rotation{m}
     }
 
-    fn _get_permutation(&self,variant:usize) -> Pos {
-        let v = self.get_permutations();
-        *v.get(variant).unwrap()
+    pub fn apply(&self, pos:&Pos) -> Pos {
+        let v = [pos.x, pos.y, pos.z];
+        let row = |r:&[i32; 3]| r[0] * v[0] + r[1] * v[1] + r[2] * v[2];
+        Pos{x:row(&self.m[0]), y:row(&self.m[1]), z:row(&self.m[2])}
     }
 
-    fn _get_scanner_candidate_pos(&self, other:&Pos) -> Pos {
-        let x = self.x - other.x;
-        let y = self.y - other.y;
-        let z = self.z - other.z;
-        Pos{x,y,z}
+    pub fn determinant(&self) -> i32 {
+        let m = &self.m;
+        m[0][0] * (m[1][1] * m[2][2] - m[1][2] * m[2][1])
+            - m[0][1] * (m[1][0] * m[2][2] - m[1][2] * m[2][0])
+            + m[0][2] * (m[1][0] * m[2][1] - m[1][1] * m[2][0])
+    }
+}
+
+impl Pos {
+    pub fn new(x:i32, y:i32, z:i32) -> Pos {
+        Pos{x, y, z}
+    }
+
+    pub fn x(&self) -> i32 {
+        self.x
+    }
+
+    pub fn y(&self) -> i32 {
+        self.y
+    }
+
+    pub fn z(&self) -> i32 {
+        self.z
     }
 
+    fn add(&self, other:&Pos) -> Pos {
+        Pos{x:self.x + other.x, y:self.y + other.y, z:self.z + other.z}
+    }
+
+    fn sub(&self, other:&Pos) -> Pos {
+        Pos{x:self.x - other.x, y:self.y - other.y, z:self.z - other.z}
+    }
+
+    /// Squared distance, which doesn't change when both positions are turned or moved.
+    fn distance(&self, other:&Pos) -> i64 {
+        let d = self.sub(other);
+        [d.x, d.y, d.z].iter().map(|&c| c as i64 * c as i64).sum()
+    }
+
+    /// Largest difference along one axis.
+    fn max_distance(&self, other:&Pos) -> i32 {
+        let d = self.sub(other);
+        d.x.abs().max(d.y.abs()).max(d.z.abs())
+    }
+
+    /// Bin of a grid with cells of `size` along each axis.
+    fn bin(&self, size:i32) -> Pos {
+        Pos{x:self.x.div_euclid(size), y:self.y.div_euclid(size), z:self.z.div_euclid(size)}
+    }
+
+    fn manhattan(&self, other:&Pos) -> i32 {
+        let d = self.sub(other);
+        d.x.abs() + d.y.abs() + d.z.abs()
+    }
 }
+
 impl Scanner {
 
-    fn new(lines:&str, number:usize) -> Scanner {
+    fn new(lines:&str, number:usize) -> Result<Scanner> {
         let mut it = lines.lines();
         let mut beacons = vec![];
-        it.next();
-        loop {
-            if let Some(line) = it.next() {
-                let mut cols = line.split(',');
-                let x = cols.next().unwrap().parse::<i32>().unwrap();
-                let y = cols.next().unwrap().parse::<i32>().unwrap();
-                let z = cols.next().unwrap().parse::<i32>().unwrap();
-                beacons.push(Pos {x,y,z});
-            } else {
-                break;
+        if !it.next().is_some_and(|header| header.starts_with("--- scanner")) {
+            return Err(SolveError::on_line(1, "expected '--- scanner N ---'"));
+        }
+
+        for (i, line) in it.enumerate() {
+            let line_no = i + 2;
+            let cols:Vec<&str> = line.split(',').collect();
+            if cols.len() != 3 {
+                return Err(missing(line_no, line, "coordinate, expected 'x,y,z'"));
+            }
+            let x = parse_field(line_no, line, cols[0])?;
+            let y = parse_field(line_no, line, cols[1])?;
+            let z = parse_field(line_no, line, cols[2])?;
+            beacons.push(Pos {x,y,z});
+        }
+
+        Ok(Scanner::with_beacons(number, beacons))
+    }
+
+    /// Scanner number `number` that saw `beacons`, not turned yet.
+    pub fn with_beacons(number:usize, beacons:Vec<Pos>) -> Scanner {
+        let mut fingerprint = HashMap::new();
+        for (i, a) in beacons.iter().enumerate() {
+            for b in beacons[i + 1..].iter() {
+                *fingerprint.entry(a.distance(b)).or_insert(0) += 1;
             }
         }
+        Scanner{number, beacons, rotation:Rotation::identity(), fingerprint}
+    }
 
-        Scanner{number, beacons,permutation:0}
+    pub fn number(&self) -> usize {
+        self.number
     }
 
-    fn _next(&mut self) {
-        self.permutation = self.permutation + 1;
+    pub fn beacons(&self) -> &[Pos] {
+        &self.beacons
     }
 
-    fn _get_pos(&self, index:usize) -> Pos {
-        self.beacons.get(index).unwrap()._get_permutation(self.permutation)
+    /// Turns the beacons like scanner 0 once aligned, the identity before.
+    pub fn rotation(&self) -> Rotation {
+        self.rotation
     }
 
+    /// The beacons turned by every rotation.
+    fn orientations(&self) -> Vec<(Rotation, Vec<Pos>)> {
+        Rotation::all().into_iter()
+            .map(|rotation| (rotation, self.beacons.iter().map(|pos| rotation.apply(pos)).collect()))
+            .collect()
+    }
 }
 
+/// The scanners of the puzzle input. Aligning them is most of the work for
+/// either part, so the alignment is kept for the parameters it was done with.
+pub struct Report {
+    scanners:Vec<Scanner>,
+    alignment:Mutex<Option<(Day19Params, Alignment)>>,
+}
 
+impl Report {
+    pub fn new(scanners:Vec<Scanner>) -> Report {
+        Report{scanners, alignment:Mutex::new(None)}
+    }
 
-fn match_scanners(_scanner0:&Scanner, _scanner:&mut Scanner) {
-    /*
-    for pos_scanner0 in scanner0.beacons.iter() {
-        for i in 0..24 {
-            for index in 0..scanner.beacons.len() {
-                let pos = scanner.get_pos(index);
-                println!("pos no {}:{:?}", index, pos);
+    pub fn scanners(&self) -> &[Scanner] {
+        &self.scanners
+    }
 
+    /// Calls `f` with the scanners aligned using `params`, aligning them only
+    /// if that wasn't done before with the same parameters.
+    pub fn with_alignment<T, F:FnOnce(&Alignment) -> T>(&self, params:&Day19Params, f:F) -> Result<T> {
+        let mut cached = self.alignment.lock().unwrap();
+        let stale = match *cached {
+            Some((ref aligned_with, _)) => aligned_with != params,
+            None => true,
+        };
+        if stale {
+            *cached = Some((params.clone(), align(&self.scanners, params)?));
+        }
+        Ok(f(&cached.as_ref().unwrap().1))
+    }
+}
+
+/// Scanners turned and placed relative to scanner 0.
+#[derive(Debug, Clone)]
+pub struct Alignment {
+    /// The scanners with the rotation that turns them like scanner 0.
+    pub scanners:Vec<Scanner>,
+    /// Position of each scanner as seen from scanner 0.
+    pub positions:Vec<Pos>,
+    /// Every beacon as seen from scanner 0.
+    pub beacons:HashSet<Pos>,
+    /// How each scanner but scanner 0 was matched.
+    pub matches:Vec<Match>,
+}
+
+/// A scanner matched with one aligned before it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Match {
+    /// Index of the scanner aligned before.
+    pub reference:usize,
+    pub scanner:usize,
+    /// Beacons the two have in common.
+    pub overlap:usize,
+    /// Share of the beacons each should see of the other that it does see, 1
+    /// when no reading is missing.
+    pub confidence:f64,
+}
+
+/// Pairs of scanners that share enough beacon distances to possibly overlap,
+/// by scanner index. Scanners sharing `min_overlap` beacons share the
+/// distances between every two of them. Noisy distances don't match exactly,
+/// so with a tolerance they are compared by `noisy_fingerprint` instead.
+fn candidates(scanners:&[Scanner], params:&Day19Params) -> Vec<Vec<usize>> {
+    let shared = if params.tolerance == 0 {
+        shared_counts(scanners.iter().map(|scanner| &scanner.fingerprint))
+    } else {
+        let fingerprints:Vec<_> = scanners.iter().map(|scanner| noisy_fingerprint(&scanner.beacons, params.tolerance)).collect();
+        shared_counts(fingerprints.iter())
+    };
+
+    let min_shared = params.min_overlap.saturating_mul(params.min_overlap - 1) / 2;
+    let mut candidates = vec![vec![]; scanners.len()];
+    for (&(a, b), _) in shared.iter().filter(|(_, &count)| count >= min_shared) {
+        candidates[a].push(b);
+        candidates[b].push(a);
+    }
+    candidates.iter_mut().for_each(|found| found.sort_unstable());
+    candidates
+}
+
+/// Keys two fingerprints have in common, counted as often as both have them,
+/// for every pair of fingerprints that has any.
+fn shared_counts<'a, K:'a + Eq + Hash + Clone, I:Iterator<Item = &'a HashMap<K, usize>>>(fingerprints:I) -> HashMap<(usize, usize), usize> {
+    let mut index:HashMap<K, Vec<(usize, usize)>> = HashMap::new();
+    for (i, fingerprint) in fingerprints.enumerate() {
+        for (key, &count) in fingerprint.iter() {
+            index.entry(key.clone()).or_default().push((i, count));
+        }
+    }
+
+    let mut shared:HashMap<(usize, usize), usize> = HashMap::new();
+    for found in index.values() {
+        for (n, &(a, count_a)) in found.iter().enumerate() {
+            for &(b, count_b) in found[n + 1..].iter() {
+                *shared.entry((a, b)).or_insert(0) += count_a.min(count_b);
             }
-            scanner.next();
+        }
+    }
+    shared
+}
 
+/// Fingerprint for readings up to `tolerance` off. The sorted absolute
+/// differences between two beacons don't change when the beacons are turned
+/// or moved, and those of two readings of the same beacons are at most twice
+/// the tolerance apart. So they share a cell of `4 * tolerance + 2` in at
+/// least one of 8 grids, each shifted by half a cell along some of the axes.
+/// A pair of beacons is counted once in every grid.
+fn noisy_fingerprint(beacons:&[Pos], tolerance:i32) -> HashMap<(usize, [i32; 3]), usize> {
+    let width = 4 * tolerance + 2;
+    let mut fingerprint = HashMap::new();
+    for (i, a) in beacons.iter().enumerate() {
+        for b in beacons[i + 1..].iter() {
+            let d = a.sub(b);
+            let mut sorted = [d.x.abs(), d.y.abs(), d.z.abs()];
+            sorted.sort_unstable();
+
+            for grid in 0..8 {
+                let mut cell = [0; 3];
+                for (axis, value) in cell.iter_mut().enumerate() {
+                    let shift = (grid >> axis & 1) as i32 * width / 2;
+                    *value = (sorted[axis] + shift) / width;
+                }
+                *fingerprint.entry((grid, cell)).or_insert(0) += 1;
+            }
         }
+    }
+    fingerprint
+}
 
+/// Beacons of `beacons` moved by `offset` that are within `tolerance` of a
+/// beacon of `reference`, each reference beacon used once.
+fn overlap(reference:&[Pos], beacons:&[Pos], offset:&Pos, tolerance:i32) -> usize {
+    let mut used = vec![false; reference.len()];
+    beacons.iter()
+        .filter(|pos| {
+            let moved = pos.add(offset);
+            match (0..reference.len()).find(|&i| !used[i] && reference[i].max_distance(&moved) <= tolerance) {
+                Some(i) => {
+                    used[i] = true;
+                    true
+                }
+                None => false,
+            }
+        })
+        .count()
+}
+
+/// Finds the rotation and the position that put at least `min_overlap` of the
+/// beacons in `orientations` on `reference`, with the overlap.
+///
+/// Every pair of readings votes for the offset between them. The votes of
+/// the readings of one beacon lie within a cube of `2 * tolerance + 1`, so
+/// they fall in one block of 2x2x2 bins of that size. The votes around the
+/// median of the best blocks give the offset to try.
+fn match_scanners(reference:&[Pos], orientations:&[(Rotation, Vec<Pos>)], params:&Day19Params) -> Option<(Rotation, Pos, usize)> {
+    let size = 2 * params.tolerance + 1;
+    let span = if params.tolerance == 0 { 1 } else { 2 };
+    let block = |corner:Pos| {
+        (0..span).flat_map(move |x| (0..span).flat_map(move |y| (0..span).map(move |z| corner.add(&Pos{x, y, z}))))
+    };
+
+    for (rotation, beacons) in orientations.iter() {
+        let offsets:Vec<Pos> = reference.iter()
+            .flat_map(|known| beacons.iter().map(move |pos| known.sub(pos)))
+            .collect();
+
+        // Counted for every block the bin of an offset is in, so the lowest bin may be empty
+        let mut blocks:HashMap<Pos, usize> = HashMap::new();
+        for offset in offsets.iter() {
+            for corner in block(offset.bin(size).sub(&Pos{x:span - 1, y:span - 1, z:span - 1})) {
+                *blocks.entry(corner).or_insert(0) += 1;
+            }
+        }
+        let mut blocks:Vec<(usize, Pos)> = blocks.into_iter()
+            .filter(|&(_, votes)| votes >= params.min_overlap)
+            .map(|(corner, votes)| (votes, corner))
+            .collect();
+        blocks.sort_unstable_by(|a, b| b.cmp(a));
+
+        for (_, corner) in blocks {
+            let votes:Vec<Pos> = offsets.iter().cloned()
+                .filter(|offset| offset.bin(size).sub(&corner).bin(span) == Pos::default())
+                .collect();
+            let median = |axis:fn(&Pos) -> i32| {
+                let mut values:Vec<i32> = votes.iter().map(axis).collect();
+                values.sort_unstable();
+                values[values.len() / 2]
+            };
+            let median = Pos{x:median(|pos| pos.x), y:median(|pos| pos.y), z:median(|pos| pos.z)};
+
+            // Votes of one beacon are at most twice the tolerance apart, so
+            // the middle of their range is within the tolerance of each
+            let cluster:Vec<&Pos> = votes.iter().filter(|vote| vote.max_distance(&median) <= 2 * params.tolerance).collect();
+            let middle = |axis:fn(&Pos) -> i32| {
+                let values = cluster.iter().map(|pos| axis(pos));
+                (values.clone().min().unwrap() + values.max().unwrap()).div_euclid(2)
+            };
+            let offset = Pos{x:middle(|pos| pos.x), y:middle(|pos| pos.y), z:middle(|pos| pos.z)};
+
+            let overlap = overlap(reference, beacons, &offset, params.tolerance);
+            if overlap >= params.min_overlap {
+                return Some((*rotation, offset, overlap));
+            }
+        }
     }
+    None
+}
 
-     */
-    //println!("{:?}", permutations);
+/// Share of the beacons in range of both scanners that both see, `beacons`
+/// already turned and moved by `offset`.
+fn confidence(reference:&[Pos], beacons:&[Pos], offset:&Pos, overlap:usize, tolerance:i32) -> f64 {
+    let in_range = |pos:&Pos, scanner:&Pos| pos.max_distance(scanner) <= RANGE + tolerance;
+    let expected = reference.iter().filter(|pos| in_range(pos, offset)).count()
+        + beacons.iter().filter(|pos| in_range(pos, &Pos::default())).count();
+    (2 * overlap) as f64 / expected.max(2 * overlap) as f64
 }
 
-fn part1(lines:Vec<&str>) -> String {
+/// Aligns every scanner with scanner 0 by chaining its transform to that of
+/// an overlapping scanner aligned before it.
+pub fn align(scanners:&[Scanner], params:&Day19Params) -> Result<Alignment> {
+    if scanners.is_empty() {
+        return Err(SolveError::new("no scanners"));
+    }
+
+    let candidates = candidates(scanners, params);
+    let orientations:Vec<Vec<(Rotation, Vec<Pos>)>> = scanners.iter().map(|scanner| scanner.orientations()).collect();
+    // Rotation and position that take the coordinates of a scanner to those of scanner 0
+    let mut placed:Vec<Option<(Rotation, Pos)>> = vec![None; scanners.len()];
+    placed[0] = Some((Rotation::identity(), Pos::default()));
+    let mut matches = vec![];
+
+    let mut queue = VecDeque::from(vec![0]);
+    while let Some(reference) = queue.pop_front() {
+        let (to_zero, origin) = placed[reference].unwrap();
+        let known = &scanners[reference].beacons;
+        for &i in candidates[reference].iter() {
+            if placed[i].is_some() {
+                continue;
+            }
+            if let Some((rotation, offset, overlap)) = match_scanners(known, &orientations[i], params) {
+                let moved:Vec<Pos> = scanners[i].beacons.iter().map(|pos| rotation.apply(pos).add(&offset)).collect();
+                let confidence = confidence(known, &moved, &offset, overlap, params.tolerance);
+                matches.push(Match{reference, scanner:i, overlap, confidence});
+
+                placed[i] = Some((to_zero.compose(&rotation), to_zero.apply(&offset).add(&origin)));
+                queue.push_back(i);
+            }
+        }
+    }
 
-    let mut scanners:Vec<Scanner> = lines.iter().enumerate().map(|(i,elem)| Scanner::new(elem, i) ).collect();
+    let mut aligned = scanners.to_vec();
+    let mut positions = vec![];
+    let mut beacons = HashSet::new();
+    for (scanner, placement) in aligned.iter_mut().zip(placed) {
+        let (rotation, position) = placement.ok_or_else(|| {
+            SolveError::new(&format!("scanner {} doesn't overlap with the other scanners", scanner.number))
+        })?;
+        beacons.extend(scanner.beacons.iter().map(|pos| rotation.apply(pos).add(&position)));
+        scanner.rotation = rotation;
+        positions.push(position);
+    }
 
-    match_scanners(&scanners.remove(0), scanners.get_mut(1).unwrap());
-    //println!("{:?}", scanners.first().unwrap());
-    "1".to_string()
+    Ok(Alignment{scanners:aligned, positions, beacons, matches})
 }
 
+/// Number of beacons. Noisy readings of one beacon count as one when they
+/// are within the tolerance of each other.
+pub fn part1(alignment:&Alignment, params:&Day19Params) -> String {
+    let beacons = &alignment.beacons;
+    if params.tolerance == 0 {
+        return beacons.len().to_string();
+    }
+
+    let mut unique:Vec<Pos> = vec![];
+    let mut sorted:Vec<Pos> = beacons.iter().cloned().collect();
+    sorted.sort_unstable();
+    for pos in sorted {
+        if !unique.iter().any(|known| known.max_distance(&pos) <= params.tolerance) {
+            unique.push(pos);
+        }
+    }
+    unique.len().to_string()
+}
 
-fn part2(_lines:Vec<&str>) -> String {
-    "1".to_string()
+/// Largest Manhattan distance between two scanners.
+pub fn part2(alignment:&Alignment) -> String {
+    let positions = &alignment.positions;
+    let max = positions.iter()
+        .flat_map(|a| positions.iter().map(move |b| a.manhattan(b)))
+        .max()
+        .unwrap();
+    max.to_string()
 }
 
+const EXAMPLE:&str = "--- scanner 0 ---
+404,-588,-901
+528,-643,409
+-838,591,734
+390,-675,-793
+-537,-823,-458
+-485,-357,347
+-345,-311,381
+-661,-816,-575
+-876,649,763
+-618,-824,-621
+553,345,-567
+474,580,667
+-447,-329,318
+-584,868,-557
+544,-627,-890
+564,392,-477
+455,729,728
+-892,524,684
+-689,845,-530
+423,-701,434
+7,-33,-71
+630,319,-379
+443,580,662
+-789,900,-551
+459,-707,401
+
+--- scanner 1 ---
+686,422,578
+605,423,415
+515,917,-361
+-336,658,858
+95,138,22
+-476,619,847
+-340,-569,-846
+567,-361,727
+-460,603,-452
+669,-402,600
+729,430,532
+-500,-761,534
+-322,571,750
+-466,-666,-811
+-429,-592,574
+-355,545,-477
+703,-491,-529
+-328,-685,520
+413,935,-424
+-391,539,-444
+586,-435,557
+-364,-763,-893
+807,-499,-711
+755,-354,-619
+553,889,-390
+
+--- scanner 2 ---
+649,640,665
+682,-795,504
+-784,533,-524
+-644,584,-595
+-588,-843,648
+-30,6,44
+-674,560,763
+500,723,-460
+609,671,-379
+-555,-800,653
+-675,-892,-343
+697,-426,-610
+578,704,681
+493,664,-388
+-671,-858,530
+-667,343,800
+571,-461,-707
+-138,-166,112
+-889,563,-600
+646,-828,498
+640,759,510
+-630,509,768
+-681,-892,-333
+673,-379,-804
+-742,-814,-386
+577,-820,562
+
+--- scanner 3 ---
+-589,542,597
+605,-692,669
+-500,565,-823
+-660,373,557
+-458,-679,-417
+-488,449,543
+-626,468,-788
+338,-750,-386
+528,-832,-391
+562,-778,733
+-938,-730,414
+543,643,-506
+-524,371,-870
+407,773,750
+-104,29,83
+378,-903,-323
+-778,-728,485
+426,699,580
+-438,-605,-362
+-469,-447,-387
+509,732,623
+647,635,-688
+-868,-804,481
+614,-800,639
+595,780,-596
+
+--- scanner 4 ---
+727,592,562
+-293,-554,779
+441,611,-461
+-714,465,-776
+-743,427,-804
+-660,-479,-426
+832,-632,460
+927,-485,-438
+408,393,-506
+466,436,-512
+110,16,151
+-258,-428,682
+-393,719,612
+-211,-452,876
+808,-476,-593
+-575,615,604
+-485,667,467
+-680,325,-822
+-627,-443,-432
+872,-547,-609
+833,512,582
+807,604,487
+839,-516,451
+891,-625,532
+-652,-548,-490
+30,-46,-14";
+
 
 #[cfg(test)]
 mod tests {
-    use std::collections::HashSet;
     // Note this useful idiom: importing names from outer (for mod tests) scope.
     use super::*;
+    use util::random::Rng;
+    use util::solver::Puzzle;
     use util::Part::{Part1, Part2};
 
 
 
     #[test]
-    fn test91() {
-        let pos = Pos{x:1,y:2,z:3};
-
-        let p = pos.get_permutations();
-        println!("{}, {:?}", p.len(), p);
-        let mut s = HashSet::new();
-        for n in p {
-            s.insert(n);
+    fn test_rotation() {
+        let rotations = Rotation::all();
+        let unique:HashSet<Rotation> = rotations.iter().cloned().collect();
+        assert_eq!((24, 24), (rotations.len(), unique.len()));
+        assert_eq!(Rotation::identity(), rotations[0]);
+        assert!(rotations.iter().all(|rotation| rotation.determinant() == 1));
+
+        for a in rotations.iter() {
+            assert_eq!(Rotation::identity(), a.compose(&a.inverse()));
+            for b in rotations.iter() {
+                assert!(unique.contains(&a.compose(b)));
+            }
         }
 
-        println!("{}, {:?}", s.len(), s);
-
-
+        let pos = Pos{x:1,y:2,z:3};
+        let turned:HashSet<Pos> = rotations.iter().map(|rotation| rotation.apply(&pos)).collect();
+        assert_eq!(24, turned.len());
+        let (a, b) = (rotations[5], rotations[17]);
+        assert_eq!(a.apply(&b.apply(&pos)), a.compose(&b).apply(&pos));
+        assert_eq!(pos, a.inverse().apply(&a.apply(&pos)));
     }
-        #[test]
+
+    #[test]
     fn test1() {
+        assert_eq!("79", Day19.solve(EXAMPLE, Part1).unwrap());
 
         let input = "--- scanner 0 ---
 -1,-1,1
@@ -192,28 +798,169 @@ mod tests {
 -6,-4,-5
 0,7,-8";
 
-        assert_eq!("1", solve(input.to_string(), Part1));
+        // The same beacons seen from five orientations
+        let report = Day19.parse(input).unwrap();
+        let scanners = report.scanners();
+        let reference:HashSet<Pos> = scanners[0].beacons.iter().cloned().collect();
+        for scanner in scanners.iter() {
+            let matching = scanner.orientations().into_iter()
+                .filter(|(_, beacons)| beacons.iter().cloned().collect::<HashSet<Pos>>() == reference)
+                .count();
+            assert_eq!(1, matching);
+        }
     }
 
     #[test]
     fn test_part1() {
         let input = include_str!("../../input_19.txt");
 
-        assert_eq!("1", solve(input.to_string(), Part1));
+        assert_eq!("425", Day19.solve(input, Part1).unwrap());
     }
 
     #[test]
     fn test2() {
-
-        let input = "";
-        assert_eq!("1", solve(input.to_string(), Part2));
+        assert_eq!("3621", Day19.solve(EXAMPLE, Part2).unwrap());
+
+        // Scanner 1 sees 12 of the beacons of scanner 0 from (100,-20,5), turned
+        let reference:Vec<Pos> = (0..20).map(|i| Pos{x:i * 37 % 101, y:i * i % 53 - 20, z:-i * 11}).collect();
+        let position = Pos::new(100, -20, 5);
+        let mut seen:Vec<Pos> = reference[8..].iter().map(|pos| Rotation::all()[17].apply(&pos.sub(&position))).collect();
+        seen.push(Pos{x:999, y:0, z:0});
+
+        let scanners = vec![Scanner::with_beacons(0, reference), Scanner::with_beacons(1, seen)];
+        let alignment = align(&scanners, &Day19Params::default()).unwrap();
+        assert_eq!(vec![Pos::default(), position], alignment.positions);
+        assert_eq!((100, -20, 5), (alignment.positions[1].x(), alignment.positions[1].y(), alignment.positions[1].z()));
+        assert_eq!(Rotation::all()[17].inverse(), alignment.scanners[1].rotation());
+        assert_eq!(21, alignment.beacons.len());
+        assert_eq!("125", part2(&alignment));
+
+        let apart = vec![scanners[0].clone(), Scanner::with_beacons(1, vec![Pos::default()])];
+        assert_eq!("scanner 1 doesn't overlap with the other scanners", align(&apart, &Day19Params::default()).unwrap_err().message);
+        assert!(Day19.solve("", Part2).is_err());
     }
 
     #[test]
     fn test_part2() {
         let input = include_str!("../../input_19.txt");
 
-        assert_eq!("1", solve(input.to_string(), Part2));
+        assert_eq!("13354", Day19.solve(input, Part2).unwrap());
     }
 
+    #[test]
+    fn test_fingerprint() {
+        let input = include_str!("../../input_19.txt");
+        let report = Day19.parse(input).unwrap();
+        let scanners = report.scanners();
+
+        let scanner = &scanners[3];
+        let moved:Vec<Pos> = scanner.beacons.iter().map(|pos| Rotation::all()[9].apply(pos).add(&Pos{x:-7, y:300, z:12})).collect();
+        assert_eq!(scanner.fingerprint, Scanner::with_beacons(3, moved).fingerprint);
+
+        let noisy = candidates(scanners, &Day19Params{min_overlap:12, tolerance:1});
+        let candidates = candidates(scanners, &Day19Params::default());
+        let pairs:usize = candidates.iter().map(|found| found.len()).sum::<usize>() / 2;
+        assert!(candidates.iter().all(|found| !found.is_empty()));
+        assert!(pairs < scanners.len() * 3, "{} candidate pairs", pairs);
+
+        // Distances that match exactly share a cell too, coarse cells let more pairs through
+        let noisy_pairs:usize = noisy.iter().map(|found| found.len()).sum::<usize>() / 2;
+        assert!(candidates.iter().zip(noisy.iter()).all(|(exact, noisy)| exact.iter().all(|b| noisy.contains(b))));
+        assert!(noisy_pairs < scanners.len() * (scanners.len() - 1) / 4, "{} noisy candidate pairs", noisy_pairs);
+    }
+
+    /// Scanners in a row, each sharing 13 beacons with the next one.
+    fn chain(length:usize) -> Vec<Scanner> {
+        let mut rng = Rng::new(19);
+        let mut random = move || rng.range(-500, 500) as i32;
+        let blocks:Vec<Vec<Pos>> = (0..=length as i32)
+            .map(|i| (0..13).map(|_| Pos{x:i * 1000 + random(), y:random(), z:random()}).collect())
+            .collect();
+
+        let rotations = Rotation::all();
+        (0..length)
+            .map(|i| {
+                let position = Pos{x:i as i32 * 1000 + 500, y:random(), z:random()};
+                let rotation = rotations[i % rotations.len()];
+                let beacons = blocks[i].iter().chain(blocks[i + 1].iter())
+                    .map(|pos| rotation.apply(&pos.sub(&position)))
+                    .collect();
+                Scanner::with_beacons(i, beacons)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_many_scanners() {
+        let scanners = chain(200);
+        let candidates = candidates(&scanners, &Day19Params::default());
+        assert_eq!(vec![0, 2], candidates[1]);
+
+        let alignment = align(&scanners, &Day19Params::default()).unwrap();
+        assert_eq!(201 * 13, alignment.beacons.len());
+        // Scanner 0 isn't turned, so only the row offset is left along x
+        assert_eq!(199 * 1000, alignment.positions[199].x);
+    }
+
+    #[test]
+    fn test_confidence() {
+        let input = include_str!("../../input_19.txt");
+        let report = Day19.parse(input).unwrap();
+        let alignment = align(report.scanners(), &Day19Params::default()).unwrap();
+
+        assert_eq!(report.scanners().len() - 1, alignment.matches.len());
+        assert!(alignment.matches.iter().all(|found| found.overlap >= 12 && found.confidence == 1.0));
+    }
+
+    #[test]
+    fn test_params() {
+        let params = |assignments:&[(&str, &str)]| {
+            let mut params = Day19Params::default();
+            assignments.iter().try_for_each(|(name, value)| params.set(name, value)).map(|_| params)
+        };
+        assert_eq!(2, params(&[]).unwrap().list().len());
+        assert!(params(&[("min_overlap", "1")]).is_err());
+        assert!(params(&[("tolerance", "-1")]).is_err());
+        assert!(params(&[("tolerance", "2147483647")]).is_err());
+        assert!(params(&[("noise", "1")]).is_err());
+
+        let input = include_str!("../../input_19.txt");
+        let assignments = vec![("min_overlap".to_string(), "6".to_string())];
+        assert_eq!("425", Day19.solve_with(input, Part1, &assignments).unwrap());
+
+        let huge = params(&[("min_overlap", &usize::MAX.to_string())]).unwrap();
+        assert!(align(&chain(3), &huge).is_err());
+    }
+
+    #[test]
+    fn test_noisy() {
+        // Readings off by up to 1, and one shared beacon of every other scanner missing
+        let mut offsets = [-1, 0, 1].iter().cycle();
+        let mut scanners = chain(8);
+        for (i, scanner) in scanners.iter_mut().enumerate() {
+            let mut beacons:Vec<Pos> = scanner.beacons.iter()
+                .map(|pos| pos.add(&Pos{x:*offsets.next().unwrap(), y:*offsets.next().unwrap(), z:0}))
+                .collect();
+            if i % 2 == 1 {
+                beacons.remove(20);
+            }
+            *scanner = Scanner::with_beacons(i, beacons);
+        }
+
+        assert!(align(&scanners, &Day19Params::default()).is_err());
+        let params = Day19Params{min_overlap:10, tolerance:2};
+
+        // Neighbours are still found from the distances, without trying every pair
+        let found = candidates(&scanners, &params);
+        let pairs:usize = found.iter().map(|found| found.len()).sum::<usize>() / 2;
+        assert!((1..8).all(|i| found[i].contains(&(i - 1))));
+        assert!(pairs < 8 * 7 / 2, "{} candidate pairs", pairs);
+        let alignment = align(&scanners, &params).unwrap();
+        assert_eq!(7, alignment.matches.len());
+        assert!(alignment.matches.iter().all(|found| found.overlap >= 12 && found.confidence > 0.5), "{:?}", alignment.matches);
+        assert!(alignment.matches.iter().any(|found| found.confidence < 1.0));
+        assert!((alignment.positions[7].x - 7000).abs() <= 7 * 2);
+        // Only the beacon missing from the last scanner isn't seen at all
+        assert_eq!((9 * 13 - 1).to_string(), part1(&alignment, &params));
+    }
 }
@@ -1,18 +1,82 @@
-use super::Part;
+use super::error::{check_grid, Result, SolveError};
+use super::params::{parse_value, per_part, unknown, Param, Params};
+use super::solver::{Example, Solver};
 
-pub fn solve(input : String, part: Part) -> String {
+pub struct Day20;
 
-    let lines:Vec<&str> = input.split("\n\n")
-        .collect();
+impl Solver for Day20 {
+    type Input = (String, Image);
 
-    match part {
-        Part::Part1 => part1(lines),
-        Part::Part2 => part2(lines)
+    type Params = Day20Params;
+
+    fn name(&self) -> &'static str {
+        "Trench Map"
+    }
+
+    fn day(&self) -> u8 {
+        20
+    }
+
+    fn examples(&self) -> Vec<Example> {
+        vec![Example{input:EXAMPLE, part1:Some("35"), part2:Some("3351")}]
+    }
+
+    fn parse(&self, input:&str) -> Result<(String, Image)> {
+        let lines:Vec<&str> = input.split("\n\n").collect();
+        let algo_lines = lines.first().unwrap();
+        for (i, line) in algo_lines.lines().enumerate() {
+            if let Some(pos) = line.find(|c| c != '#' && c != '.') {
+                return Err(SolveError::at(i + 1, pos + 1, "expected '#' or '.'"));
+            }
+        }
+
+        let algo:String = algo_lines.chars().filter(|c| *c != '\n').collect();
+        if algo.len() != 512 {
+            return Err(SolveError::new(&format!("algorithm has {} entries, expected 512", algo.len())));
+        }
+
+        let image = match lines.get(1) {
+            Some(image) => Image::parse(image).map_err(|e| e.offset_lines(algo_lines.lines().count() + 1))?,
+            None => return Err(SolveError::new("missing input image")),
+        };
+
+        Ok((algo, image))
+    }
+
+    fn part1(&self, image:&(String, Image), params:&Day20Params) -> Result<String> {
+        Ok(enhance(&image.0, &image.1, params.rounds.unwrap_or(2)))
+    }
+
+    fn part2(&self, image:&(String, Image), params:&Day20Params) -> Result<String> {
+        Ok(enhance(&image.0, &image.1, params.rounds.unwrap_or(50)))
     }
 }
 
+#[derive(Debug, Clone, Default)]
+pub struct Day20Params {
+    /// Enhancement rounds, 2 for part 1 and 50 for part 2 when not set.
+    pub rounds:Option<usize>,
+}
 
-struct Image {
+impl Params for Day20Params {
+    fn list(&self) -> Vec<Param> {
+        vec![Param{name:"rounds", value:per_part(&self.rounds, 2, 50), description:"enhancement rounds"}]
+    }
+
+    fn set(&mut self, name:&str, value:&str) -> Result<()> {
+        match name {
+            "rounds" => self.rounds = Some(parse_value(name, value)?),
+            _ => return Err(unknown(name, self)),
+        }
+        Ok(())
+    }
+}
+
+
+
+/// Image with the state of the infinite surrounding pixels.
+#[derive(Clone)]
+pub struct Image {
     width:i32,
     height:i32,
     infinite_pixel:bool,
@@ -20,26 +84,16 @@ struct Image {
 }
 
 impl Image {
-    fn parse(input:&str) -> Image {
-        let mut pixels = vec![];
+    fn parse(input:&str) -> Result<Image> {
         let lines:Vec<&str> = input.lines().collect();
-        for y in 0..lines.len() {
-            let mut image_col = vec![];
-            let line = lines.get(y).unwrap();
-            for ch in line.chars() {
-                image_col.push(
-                    match ch {
-                        '#' => true,
-                        '.' => false,
-                        _ => panic!("Invalid input"),
-                    }
-                )
-            }
-            pixels.push(image_col);
-        }
+        check_grid(&lines, |ch| ch == '#' || ch == '.')?;
+
+        let pixels:Vec<Vec<bool>> = lines.iter()
+            .map(|line| line.chars().map(|ch| ch == '#').collect())
+            .collect();
         let height = pixels.len() as i32;
         let width = pixels.first().unwrap().len() as i32;
-        Image{infinite_pixel:false,pixels,height, width}
+        Ok(Image{infinite_pixel:false,pixels,height, width})
     }
 
     fn generate_next(&self, algo:&str) -> Image {
@@ -77,7 +131,7 @@ impl Image {
         for y in (yp-1)..=(yp+1) {
             for x in (xp-1)..=(xp+1) {
                 // If outside image, check infinite pixel...
-                if x < 0 || x >= self.width as i32 || y < 0 || y >= self.height as i32 {
+                if x < 0 || x >= self.width || y < 0 || y >= self.height {
                     algo_offset = (algo_offset << 1) + self.infinite_pixel as usize;
                 } else {
                     let pixel = *self.pixels.get(y as usize).unwrap().get(x as usize).unwrap() as usize;
@@ -93,41 +147,19 @@ impl Image {
 }
 
 
-fn part1(lines:Vec<&str>) -> String {
-    let algo:String = lines.get(0).unwrap().chars().filter(|c| *c != '\n').collect();
-    let mut next_image = Image::parse(lines.get(1).unwrap());
-
-    for _ in 0..2 {
-        next_image = next_image.generate_next(algo.as_str());
-    }
-
-    next_image.count_pixels().to_string()
-}
-
-
-fn part2(lines:Vec<&str>) -> String {
-    let algo:String = lines.get(0).unwrap().chars().filter(|c| *c != '\n').collect();
-    let mut next_image = Image::parse(lines.get(1).unwrap());
+/// Number of lit pixels after `rounds` rounds of enhancement.
+pub fn enhance(algo:&str, image:&Image, rounds:usize) -> String {
+    let mut next_image = image.clone();
 
-    for _ in 0..50 {
-        next_image = next_image.generate_next(algo.as_str());
+    for _ in 0..rounds {
+        next_image = next_image.generate_next(algo);
     }
 
     next_image.count_pixels().to_string()
 }
 
 
-#[cfg(test)]
-mod tests {
-    // Note this useful idiom: importing names from outer (for mod tests) scope.
-    use super::*;
-    use util::Part::{Part1, Part2};
-
-
-    #[test]
-    fn test1() {
-
-        let input = "..#.#..#####.#.#.#.###.##.....###.##.#..###.####..#####..#....#..#..##..##
+const EXAMPLE:&str = "..#.#..#####.#.#.#.###.##.....###.##.#..###.####..#####..#....#..#..##..##
 #..######.###...####..#..#####..##..#.#####...##.#.#..#.##..#.#......#.###
 .######.###.####...#.##.##..#..#..#####.....#.#....###..#.##......#.....#.
 .#..#..##..#...##.######.####.####.#.#...#.......#..#.#.#...####.##.#.....
@@ -141,40 +173,47 @@ mod tests {
 ..#..
 ..###";
 
-        assert_eq!("35", solve(input.to_string(), Part1));
+
+#[cfg(test)]
+mod tests {
+    // Note this useful idiom: importing names from outer (for mod tests) scope.
+    use super::*;
+    use util::solver::Puzzle;
+    use util::Part::{Part1, Part2};
+
+
+    #[test]
+    fn test1() {
+        assert_eq!("35", Day20.solve(EXAMPLE, Part1).unwrap());
     }
 
     #[test]
     fn test_part1() {
         let input = include_str!("../../input_20.txt");
         //5278
-        assert_eq!("5081", solve(input.to_string(), Part1));
+        assert_eq!("5081", Day20.solve(input, Part1).unwrap());
     }
 
     #[test]
     fn test2() {
-
-        let input = "..#.#..#####.#.#.#.###.##.....###.##.#..###.####..#####..#....#..#..##..##
-#..######.###...####..#..#####..##..#.#####...##.#.#..#.##..#.#......#.###
-.######.###.####...#.##.##..#..#..#####.....#.#....###..#.##......#.....#.
-.#..#..##..#...##.######.####.####.#.#...#.......#..#.#.#...####.##.#.....
-.#..#...##.#.##..#...##.#.##..###.#......#.#.......#.#.#.####.###.##...#..
-...####.#..#..#.##.#....##..#.####....##...##..#...#......#.#.......#.....
-..##..####..#...#.#.#...##..#.#..###..#####........#..####......#..#
-
-#..#.
-#....
-##..#
-..#..
-..###";
-        assert_eq!("3351", solve(input.to_string(), Part2));
+        assert_eq!("3351", Day20.solve(EXAMPLE, Part2).unwrap());
     }
 
     #[test]
     fn test_part2() {
         let input = include_str!("../../input_20.txt");
 
-        assert_eq!("15088", solve(input.to_string(), Part2));
+        assert_eq!("15088", Day20.solve(input, Part2).unwrap());
     }
 
+    #[test]
+    fn test_invalid_input() {
+        let algo = "#.".repeat(256);
+
+        let error = Day20.solve(&format!("{}\n\n#..\n#x.", algo), Part1).unwrap_err();
+        assert_eq!((Some(4), Some(2)), (error.line, error.column));
+
+        assert!(Day20.solve(&format!("{}#\n\n#..", algo), Part1).is_err());
+        assert!(Day20.solve(&algo, Part1).is_err());
+    }
 }
@@ -1,21 +1,109 @@
+use std::collections::HashMap;
 use regex::Regex;
-use super::Part;
+use super::error::{parse_field, Result, SolveError};
+use super::params::{parse_value, per_part, unknown, Param, Params};
+use super::solver::{Example, Solver};
 
-pub fn solve(input : String, part: Part) -> String {
+pub struct Day21;
 
-    let lines:Vec<&str> = input.lines()
-        .collect();
+impl Solver for Day21 {
+    type Input = Vec<Player>;
 
-    match part {
-        Part::Part1 => part1(lines),
-        Part::Part2 => part2(lines)
+    type Params = Day21Params;
+
+    fn name(&self) -> &'static str {
+        "Dirac Dice"
+    }
+
+    fn day(&self) -> u8 {
+        21
+    }
+
+    fn examples(&self) -> Vec<Example> {
+        vec![Example{input:EXAMPLE, part1:Some("739785"), part2:Some("444356092776315")}]
+    }
+
+    fn parse(&self, input:&str) -> Result<Vec<Player>> {
+        let players = input.lines()
+            .enumerate()
+            .map(|(i, line)| Player::parse(i + 1, line))
+            .collect::<Result<Vec<Player>>>()?;
+
+        if players.len() != 2 {
+            return Err(SolveError::new(&format!("expected 2 players, found {}", players.len())));
+        }
+        Ok(players)
     }
+
+    fn part1(&self, players:&Vec<Player>, params:&Day21Params) -> Result<String> {
+        let rules = params.rules(players, 1000)?;
+        Ok(part1(players.clone(), &rules))
+    }
+
+    fn part2(&self, players:&Vec<Player>, params:&Day21Params) -> Result<String> {
+        let rules = params.rules(players, 21)?;
+        part2(players.clone(), &rules)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Day21Params {
+    /// Score needed to win, 1000 for part 1 and 21 for part 2 when not set.
+    pub winning_score:Option<u64>,
+    pub board_size:u64,
+}
+
+impl Default for Day21Params {
+    fn default() -> Day21Params {
+        Day21Params{winning_score:None, board_size:10}
+    }
+}
+
+impl Day21Params {
+    fn rules(&self, players:&[Player], winning_score:u64) -> Result<Rules> {
+        if self.board_size == 0 {
+            return Err(SolveError::new("board_size must be at least 1"));
+        }
+
+        if let Some(player) = players.iter().find(|player| player.position > self.board_size) {
+            let message = format!("starting position {} is outside a board of size {}", player.position, self.board_size);
+            return Err(SolveError::new(&message));
+        }
+
+        Ok(Rules{board_size:self.board_size, winning_score:self.winning_score.unwrap_or(winning_score)})
+    }
+}
+
+impl Params for Day21Params {
+    fn list(&self) -> Vec<Param> {
+        vec![
+            Param{name:"winning_score", value:per_part(&self.winning_score, 1000, 21), description:"score needed to win"},
+            Param{name:"board_size", value:self.board_size.to_string(), description:"number of spaces on the board"},
+        ]
+    }
+
+    fn set(&mut self, name:&str, value:&str) -> Result<()> {
+        match name {
+            "winning_score" => self.winning_score = Some(parse_value(name, value)?),
+            "board_size" => self.board_size = parse_value(name, value)?,
+            _ => return Err(unknown(name, self)),
+        }
+        Ok(())
+    }
+}
+
+/// Board size and winning score of one game.
+#[derive(Debug, Clone, Copy)]
+pub struct Rules {
+    pub board_size:u64,
+    pub winning_score:u64,
 }
 
 
-#[derive(Debug)]
-struct Player {
-    number:u64,
+
+/// Player position and score.
+#[derive(Debug, Clone)]
+pub struct Player {
     position:u64,
     score:u64,
 }
@@ -23,62 +111,59 @@ struct Player {
 
 impl Player {
 
-    fn parse(line:&str) -> Player {
+    fn parse(line_no:usize, line:&str) -> Result<Player> {
         //Player 1 starting position: 4
         let re = Regex::new(r"Player (\d*) starting position: (\d*)").unwrap();
-        for cap in re.captures_iter(line) {
-            let (number, position) =
-                (cap[1].parse::<u64>().unwrap(), cap[2].parse::<u64>().unwrap());
-            return Player{number, position, score:0}
+        let cap = re.captures(line)
+            .ok_or_else(|| SolveError::on_line(line_no, "expected 'Player N starting position: P'"))?;
+        let field = cap.get(2).unwrap().as_str();
+        let position:u64 = parse_field(line_no, line, field)?;
+        if position == 0 {
+            return Err(SolveError::at(line_no, cap.get(2).unwrap().start() + 1, "position must be at least 1"));
         }
-        panic!("...");
+        Ok(Player{position, score:0})
     }
 
-    fn roll(&mut self, dice:&mut u64, rolls:&mut u64) -> bool {
+    fn roll(&mut self, dice:&mut u64, rolls:&mut u64, rules:&Rules) -> bool {
         for _ in 0..3 {
-            self.advance(*dice+1);
+            self.advance(*dice+1, rules.board_size);
             *dice = (*dice + 1) % 100;
         }
 
         self.score += self.position;
         *rolls +=3;
 
-        self.score >= 1000
+        self.score >= rules.winning_score
     }
 
-    fn advance(&mut self, dice_value:u64) {
-        self.position += dice_value;
-        while self.position > 10 {
-            self.position -= 10;
-        }
+    fn advance(&mut self, dice_value:u64, board_size:u64) {
+        self.position = (self.position + dice_value - 1) % board_size + 1;
     }
 }
 
 
 
 
-fn part1(lines:Vec<&str>) -> String {
-    let mut game_context:Vec<Player> = lines.iter().map( |line| Player::parse(line)).collect();
+/// Losing score times the number of rolls with the deterministic die.
+pub fn part1(mut game_context:Vec<Player>, rules:&Rules) -> String {
     let mut player2 = game_context.pop().unwrap();
     let mut player1 = game_context.pop().unwrap();
 
     let mut rolls = 0;
     let mut dice = 0;
     loop {
-        if player1.roll(&mut dice, &mut rolls) {
+        if player1.roll(&mut dice, &mut rolls, rules) {
             // Player 1 wins
-            println!("player 2 score:{}, rolls={}",player2.score, rolls);
             return (player2.score * rolls).to_string()
         }
-        if player2.roll(&mut dice, &mut rolls) {
-            println!("player 1 score:{}, rolls={}",player1.score, rolls);
+        if player2.roll(&mut dice, &mut rolls, rules) {
             return (player1.score * rolls).to_string()
         }
     }
 }
 
-fn get_number_of_outcomes(dice_value:u64) -> u64 {
-    return match dice_value {
+fn get_number_of_outcomes(dice_value:u64) -> u128 {
+    match dice_value {
         3 => 1,
         4 => 3,
         5 => 6,
@@ -90,137 +175,159 @@ fn get_number_of_outcomes(dice_value:u64) -> u64 {
     }
 }
 
-fn play_part2(state:&mut Vec<Vec<Option<(u64, u64)>>>, player1_score:u64, player1_pos:u64, player2_score:u64, player2_pos:u64, player1_turn:bool) -> (u64, u64){
-    let mut player1_acc_wins:u64 = 0;
-    let mut player2_acc_wins:u64 = 0;
+type State = HashMap<(u64, u64, u64, u64, bool), (u128, u128)>;
 
-    // Check if we already have the count for this combo
-    let pos_index = ((player1_pos << 5) + player2_pos) as usize;
-    let points_index = ((player1_score << 5) + player2_score) as usize;
+fn overflow() -> SolveError {
+    SolveError::new("number of universes overflows")
+}
 
-    //println!("play => player1:{},{}, player2:{},{}", player1_score, player1_pos, player2_score, player2_pos);
-    if let Some(state_element) = state.get(pos_index).unwrap().get(points_index).unwrap() {
-        //println!("    => Cached res {:?}", state_element);
-        return *state_element;
+fn play_part2(state:&mut State, rules:&Rules, player1_score:u64, player1_pos:u64, player2_score:u64, player2_pos:u64, player1_turn:bool) -> Result<(u128, u128)> {
+    let mut player1_acc_wins:u128 = 0;
+    let mut player2_acc_wins:u128 = 0;
+
+    // Check if we already have the count for this combo
+    let key = (player1_score, player1_pos, player2_score, player2_pos, player1_turn);
+    if let Some(state_element) = state.get(&key) {
+        return Ok(*state_element);
     }
 
 
-    // Check if any player reached 21?
-    if player1_score > 20 {
+    // Check if any player reached the winning score
+    if player1_score >= rules.winning_score {
         // Player 1 wins...
         player1_acc_wins += 1;
-    } else if player2_score > 20 {
+    } else if player2_score >= rules.winning_score {
         // Player 2 wins...
         player2_acc_wins += 1;
     } else {
         // Roll dices
-        let rolls: Vec<(u64, u64)> = (3..=9).into_iter().map(|v| (v, get_number_of_outcomes(v))).collect();
+        let rolls: Vec<(u64, u128)> = (3..=9).map(|v| (v, get_number_of_outcomes(v))).collect();
 
         for (dice_value, outcomes) in rolls {
             if player1_turn {
-                let mut player1_next_pos = player1_pos + dice_value;
-                while player1_next_pos > 10 {
-                    player1_next_pos -= 10;
-                }
+                let player1_next_pos = (player1_pos + dice_value - 1) % rules.board_size + 1;
                 let player1_next_score = player1_score + player1_next_pos;
 
                 // Call next game
-                let (player1_wins, player2_wins) = play_part2(state, player1_next_score, player1_next_pos, player2_score, player2_pos, !player1_turn);
+                let (player1_wins, player2_wins) = play_part2(state, rules, player1_next_score, player1_next_pos, player2_score, player2_pos, !player1_turn)?;
 
                 // Accumulate number of outcomes for this branch
-                player1_acc_wins += player1_wins * outcomes;
-                player2_acc_wins += player2_wins * outcomes;
+                player1_acc_wins = player1_wins.checked_mul(outcomes).and_then(|wins| wins.checked_add(player1_acc_wins)).ok_or_else(overflow)?;
+                player2_acc_wins = player2_wins.checked_mul(outcomes).and_then(|wins| wins.checked_add(player2_acc_wins)).ok_or_else(overflow)?;
 
             } else {
-                let mut player2_next_pos = player2_pos + dice_value;
-                while player2_next_pos > 10 {
-                    player2_next_pos -= 10;
-                }
+                let player2_next_pos = (player2_pos + dice_value - 1) % rules.board_size + 1;
                 let player2_next_score = player2_score + player2_next_pos;
 
                 // Call next game
-                let (player1_wins, player2_wins) = play_part2(state, player1_score, player1_pos, player2_next_score, player2_next_pos, !player1_turn);
+                let (player1_wins, player2_wins) = play_part2(state, rules, player1_score, player1_pos, player2_next_score, player2_next_pos, !player1_turn)?;
 
                 // Accumulate number of outcomes for this branch
-                player1_acc_wins += player1_wins * outcomes;
-                player2_acc_wins += player2_wins * outcomes;
+                player1_acc_wins = player1_wins.checked_mul(outcomes).and_then(|wins| wins.checked_add(player1_acc_wins)).ok_or_else(overflow)?;
+                player2_acc_wins = player2_wins.checked_mul(outcomes).and_then(|wins| wins.checked_add(player2_acc_wins)).ok_or_else(overflow)?;
 
             }
         }
     }
 
     // Update cached state
-    let update_state_element = state.get_mut(pos_index).unwrap().get_mut(points_index).unwrap();
-    *update_state_element = Some((player1_acc_wins, player2_acc_wins));
+    state.insert(key, (player1_acc_wins, player2_acc_wins));
 
-    //println!("    => CALCULATE res {:?}", update_state_element);
+    Ok((player1_acc_wins, player2_acc_wins))
+}
 
-    return (player1_acc_wins, player2_acc_wins)
+/// Fewest turns `player` needs to reach the winning score, at most `limit`.
+/// The position can't grow faster than 9 per turn or get past the board.
+fn fewest_turns(player:&Player, rules:&Rules, limit:u64) -> u64 {
+    let mut score = 0u64;
+    (1..=limit)
+        .find(|turn| {
+            score = score.saturating_add(rules.board_size.min(player.position.saturating_add(9 * turn)));
+            score >= rules.winning_score
+        })
+        .unwrap_or(limit)
 }
 
-fn part2(lines:Vec<&str>) -> String {
-    let mut game_context:Vec<Player> = lines.iter().map( |line| Player::parse(line)).collect();
-    println!("{:?}",game_context);
+/// Number of universes the player who wins most often with the Dirac die wins in.
+pub fn part2(mut game_context:Vec<Player>, rules:&Rules) -> Result<String> {
     let player2 = game_context.pop().unwrap();
     let player1 = game_context.pop().unwrap();
 
-    let mut state = vec![];
-    for _ in 0..1024 {
-        let mut sub_vec = vec![];
-        for _ in 0..1024 {
-            sub_vec.push(Option::None);
-        }
-        state.push(sub_vec);
+    // Every turn splits each universe in 27, after 27 turns there are more than fit in a u128.
+    // Failing early also keeps games too long to count from recursing that deep.
+    let turns = (2 * fewest_turns(&player1, rules, 14) - 1).min(2 * fewest_turns(&player2, rules, 14));
+    if turns >= 27 {
+        return Err(overflow());
     }
 
-    let outcomes = play_part2(&mut state, 0, player1.position, 20, player2.position, true);
+    let mut state = HashMap::new();
 
-    //307571216519574
-    //89974745806771
-
-    //444356092776315
-    //341960390180808
-    println!("{:?}", outcomes);
-    outcomes.0.to_string()
+    let outcomes = play_part2(&mut state, rules, 0, player1.position, 0, player2.position, true)?;
+    Ok(outcomes.0.max(outcomes.1).to_string())
 }
 
 
+const EXAMPLE:&str = "Player 1 starting position: 4
+Player 2 starting position: 8";
+
+
 #[cfg(test)]
 mod tests {
     // Note this useful idiom: importing names from outer (for mod tests) scope.
     use super::*;
+    use util::solver::Puzzle;
     use util::Part::{Part1, Part2};
 
 
     #[test]
     fn test1() {
-
-        let input = "Player 1 starting position: 4
-Player 2 starting position: 8";
-
-        assert_eq!("739785", solve(input.to_string(), Part1));
+        assert_eq!("739785", Day21.solve(EXAMPLE, Part1).unwrap());
     }
 
     #[test]
     fn test_part1() {
         let input = include_str!("../../input_21.txt");
 
-        assert_eq!("675024", solve(input.to_string(), Part1));
+        assert_eq!("675024", Day21.solve(input, Part1).unwrap());
     }
 
-    //#[test]
-    fn test2() {
+    #[test]
+    fn test_invalid_player() {
+        let input = "Player 1 starting position: 4
+Player 2 starting position: 0";
+        let error = Day21.solve(input, Part1).unwrap_err();
+        assert_eq!((Some(2), Some(29)), (error.line, error.column));
 
         let input = "Player 1 starting position: 4
-Player 2 starting position: 8";
-        assert_eq!("444356092776315", solve(input.to_string(), Part2));
+Player 2 starting position: 12";
+        assert!(Day21.solve(input, Part1).is_err());
+        assert!(Day21.solve_with(input, Part1, &[("board_size".to_string(), "12".to_string())]).is_ok());
+
+        assert!(Day21.solve("Player 1 starting position: 4", Part1).is_err());
+    }
+
+    #[test]
+    fn test_params() {
+        assert_eq!("0", Day21.solve_with(EXAMPLE, Part1, &[("winning_score".to_string(), "1".to_string())]).unwrap());
+        assert_eq!("27", Day21.solve_with(EXAMPLE, Part2, &[("winning_score".to_string(), "1".to_string())]).unwrap());
+
+        let input = include_str!("../../input_21.txt");
+        for score in ["60", "1000000"].iter() {
+            let error = Day21.solve_with(input, Part2, &[("winning_score".to_string(), score.to_string())]).unwrap_err();
+            assert_eq!("number of universes overflows", error.message);
+        }
+    }
+
+    #[test]
+    fn test2() {
+        assert_eq!("444356092776315", Day21.solve(EXAMPLE, Part2).unwrap());
     }
 
-    //#[test]
+    #[test]
     fn test_part2() {
         let input = include_str!("../../input_21.txt");
 
-        assert_eq!("1", solve(input.to_string(), Part2));
+        assert_eq!("570239341223618", Day21.solve(input, Part2).unwrap());
     }
 
 }
@@ -1,61 +1,277 @@
-use super::Part;
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use regex::Regex;
+use super::error::{parse_field, Result, SolveError};
+use super::params::NoParams;
+use super::solver::{Example, Solver};
 
-pub fn solve(input : String, part: Part) -> String {
+pub struct Day22;
 
-    let lines:Vec<&str> = input.lines()
-        .collect();
+impl Solver for Day22 {
+    type Input = Vec<Step>;
 
-    match part {
-        Part::Part1 => part1(lines),
-        Part::Part2 => part2(lines)
+    type Params = NoParams;
+
+    fn name(&self) -> &'static str {
+        "Reactor Reboot"
+    }
+
+    fn day(&self) -> u8 {
+        22
+    }
+
+    fn examples(&self) -> Vec<Example> {
+        vec![Example{input:EXAMPLE, part1:Some("39"), part2:Some("39")}]
+    }
+
+    fn parse(&self, input:&str) -> Result<Vec<Step>> {
+        let re = Regex::new(r"^(on|off) x=(-?\d+)\.\.(-?\d+),y=(-?\d+)\.\.(-?\d+),z=(-?\d+)\.\.(-?\d+)$").unwrap();
+        input.lines()
+            .enumerate()
+            .filter(|(_, line)| !line.trim().is_empty())
+            .map(|(i, line)| Step::parse(&re, i + 1, line.trim()))
+            .collect()
+    }
+
+    fn part1(&self, steps:&Vec<Step>, _:&NoParams) -> Result<String> {
+        part1(steps)
+    }
+
+    fn part2(&self, steps:&Vec<Step>, _:&NoParams) -> Result<String> {
+        part2(steps)
     }
 }
 
+/// Block of cubes, inclusive on all sides.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct Cuboid {
+    x:(i64, i64),
+    y:(i64, i64),
+    z:(i64, i64),
+}
+
+impl Cuboid {
+    pub fn new(x:(i64, i64), y:(i64, i64), z:(i64, i64)) -> Cuboid {
+        Cuboid{x, y, z}
+    }
 
-fn part1(_lines:Vec<&str>) -> String {
-    "1".to_string()
+    /// Number of cubes.
+    pub fn volume(&self) -> u128 {
+        let len = |(lo, hi):(i64, i64)| (hi as i128 - lo as i128 + 1) as u128;
+        len(self.x) * len(self.y) * len(self.z)
+    }
+
+    /// The cubes in both, if there are any.
+    pub fn intersection(&self, other:&Cuboid) -> Option<Cuboid> {
+        let overlap = |(a_lo, a_hi):(i64, i64), (b_lo, b_hi):(i64, i64)| {
+            let (lo, hi) = (a_lo.max(b_lo), a_hi.min(b_hi));
+            if lo <= hi { Some((lo, hi)) } else { None }
+        };
+        Some(Cuboid{x:overlap(self.x, other.x)?, y:overlap(self.y, other.y)?, z:overlap(self.z, other.z)?})
+    }
 }
 
+/// Largest coordinate along any axis, so that even the sum of the volumes of
+/// billions of cuboids of the whole range fits in an i128.
+const MAX_COORDINATE:i64 = 1_000_000_000;
 
-fn part2(_lines:Vec<&str>) -> String {
-    "1".to_string()
+/// Turns the cubes of a cuboid on or off.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct Step {
+    pub on:bool,
+    pub cuboid:Cuboid,
 }
 
+impl Step {
+    fn parse(re:&Regex, line_no:usize, line:&str) -> Result<Step> {
+        let cap = match re.captures(line) {
+            Some(cap) => cap,
+            None => return Err(SolveError::on_line(line_no, "expected 'on|off x=A..B,y=C..D,z=E..F'")),
+        };
+
+        let field = |n| parse_field::<i64>(line_no, line, cap.get(n).unwrap().as_str());
+        let mut ranges = vec![];
+        for axis in 0..3 {
+            let (lo, hi) = (field(2 + 2 * axis)?, field(3 + 2 * axis)?);
+            for (n, value) in [(2, lo), (3, hi)].iter() {
+                if value.abs() > MAX_COORDINATE {
+                    let column = cap.get(n + 2 * axis).unwrap().start() + 1;
+                    let message = format!("coordinate {} is outside -{}..{}", value, MAX_COORDINATE, MAX_COORDINATE);
+                    return Err(SolveError::at(line_no, column, &message));
+                }
+            }
+            if lo > hi {
+                let column = cap.get(2 + 2 * axis).unwrap().start() + 1;
+                return Err(SolveError::at(line_no, column, "range ends before it starts"));
+            }
+            ranges.push((lo, hi));
+        }
+
+        Ok(Step{on:&cap[1] == "on", cuboid:Cuboid::new(ranges[0], ranges[1], ranges[2])})
+    }
+}
+
+/// Cubes that are on, kept as cuboids that are added or subtracted so that
+/// overlapping cuboids are counted once.
+#[derive(Debug, Default)]
+pub struct Reactor {
+    cuboids:HashMap<Cuboid, i64>,
+}
+
+impl Reactor {
+    pub fn new() -> Reactor {
+        Reactor::default()
+    }
+
+    pub fn apply(&mut self, step:&Step) {
+        // Takes out what the cubes of the step added so far, then adds them once if on
+        let mut changes:HashMap<Cuboid, i64> = HashMap::new();
+        for (cuboid, &sign) in self.cuboids.iter() {
+            if let Some(overlap) = cuboid.intersection(&step.cuboid) {
+                *changes.entry(overlap).or_insert(0) -= sign;
+            }
+        }
+        if step.on {
+            *changes.entry(step.cuboid).or_insert(0) += 1;
+        }
+
+        for (cuboid, change) in changes {
+            let sign = self.cuboids.entry(cuboid).or_insert(0);
+            *sign += change;
+            if *sign == 0 {
+                self.cuboids.remove(&cuboid);
+            }
+        }
+    }
+
+    /// Number of cubes that are on.
+    pub fn volume(&self) -> u128 {
+        let total:i128 = self.cuboids.iter().map(|(cuboid, &sign)| cuboid.volume() as i128 * sign as i128).sum();
+        u128::try_from(total).unwrap()
+    }
+}
+
+/// Cubes on after all steps, only counting those in `region` if given.
+pub fn reboot(steps:&[Step], region:Option<&Cuboid>) -> u128 {
+    let mut reactor = Reactor::new();
+    for step in steps {
+        let cuboid = match region {
+            Some(region) => match step.cuboid.intersection(region) {
+                Some(cuboid) => cuboid,
+                None => continue,
+            },
+            None => step.cuboid,
+        };
+        reactor.apply(&Step{on:step.on, cuboid});
+    }
+    reactor.volume()
+}
+
+/// Cubes on in the initialization region.
+pub fn part1(steps:&[Step]) -> Result<String> {
+    let region = Cuboid::new((-50, 50), (-50, 50), (-50, 50));
+    Ok(reboot(steps, Some(&region)).to_string())
+}
+
+/// Cubes on anywhere.
+pub fn part2(steps:&[Step]) -> Result<String> {
+    Ok(reboot(steps, None).to_string())
+}
+
+
+const EXAMPLE:&str = "on x=10..12,y=10..12,z=10..12
+on x=11..13,y=11..13,z=11..13
+off x=9..11,y=9..11,z=9..11
+on x=10..10,y=10..10,z=10..10";
+
 
 #[cfg(test)]
 mod tests {
     // Note this useful idiom: importing names from outer (for mod tests) scope.
     use super::*;
+    use std::collections::HashSet;
+    use util::random::Rng;
+    use util::solver::Puzzle;
     use util::Part::{Part1, Part2};
 
 
     #[test]
     fn test1() {
+        assert_eq!("39", Day22.solve(EXAMPLE, Part1).unwrap());
 
-        let input = "";
-
-        assert_eq!("1", solve(input.to_string(), Part1));
+        let outside = "on x=-60..-51,y=0..0,z=0..0\non x=50..51,y=0..1,z=0..0";
+        assert_eq!("2", Day22.solve(outside, Part1).unwrap());
     }
 
     #[test]
     fn test_part1() {
         let input = include_str!("../../input_22.txt");
 
-        assert_eq!("1", solve(input.to_string(), Part1));
+        assert_eq!("650099", Day22.solve(input, Part1).unwrap());
     }
 
     #[test]
     fn test2() {
+        assert_eq!("39", Day22.solve(EXAMPLE, Part2).unwrap());
 
-        let input = "";
-        assert_eq!("1", solve(input.to_string(), Part2));
+        let huge = "on x=-100000..100000,y=-100000..100000,z=-100000..100000\noff x=0..0,y=0..0,z=0..0";
+        assert_eq!((200001u128.pow(3) - 1).to_string(), Day22.solve(huge, Part2).unwrap());
+
+        let largest = "on x=-1000000000..1000000000,y=-1000000000..1000000000,z=-1000000000..1000000000";
+        assert_eq!(2000000001u128.pow(3).to_string(), Day22.solve(largest, Part2).unwrap());
+        let error = Day22.solve("on x=-4000000000000..4000000000000,y=0..0,z=0..0", Part2).unwrap_err();
+        assert_eq!((Some(1), Some(6)), (error.line, error.column));
+        assert_eq!("coordinate -4000000000000 is outside -1000000000..1000000000", error.message);
     }
 
     #[test]
     fn test_part2() {
         let input = include_str!("../../input_22.txt");
 
-        assert_eq!("1", solve(input.to_string(), Part2));
+        assert_eq!("1254011191104293", Day22.solve(input, Part2).unwrap());
+    }
+
+    #[test]
+    fn test_cuboid() {
+        let a = Cuboid::new((0, 2), (0, 2), (0, 2));
+        let b = Cuboid::new((2, 5), (1, 1), (-3, 0));
+        assert_eq!((27, 16), (a.volume(), b.volume()));
+        assert_eq!(Some(Cuboid::new((2, 2), (1, 1), (0, 0))), a.intersection(&b));
+        assert_eq!(None, a.intersection(&Cuboid::new((3, 4), (0, 2), (0, 2))));
+    }
+
+    #[test]
+    fn test_against_cubes() {
+        // Small random steps, checked against a set of the cubes
+        let mut rng = Rng::new(22);
+        let mut steps = vec![];
+        let mut cubes = HashSet::new();
+        for _ in 0..40 {
+            let mut range = || {
+                let lo = rng.range(-6, 5);
+                (lo, lo + rng.range(0, 4))
+            };
+            let cuboid = Cuboid::new(range(), range(), range());
+            let on = rng.below(3) != 0;
+            for x in cuboid.x.0..=cuboid.x.1 {
+                for y in cuboid.y.0..=cuboid.y.1 {
+                    for z in cuboid.z.0..=cuboid.z.1 {
+                        if on { cubes.insert((x, y, z)); } else { cubes.remove(&(x, y, z)); }
+                    }
+                }
+            }
+            steps.push(Step{on, cuboid});
+            assert_eq!(cubes.len() as u128, reboot(&steps, None));
+        }
+    }
+
+    #[test]
+    fn test_parse() {
+        let error = Day22.parse("on x=1..2,y=1..2,z=1..2\nof x=1..2,y=1..2,z=1..2").unwrap_err();
+        assert_eq!(Some(2), error.line);
+
+        let error = Day22.parse("off x=1..2,y=5..2,z=1..2").unwrap_err();
+        assert_eq!((Some(1), Some(14)), (error.line, error.column));
     }
 
 }
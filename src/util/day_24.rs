@@ -1,23 +1,50 @@
-use super::Part;
+use super::error::Result;
+use super::params::NoParams;
+use super::solver::Solver;
 
-pub fn solve(input : String, part: Part) -> String {
+pub struct Day24;
 
-    let lines:Vec<&str> = input.lines()
-        .collect();
+impl Solver for Day24 {
+    type Input = Vec<String>;
 
-    match part {
-        Part::Part1 => part1(lines),
-        Part::Part2 => part2(lines)
+    type Params = NoParams;
+
+    fn name(&self) -> &'static str {
+        "Arithmetic Logic Unit"
+    }
+
+    fn day(&self) -> u8 {
+        24
+    }
+
+    fn is_stub(&self) -> bool {
+        true
+    }
+
+    fn parse(&self, input:&str) -> Result<Vec<String>> {
+        input.lines()
+            .map(|line| Ok(line.to_string()))
+            .collect()
+    }
+
+    fn part1(&self, lines:&Vec<String>, _:&NoParams) -> Result<String> {
+        Ok(part1(lines))
+    }
+
+    fn part2(&self, lines:&Vec<String>, _:&NoParams) -> Result<String> {
+        Ok(part2(lines))
     }
 }
 
 
-fn part1(_lines:Vec<&str>) -> String {
+/// Not solved yet, returns a placeholder.
+pub fn part1(_lines:&[String]) -> String {
     "1".to_string()
 }
 
 
-fn part2(_lines:Vec<&str>) -> String {
+/// Not solved yet, returns a placeholder.
+pub fn part2(_lines:&[String]) -> String {
     "1".to_string()
 }
 
@@ -26,6 +53,7 @@ fn part2(_lines:Vec<&str>) -> String {
 mod tests {
     // Note this useful idiom: importing names from outer (for mod tests) scope.
     use super::*;
+    use util::solver::Puzzle;
     use util::Part::{Part1, Part2};
 
 
@@ -34,28 +62,28 @@ mod tests {
 
         let input = "";
 
-        assert_eq!("1", solve(input.to_string(), Part1));
+        assert_eq!("1", Day24.solve(input, Part1).unwrap());
     }
 
     #[test]
     fn test_part1() {
         let input = include_str!("../../input_24.txt");
 
-        assert_eq!("1", solve(input.to_string(), Part1));
+        assert_eq!("1", Day24.solve(input, Part1).unwrap());
     }
 
     #[test]
     fn test2() {
 
         let input = "";
-        assert_eq!("1", solve(input.to_string(), Part2));
+        assert_eq!("1", Day24.solve(input, Part2).unwrap());
     }
 
     #[test]
     fn test_part2() {
         let input = include_str!("../../input_24.txt");
 
-        assert_eq!("1", solve(input.to_string(), Part2));
+        assert_eq!("1", Day24.solve(input, Part2).unwrap());
     }
 
 }
@@ -1,14 +1,42 @@
 use util::day_25::Point::{Down, Empty, Right};
-use super::Part;
+use super::error::{check_grid, Result};
+use super::params::NoParams;
+use super::solver::{Example, Solver};
 
-pub fn solve(input : String, part: Part) -> String {
+pub struct Day25;
 
-    let lines:Vec<&str> = input.lines()
-        .collect();
+impl Solver for Day25 {
+    type Input = Grid;
 
-    match part {
-        Part::Part1 => part1(lines),
-        Part::Part2 => part2(lines)
+    type Params = NoParams;
+
+    fn name(&self) -> &'static str {
+        "Sea Cucumber"
+    }
+
+    fn day(&self) -> u8 {
+        25
+    }
+
+    fn examples(&self) -> Vec<Example> {
+        vec![Example{input:EXAMPLE, part1:Some("58"), part2:Some("1")}]
+    }
+
+    fn parse(&self, input:&str) -> Result<Grid> {
+        let lines:Vec<&str> = input.lines().collect();
+        // Part 2 has no puzzle, so an empty input is accepted
+        if !lines.is_empty() {
+            check_grid(&lines, |ch| ch == '>' || ch == 'v' || ch == '.')?;
+        }
+        Ok(Grid::from(lines))
+    }
+
+    fn part1(&self, grid:&Grid, _:&NoParams) -> Result<String> {
+        Ok(part1(grid.clone()))
+    }
+
+    fn part2(&self, grid:&Grid, _:&NoParams) -> Result<String> {
+        Ok(part2(grid))
     }
 }
 
@@ -19,7 +47,9 @@ enum Point {
     Down,
 }
 
-struct Grid {
+/// Sea floor with the two herds of sea cucumbers.
+#[derive(Clone)]
+pub struct Grid {
     points:Vec<Vec<Point>>,
 }
 
@@ -38,7 +68,7 @@ impl Grid {
     fn from(lines:Vec<&str>) -> Grid {
         let mut points = vec![];
         for line in lines {
-            points.push(line.chars().map(|ch| Point::from(ch)).collect());
+            points.push(line.chars().map(Point::from).collect());
         }
         Grid{points}
     }
@@ -50,11 +80,7 @@ impl Grid {
             for x in 0..line.len() {
                 if *line.get(x).unwrap() == Point::Right {
                     // check next
-                    let next_x = if x <= (line.len() - 2) {
-                        x+1
-                    } else {
-                        0
-                    };
+                    let next_x = (x + 1) % line.len();
 
                     // Is next clear
                     if *line.get(next_x).unwrap() == Point::Empty {
@@ -65,13 +91,13 @@ impl Grid {
         }
 
         if moves.is_empty() {
-            return false;
+            false
         } else {
             for (x, y, next_x) in moves {
                 *self.points.get_mut(y).unwrap().get_mut(x).unwrap() = Empty;
                 *self.points.get_mut(y).unwrap().get_mut(next_x).unwrap() = Right;
             }
-            return true;
+            true
         }
     }
 
@@ -83,11 +109,7 @@ impl Grid {
             for x in 0..line.len() {
                 if *line.get(x).unwrap() == Point::Down {
                     // check next
-                    let next_y = if y <= (height - 2) {
-                        y+1
-                    } else {
-                        0
-                    };
+                    let next_y = (y + 1) % height;
 
                     // Is next clear
                     if *self.points.get(next_y).unwrap().get(x).unwrap() == Point::Empty {
@@ -98,18 +120,19 @@ impl Grid {
         }
 
         if moves.is_empty() {
-            return false;
+            false
         } else {
             for (x,y, next_y) in moves {
                 *self.points.get_mut(y).unwrap().get_mut(x).unwrap() = Empty;
                 *self.points.get_mut(next_y).unwrap().get_mut(x).unwrap() = Down;
             }
-            return true;
+            true
         }
     }
 
 
-    fn step(&mut self) -> bool {
+    /// Moves both herds once, returns whether any sea cucumber moved.
+    pub fn step(&mut self) -> bool {
         let right = self.move_cucumbers_right();
         let down = self.move_cucumbers_down();
         right || down
@@ -117,12 +140,11 @@ impl Grid {
 
 }
 
-fn part1(lines:Vec<&str>) -> String {
-    let mut grid = Grid::from(lines);
+/// First step on which no sea cucumber moves.
+pub fn part1(mut grid:Grid) -> String {
     let mut step = 1;
     loop {
         if !grid.step() {
-            println!("step={}", step);
             break;
         } else {
             step += 1;
@@ -134,22 +156,13 @@ fn part1(lines:Vec<&str>) -> String {
 }
 
 
-fn part2(_lines:Vec<&str>) -> String {
+/// There is no puzzle for part 2, always `1`.
+pub fn part2(_grid:&Grid) -> String {
     "1".to_string()
 }
 
 
-#[cfg(test)]
-mod tests {
-    // Note this useful idiom: importing names from outer (for mod tests) scope.
-    use super::*;
-    use util::Part::{Part1, Part2};
-
-
-    #[test]
-    fn test1() {
-
-        let input = "v...>>.vv>
+const EXAMPLE:&str = "v...>>.vv>
 .vv>>.vv..
 >>.>v>...v
 >>v>>.>.v.
@@ -159,28 +172,45 @@ v>v.vv.v..
 v.v..>>v.v
 ....v..v.>";
 
-        assert_eq!("58", solve(input.to_string(), Part1));
+
+#[cfg(test)]
+mod tests {
+    // Note this useful idiom: importing names from outer (for mod tests) scope.
+    use super::*;
+    use util::solver::Puzzle;
+    use util::Part::{Part1, Part2};
+
+
+    #[test]
+    fn test1() {
+        assert_eq!("58", Day25.solve(EXAMPLE, Part1).unwrap());
     }
 
     #[test]
     fn test_part1() {
         let input = include_str!("../../input_25.txt");
 
-        assert_eq!("334", solve(input.to_string(), Part1));
+        assert_eq!("334", Day25.solve(input, Part1).unwrap());
+    }
+
+    #[test]
+    fn test_single_row() {
+        assert_eq!("2", Day25.solve(">.v", Part1).unwrap());
+        assert_eq!("1", Day25.solve(">\nv", Part1).unwrap());
     }
 
     #[test]
     fn test2() {
 
         let input = "";
-        assert_eq!("1", solve(input.to_string(), Part2));
+        assert_eq!("1", Day25.solve(input, Part2).unwrap());
     }
 
     #[test]
     fn test_part2() {
         let input = include_str!("../../input_25.txt");
 
-        assert_eq!("1", solve(input.to_string(), Part2));
+        assert_eq!("1", Day25.solve(input, Part2).unwrap());
     }
 
 }
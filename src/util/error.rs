@@ -0,0 +1,147 @@
+use std::error::Error;
+use std::fmt;
+use std::str::FromStr;
+use super::Part;
+
+/// Error raised when a puzzle input can't be parsed or solved.
+///
+/// Line and column are 1-based positions in the puzzle input, day and part are
+/// filled in by the solver pipeline.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SolveError {
+    pub day:Option<u8>,
+    pub part:Option<Part>,
+    pub line:Option<usize>,
+    pub column:Option<usize>,
+    pub message:String,
+}
+
+pub type Result<T> = std::result::Result<T, SolveError>;
+
+impl SolveError {
+    pub fn new(message:&str) -> SolveError {
+        SolveError{day:None, part:None, line:None, column:None, message:message.to_string()}
+    }
+
+    pub fn on_line(line:usize, message:&str) -> SolveError {
+        SolveError{line:Some(line), ..SolveError::new(message)}
+    }
+
+    pub fn at(line:usize, column:usize, message:&str) -> SolveError {
+        SolveError{line:Some(line), column:Some(column), ..SolveError::new(message)}
+    }
+
+    pub fn with_day(mut self, day:u8) -> SolveError {
+        self.day = self.day.or(Some(day));
+        self
+    }
+
+    pub fn with_part(mut self, part:Part) -> SolveError {
+        self.part = self.part.or(Some(part));
+        self
+    }
+
+    /// Moves the reported position down by `lines`, used when a section of the
+    /// input is parsed on its own.
+    pub fn offset_lines(mut self, lines:usize) -> SolveError {
+        self.line = self.line.map(|line| line + lines);
+        self
+    }
+}
+
+impl fmt::Display for SolveError {
+    fn fmt(&self, f:&mut fmt::Formatter) -> fmt::Result {
+        let mut context = vec![];
+        if let Some(day) = self.day {
+            context.push(format!("day {}", day));
+        }
+        if let Some(part) = self.part {
+            context.push(part.to_string());
+        }
+        match (self.line, self.column) {
+            (Some(line), Some(column)) => context.push(format!("line {}, column {}", line, column)),
+            (Some(line), None) => context.push(format!("line {}", line)),
+            _ => {}
+        }
+
+        if context.is_empty() {
+            write!(f, "{}", self.message)
+        } else {
+            write!(f, "{}: {}", context.join(", "), self.message)
+        }
+    }
+}
+
+impl Error for SolveError {}
+
+/// 1-based column of `field`, which must be a slice of `line`.
+pub fn column(line:&str, field:&str) -> usize {
+    field.as_ptr() as usize - line.as_ptr() as usize + 1
+}
+
+/// Parses `field`, a slice of `line`, reporting its position on failure.
+pub fn parse_field<T:FromStr>(line_no:usize, line:&str, field:&str) -> Result<T> {
+    field.parse().map_err(|_| {
+        SolveError::at(line_no, column(line, field), &format!("invalid value '{}'", field))
+    })
+}
+
+/// Error for a field missing at the end of `line`.
+pub fn missing(line_no:usize, line:&str, what:&str) -> SolveError {
+    SolveError::at(line_no, line.len() + 1, &format!("missing {}", what))
+}
+
+/// Checks that `lines` form a non-empty rectangle of characters accepted by `valid`.
+pub fn check_grid(lines:&[&str], valid:fn(char) -> bool) -> Result<()> {
+    let width = match lines.first() {
+        Some(line) if !line.is_empty() => line.chars().count(),
+        _ => return Err(SolveError::new("empty input")),
+    };
+
+    for (i, line) in lines.iter().enumerate() {
+        if let Some((col, ch)) = line.chars().enumerate().find(|(_, ch)| !valid(*ch)) {
+            return Err(SolveError::at(i + 1, col + 1, &format!("unexpected character '{}'", ch)));
+        }
+
+        let len = line.chars().count();
+        if len != width {
+            return Err(SolveError::on_line(i + 1, &format!("expected {} characters, found {}", width, len)));
+        }
+    }
+
+    Ok(())
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_display() {
+        let error = SolveError::at(3, 7, "invalid value 'x'").with_day(5).with_part(Part::Part2);
+        assert_eq!("day 5, part 2, line 3, column 7: invalid value 'x'", error.to_string());
+        assert_eq!("no solution", SolveError::new("no solution").to_string());
+    }
+
+    #[test]
+    fn test_parse_field() {
+        let line = "forward x5";
+        let error = parse_field::<i32>(2, line, &line[8..]).unwrap_err();
+        assert_eq!((Some(2), Some(9)), (error.line, error.column));
+        assert_eq!(Ok(5), parse_field::<i32>(1, line, &line[9..]));
+    }
+
+    #[test]
+    fn test_check_grid() {
+        let digit = |ch:char| ch.is_ascii_digit();
+        assert_eq!(Ok(()), check_grid(&["123", "456"], digit));
+
+        let error = check_grid(&["123", "4x6"], digit).unwrap_err();
+        assert_eq!((Some(2), Some(2)), (error.line, error.column));
+
+        let error = check_grid(&["123", "45"], digit).unwrap_err();
+        assert_eq!((Some(2), None), (error.line, error.column));
+        assert!(check_grid(&[], digit).is_err());
+    }
+}
@@ -0,0 +1,83 @@
+use std::fmt;
+
+/// Quotes `value` as a JSON string.
+pub fn string(value:&str) -> String {
+    let mut result = String::with_capacity(value.len() + 2);
+    result.push('"');
+    for ch in value.chars() {
+        match ch {
+            '"' => result.push_str("\\\""),
+            '\\' => result.push_str("\\\\"),
+            '\n' => result.push_str("\\n"),
+            '\r' => result.push_str("\\r"),
+            '\t' => result.push_str("\\t"),
+            ch if (ch as u32) < 0x20 => result.push_str(&format!("\\u{:04x}", ch as u32)),
+            ch => result.push(ch),
+        }
+    }
+    result.push('"');
+    result
+}
+
+/// A flat JSON object, fields are written in the order they are added.
+#[derive(Default)]
+pub struct Object {
+    fields:Vec<(String, String)>,
+}
+
+impl Object {
+    pub fn new() -> Object {
+        Object::default()
+    }
+
+    pub fn string(mut self, name:&str, value:&str) -> Object {
+        self.fields.push((name.to_string(), string(value)));
+        self
+    }
+
+    pub fn number<T:fmt::Display>(mut self, name:&str, value:T) -> Object {
+        self.fields.push((name.to_string(), value.to_string()));
+        self
+    }
+
+    /// Adds a string field, or null when `value` is `None`.
+    pub fn optional(mut self, name:&str, value:Option<&str>) -> Object {
+        self.fields.push((name.to_string(), value.map(string).unwrap_or_else(|| "null".to_string())));
+        self
+    }
+}
+
+impl fmt::Display for Object {
+    fn fmt(&self, f:&mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{{")?;
+        for (i, (name, value)) in self.fields.iter().enumerate() {
+            if i > 0 {
+                write!(f, ",")?;
+            }
+            write!(f, "{}:{}", string(name), value)?;
+        }
+        write!(f, "}}")
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_string() {
+        assert_eq!("\"a \\\"b\\\" \\\\ c\"", string("a \"b\" \\ c"));
+        assert_eq!("\"#.\\n.#\\u0001\"", string("#.\n.#\u{1}"));
+    }
+
+    #[test]
+    fn test_object() {
+        let object = Object::new()
+            .number("day", 16)
+            .string("answer", "901")
+            .optional("error", None);
+        assert_eq!("{\"day\":16,\"answer\":\"901\",\"error\":null}", object.to_string());
+        assert_eq!("{}", Object::new().to_string());
+    }
+}
@@ -1,14 +1,36 @@
-use std::collections::VecDeque;
+use std::convert::TryFrom;
+use std::fmt;
+use std::mem;
+use num_bigint::BigUint;
+use super::error::{Result, SolveError};
+use self::bits::{BitCursor, BitRead};
+use self::dialect::Dialect;
 
+pub mod bits;
+pub mod dialect;
+pub mod disassembler;
+pub mod encoder;
+pub mod expression;
+pub mod generator;
+pub mod transmissions;
+
+/// Deepest nesting of packets the decoder accepts, deeper transmissions would
+/// overflow the stack.
+pub const MAX_DEPTH:usize = 256;
+
+/// Decoder for BITS transmissions, by default given as hex.
 #[derive(Debug)]
-pub(crate) struct PacketComputer {
-    bits:VecDeque<bool>,
-    offset:usize,
-    pub(crate) version_sum:u64,
+pub struct PacketComputer<R = BitCursor<'static>> {
+    reader:R,
+    mode:Mode,
+    warnings:Vec<DecodeError>,
+    /// Operators the packet being decoded is in.
+    depth:usize,
 }
 
-#[derive(Debug, PartialEq)]
-enum TypeID {
+#[allow(clippy::upper_case_acronyms)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TypeID {
     SUM = 0,
     PRODUCT = 1,
     MIN = 2,
@@ -19,165 +41,570 @@ enum TypeID {
     EQUAL = 7,
 }
 
-#[derive(Debug, PartialEq)]
-enum LengthId {
+impl TypeID {
+    pub fn from_bits(bits:u64) -> Option<TypeID> {
+        match bits {
+            0 => Some(TypeID::SUM),
+            1 => Some(TypeID::PRODUCT),
+            2 => Some(TypeID::MIN),
+            3 => Some(TypeID::MAX),
+            4 => Some(TypeID::LITERAL),
+            5 => Some(TypeID::GreaterThan),
+            6 => Some(TypeID::LessThan),
+            7 => Some(TypeID::EQUAL),
+            _ => None,
+        }
+    }
+
+    /// Comparisons take exactly two sub packets.
+    pub fn is_comparison(&self) -> bool {
+        matches!(self, TypeID::GreaterThan | TypeID::LessThan | TypeID::EQUAL)
+    }
+}
+
+/// How an operator packet gives the size of its sub packets.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum LengthId {
+    /// 15 bit total length of the sub packets.
     NumberOfBits = 0,
+    /// 11 bit count of the sub packets.
     NumberOfPackets = 1,
 }
 
-trait Packet {
-    fn process(&self) -> u64;
+/// A decoded packet. `offset` is the position of its first bit in the
+/// transmission and `length` the number of bits it covers. `declared` is the
+/// value of the length field of an operator, bits or packets depending on
+/// `length_id`, which may not match the sub packets of a damaged transmission.
+#[derive(Debug, Clone, PartialEq)]
+pub enum PacketNode {
+    Literal {
+        version:u8,
+        value:BigUint,
+        offset:usize,
+        length:usize,
+    },
+    Operator {
+        version:u8,
+        type_id:TypeID,
+        length_id:LengthId,
+        declared:usize,
+        offset:usize,
+        length:usize,
+        sub_packets:Vec<PacketNode>,
+    },
+}
+
+impl PacketNode {
+    /// Literal that isn't part of a transmission yet, offset and length are 0.
+    pub fn literal(version:u8, value:u64) -> PacketNode {
+        PacketNode::big_literal(version, BigUint::from(value))
+    }
+
+    pub fn big_literal(version:u8, value:BigUint) -> PacketNode {
+        PacketNode::Literal{version, value, offset:0, length:0}
+    }
+
+    /// Operator that isn't part of a transmission yet, its sub packet count is encoded.
+    pub fn operator(version:u8, type_id:TypeID, sub_packets:Vec<PacketNode>) -> PacketNode {
+        let declared = sub_packets.len();
+        PacketNode::Operator{version, type_id, length_id:LengthId::NumberOfPackets, declared, offset:0, length:0, sub_packets}
+    }
+
+    pub fn version(&self) -> u8 {
+        match self {
+            PacketNode::Literal{version, ..} | PacketNode::Operator{version, ..} => *version,
+        }
+    }
+
+    pub fn type_id(&self) -> TypeID {
+        match self {
+            PacketNode::Literal{..} => TypeID::LITERAL,
+            PacketNode::Operator{type_id, ..} => *type_id,
+        }
+    }
+
+    /// Length type of an operator, `None` for literals.
+    pub fn length_id(&self) -> Option<LengthId> {
+        match self {
+            PacketNode::Literal{..} => None,
+            PacketNode::Operator{length_id, ..} => Some(*length_id),
+        }
+    }
+
+    /// Value of the length field of an operator, `None` for literals.
+    pub fn declared_length(&self) -> Option<usize> {
+        match self {
+            PacketNode::Literal{..} => None,
+            PacketNode::Operator{declared, ..} => Some(*declared),
+        }
+    }
+
+    pub fn offset(&self) -> usize {
+        match self {
+            PacketNode::Literal{offset, ..} | PacketNode::Operator{offset, ..} => *offset,
+        }
+    }
+
+    pub fn bit_length(&self) -> usize {
+        match self {
+            PacketNode::Literal{length, ..} | PacketNode::Operator{length, ..} => *length,
+        }
+    }
+
+    pub fn sub_packets(&self) -> &[PacketNode] {
+        match self {
+            PacketNode::Literal{..} => &[],
+            PacketNode::Operator{sub_packets, ..} => sub_packets,
+        }
+    }
+
+    /// Sum of the versions of this packet and all packets below it.
+    pub fn version_sum(&self) -> u64 {
+        self.walk().map(|(_, packet)| packet.version() as u64).sum()
+    }
+
+    /// Value of the expression this packet describes, with arbitrary precision.
+    pub fn evaluate_big(&self) -> Result<BigUint> {
+        self.evaluate_with(&Dialect::classic())
+    }
+
+    /// Value with the operators of `dialect`.
+    pub fn evaluate_with(&self, dialect:&Dialect) -> Result<BigUint> {
+        dialect.evaluate(self)
+    }
+
+    /// Value of the expression this packet describes, fails if a literal or
+    /// intermediate value doesn't fit in a `u64`.
+    pub fn evaluate(&self) -> Result<u64> {
+        self.fold(|packet, values| {
+            let type_id = match packet {
+                PacketNode::Literal{value, offset, ..} => {
+                    return u64::try_from(value).map_err(|_| overflow(*offset));
+                }
+                PacketNode::Operator{type_id, ..} => *type_id,
+            };
+            check_arity(type_id, values.len())?;
+
+            match type_id {
+                TypeID::SUM => values.iter().try_fold(0u64, |sum, &value| sum.checked_add(value)).ok_or_else(|| overflow(packet.offset())),
+                TypeID::PRODUCT => values.iter().try_fold(1u64, |product, &value| product.checked_mul(value)).ok_or_else(|| overflow(packet.offset())),
+                TypeID::MIN => Ok(*values.iter().min().unwrap()),
+                TypeID::MAX => Ok(*values.iter().max().unwrap()),
+                TypeID::GreaterThan => Ok((values[0] > values[1]) as u64),
+                TypeID::LessThan => Ok((values[0] < values[1]) as u64),
+                TypeID::EQUAL => Ok((values[0] == values[1]) as u64),
+                TypeID::LITERAL => Err(SolveError::new("literal packet used as operator")),
+            }
+        })
+    }
+
+    /// Combines the results of the sub packets of every packet with `f`,
+    /// bottom up and left to right, stopping at the first error. Doesn't
+    /// recurse, so any depth of nesting works.
+    pub fn fold<T, F>(&self, mut f:F) -> Result<T>
+        where F:FnMut(&PacketNode, Vec<T>) -> Result<T> {
+        // Packets are pushed again once their sub packets are on the stack
+        let mut stack = vec![(self, false)];
+        let mut results:Vec<T> = vec![];
+        while let Some((packet, expanded)) = stack.pop() {
+            if expanded {
+                let values = results.split_off(results.len() - packet.sub_packets().len());
+                results.push(f(packet, values)?);
+            } else {
+                stack.push((packet, true));
+                stack.extend(packet.sub_packets().iter().rev().map(|sub| (sub, false)));
+            }
+        }
+        Ok(results.pop().unwrap())
+    }
+
+    /// Depth first walk over this packet and all packets below it, with the
+    /// depth relative to this packet.
+    pub fn walk(&self) -> Walk<'_> {
+        Walk{stack:vec![(0, self)]}
+    }
+}
+
+impl Drop for PacketNode {
+    fn drop(&mut self) {
+        // The default drop recurses once per level of nesting
+        let mut stack = match self {
+            PacketNode::Literal{..} => return,
+            PacketNode::Operator{sub_packets, ..} => mem::take(sub_packets),
+        };
+        while let Some(mut packet) = stack.pop() {
+            if let PacketNode::Operator{sub_packets, ..} = &mut packet {
+                stack.append(sub_packets);
+            }
+        }
+    }
 }
 
-struct LiteralPacket {
-    value:u64,
+fn check_arity(type_id:TypeID, sub_packets:usize) -> Result<()> {
+    if sub_packets == 0 || type_id.is_comparison() && sub_packets != 2 {
+        let message = format!("{:?} packet with {} sub packets", type_id, sub_packets);
+        return Err(SolveError::new(&message));
+    }
+    Ok(())
+}
+
+fn overflow(offset:usize) -> SolveError {
+    SolveError::new(&format!("value of packet at bit {} doesn't fit in 64 bits", offset))
 }
 
-struct OperatorPacket {
-    sub_packets:Vec<Box<dyn Packet>>,
-    type_id:TypeID,
+/// Iterator returned by `PacketNode::walk`.
+pub struct Walk<'a> {
+    stack:Vec<(usize, &'a PacketNode)>,
 }
 
-impl Packet for LiteralPacket {
-    fn process(&self) -> u64 {
-        self.value
+impl<'a> Iterator for Walk<'a> {
+    type Item = (usize, &'a PacketNode);
+
+    fn next(&mut self) -> Option<(usize, &'a PacketNode)> {
+        let (depth, packet) = self.stack.pop()?;
+        self.stack.extend(packet.sub_packets().iter().rev().map(|sub| (depth + 1, sub)));
+        Some((depth, packet))
     }
 }
 
-impl Packet for OperatorPacket {
-    fn process(&self) -> u64 {
-        let values:Vec<u64> = self.sub_packets.iter()
-            .map(|sub| sub.process()).collect();
+/// Problem found while decoding, positions count from 0.
+#[derive(Debug, Clone, PartialEq)]
+pub enum DecodeError {
+    InvalidHex{position:usize, ch:char},
+    Truncated{bit:usize},
+    /// Operator at `offset` without sub packets, or a comparison without exactly two.
+    Arity{offset:usize, type_id:TypeID, sub_packets:usize},
+    /// Non-zero bit after the outermost packet.
+    TrailingBits{bit:usize},
+    /// The sub packets of the operator at `offset` cover more bits than its length says.
+    Overrun{offset:usize, expected:usize, actual:usize},
+    /// Reading the transmission from a stream failed.
+    Io{message:String},
+    /// Packet at `offset` nested in more than `MAX_DEPTH` operators.
+    TooDeep{offset:usize},
+}
+
+impl DecodeError {
+    /// Column of the hex digit the problem is in.
+    pub fn column(&self) -> usize {
+        match self {
+            DecodeError::InvalidHex{position, ..} => position + 1,
+            DecodeError::Io{..} => 1,
+            DecodeError::Truncated{bit} | DecodeError::TrailingBits{bit}
+            | DecodeError::Arity{offset:bit, ..} | DecodeError::Overrun{offset:bit, ..}
+            | DecodeError::TooDeep{offset:bit} => bit / 4 + 1,
+        }
+    }
+}
 
-        match self.type_id {
-            TypeID::SUM => values.iter().fold(0, |acc,it| acc+it),
-            TypeID::PRODUCT => values.iter().fold(1, |acc,it| acc * it),
-            TypeID::MIN => *values.iter().min().unwrap(),
-            TypeID::MAX => *values.iter().max().unwrap(),
-            TypeID::GreaterThan => (values[0] > values[1]) as u64,
-            TypeID::LessThan => (values[0] < values[1]) as u64,
-            TypeID::EQUAL => (values[0] == values[1]) as u64,
-            _ => panic!("not impl"),
+impl fmt::Display for DecodeError {
+    fn fmt(&self, f:&mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DecodeError::InvalidHex{position, ch} => write!(f, "invalid hex digit '{}' at char {}", ch, position),
+            DecodeError::Truncated{bit} => write!(f, "transmission truncated at bit {}", bit),
+            DecodeError::Arity{offset, type_id, sub_packets} => {
+                write!(f, "{:?} packet at bit {} with {} sub packets", type_id, offset, sub_packets)
+            }
+            DecodeError::TrailingBits{bit} => write!(f, "non-zero padding at bit {}", bit),
+            DecodeError::Overrun{offset, expected, actual} => {
+                write!(f, "sub packets of packet at bit {} cover {} bits instead of {}", offset, actual, expected)
+            }
+            DecodeError::Io{message} => write!(f, "can't read transmission: {}", message),
+            DecodeError::TooDeep{offset} => {
+                write!(f, "packet at bit {} is nested deeper than {} packets", offset, MAX_DEPTH)
+            }
         }
     }
 }
 
+impl From<DecodeError> for SolveError {
+    fn from(error:DecodeError) -> SolveError {
+        SolveError::at(1, error.column(), &error.to_string())
+    }
+}
+
+pub type DecodeResult<T> = ::std::result::Result<T, DecodeError>;
+
+/// Strict decoding fails on the first problem, lenient decoding only fails
+/// when the transmission can't be decoded at all and collects the rest as warnings.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Mode {
+    Strict,
+    Lenient,
+}
+
+/// Decodes the outermost packet of a hex transmission, ignoring problems that
+/// don't stop decoding.
+pub fn parse(line:&str) -> Result<PacketNode> {
+    Ok(PacketComputer::new(line)?.parse()?)
+}
+
+/// Decodes the outermost packet in `mode`, returning it with the warnings of
+/// lenient decoding.
+pub fn decode(line:&str, mode:Mode) -> DecodeResult<(PacketNode, Vec<DecodeError>)> {
+    decode_from(BitCursor::from_hex(line)?, mode)
+}
+
+/// Like `decode`, for raw bytes or a stream.
+pub fn decode_from<R:BitRead>(reader:R, mode:Mode) -> DecodeResult<(PacketNode, Vec<DecodeError>)> {
+    let mut computer = PacketComputer::from_reader(reader, mode);
+    let packet = computer.parse()?;
+    Ok((packet, computer.warnings))
+}
+
 impl PacketComputer {
-    pub(crate) fn new(line:&str) -> PacketComputer {
-        let bits: VecDeque<bool> = line
-            .chars()
-            .map(|ch| u8::from_str_radix(&ch.to_string(), 16).unwrap())
-            .map(|byte| {
-                vec![byte >> 3 & 1 > 0,
-                     byte >> 2 & 1 > 0,
-                     byte >> 1 & 1 > 0,
-                     byte & 1 > 0 ]
-            })
-            .flatten()
-            .collect();
+    pub fn new(line:&str) -> DecodeResult<PacketComputer> {
+        PacketComputer::with_mode(line, Mode::Lenient)
+    }
 
-        PacketComputer {bits,offset:0, version_sum:0}
+    pub fn with_mode(line:&str, mode:Mode) -> DecodeResult<PacketComputer> {
+        Ok(PacketComputer::from_reader(BitCursor::from_hex(line)?, mode))
     }
+}
 
+impl<R:BitRead> PacketComputer<R> {
+    pub fn from_reader(reader:R, mode:Mode) -> PacketComputer<R> {
+        PacketComputer{reader, mode, warnings:vec![], depth:0}
+    }
 
+    /// Problems found so far in lenient mode.
+    pub fn warnings(&self) -> &[DecodeError] {
+        &self.warnings
+    }
 
-    fn process_literal(&mut self) -> Box<dyn Packet> {
-        let (_no_bits, literal_value) = self.read_literal();
-        //println!("   => processed literal of size:{}, value:{}", no_bits, literal_value);
-        Box::new(LiteralPacket{value:literal_value})
+    fn report(&mut self, error:DecodeError) -> DecodeResult<()> {
+        match self.mode {
+            Mode::Strict => Err(error),
+            Mode::Lenient => {
+                self.warnings.push(error);
+                Ok(())
+            }
+        }
     }
 
-    fn process_operator(&mut self, type_id:TypeID) -> Box<dyn Packet> {
-        let length_id = self.read_length_type();
+    fn process_operator(&mut self, version:u8, type_id:TypeID, offset:usize) -> DecodeResult<PacketNode> {
+        let length_id = self.read_length_type()?;
+        let declared = self.read_bits(if length_id == LengthId::NumberOfBits { 15 } else { 11 })? as usize;
+        if self.depth == MAX_DEPTH && declared > 0 {
+            return Err(DecodeError::TooDeep{offset:self.reader.position()});
+        }
+
+        self.depth += 1;
         let sub_packets = if length_id == LengthId::NumberOfBits {
-            let no_bits = self.read_bits(15) as usize;
-            //println!("  ==> Process sub packets: no_bits:{}", no_bits);
-            self.process_sub_packet_bits(no_bits)
+            self.process_sub_packet_bits(offset, declared)
         } else {
-            let no_packets = self.read_bits(11);
-            (0..no_packets).into_iter().map(|_| self.process_packet()).collect()
+            (0..declared).map(|_| self.process_packet()).collect::<DecodeResult<Vec<PacketNode>>>()
         };
+        self.depth -= 1;
+        let sub_packets = sub_packets?;
+
+        if sub_packets.is_empty() || type_id.is_comparison() && sub_packets.len() != 2 {
+            self.report(DecodeError::Arity{offset, type_id, sub_packets:sub_packets.len()})?;
+        }
 
-        Box::new(OperatorPacket {sub_packets, type_id })
+        Ok(PacketNode::Operator{version, type_id, length_id, declared, offset, length:self.reader.position() - offset, sub_packets})
     }
 
-    fn process_packet(&mut self) -> Box<dyn Packet> {
-        let _version = self.read_version();
-        let type_id = self.read_type();
+    fn process_packet(&mut self) -> DecodeResult<PacketNode> {
+        let offset = self.reader.position();
+        let version = self.read_bits(3)? as u8;
+        let type_id = self.read_type()?;
 
-        //println!("=> Found SUB packet version:{}, type={:?}", version, type_id);
         match type_id {
-            TypeID::LITERAL => self.process_literal(),
-            _ => self.process_operator(type_id)
+            TypeID::LITERAL => {
+                let value = self.read_literal()?;
+                Ok(PacketNode::Literal{version, value, offset, length:self.reader.position() - offset})
+            }
+            _ => self.process_operator(version, type_id, offset)
         }
     }
 
-    fn process_sub_packet_bits(&mut self, len:usize) -> Vec<Box<dyn Packet>> {
-        let offset = self.offset;
-        let mut sub_packets: Vec<Box<dyn Packet>> = vec![];
-        while offset + len > self.offset {
-            sub_packets.push(self.process_packet());
+    fn process_sub_packet_bits(&mut self, operator:usize, len:usize) -> DecodeResult<Vec<PacketNode>> {
+        let offset = self.reader.position();
+        let mut sub_packets = vec![];
+        while offset + len > self.reader.position() {
+            sub_packets.push(self.process_packet()?);
         }
 
-        sub_packets
+        if self.reader.position() != offset + len {
+            let actual = self.reader.position() - offset;
+            self.report(DecodeError::Overrun{offset:operator, expected:len, actual})?;
+        }
+        Ok(sub_packets)
     }
 
-    pub(crate) fn run(&mut self) -> u64 {
-        let packet = self.process_packet();
-        packet.process()
+    /// Decodes the outermost packet, the bits after it must be zero.
+    pub fn parse(&mut self) -> DecodeResult<PacketNode> {
+        let packet = self.process_packet()?;
+        if let Some(bit) = self.reader.next_set_bit()? {
+            self.report(DecodeError::TrailingBits{bit})?;
+        }
+        Ok(packet)
     }
 
-    fn read_bits(&mut self, no_bits:usize) -> u64 {
-        let mut result = 0;
-        for n in 0..no_bits {
-            let bit = self.bits.pop_front().unwrap() as u32;
-            let bit_pos = no_bits - 1 - n;
-            result = result | (bit << bit_pos);
-            self.offset += 1;
-        }
-        result as u64
+    /// Decodes the outermost packet and returns its value.
+    pub fn run(&mut self) -> Result<u64> {
+        self.parse()?.evaluate()
     }
 
-    fn read_version(&mut self) -> u64 {
-        let version = self.read_bits(3);
-        self.version_sum += version;
-        version
+    fn read_bits(&mut self, no_bits:usize) -> DecodeResult<u64> {
+        self.reader.read_bits(no_bits)
     }
 
-    fn read_type(&mut self) -> TypeID {
+    fn read_type(&mut self) -> DecodeResult<TypeID> {
+        // Every 3 bit value is a type
+        Ok(TypeID::from_bits(self.read_bits(3)?).unwrap())
+    }
 
-        match self.read_bits(3) {
-            0 => TypeID::SUM,
-            1 => TypeID::PRODUCT,
-            2 => TypeID::MIN,
-            3 => TypeID::MAX,
-            4 => TypeID::LITERAL,
-            5 => TypeID::GreaterThan,
-            6 => TypeID::LessThan,
-            7 => TypeID::EQUAL,
-            _ => panic!("unknown operator"),
+    fn read_length_type(&mut self) -> DecodeResult<LengthId> {
+        match self.read_bits(1)? {
+            0 => Ok(LengthId::NumberOfBits),
+            _ => Ok(LengthId::NumberOfPackets),
         }
     }
 
-    fn read_length_type(&mut self) -> LengthId {
-        match self.read_bits(1) {
-            0 => LengthId::NumberOfBits,
-            1 => LengthId::NumberOfPackets,
-            _ => panic!("invalid length id")
+    /// Literal of any number of groups.
+    fn read_literal(&mut self) -> DecodeResult<BigUint> {
+        let mut digits = vec![];
+        let mut flag = 1;
+        while flag == 1 {
+            flag = self.read_bits(1)?;
+            digits.push(self.read_bits(4)? as u8);
         }
+
+        Ok(BigUint::from_radix_be(&digits, 16).unwrap())
     }
+}
 
-    fn read_literal(&mut self) -> (usize,u64) {
-        let mut flag = self.read_bits(1);
-        let mut value = self.read_bits(4);
-        let mut no_bits = 4;
-        while flag == 1 {
-            flag = self.read_bits(1);
-            let next_value = self.read_bits(4);
-            value = (value << 4) + next_value;
-            no_bits += 4;
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use util::packet_computer::encoder::{to_hex, Encoder, LengthStrategy, LiteralStrategy};
+    use util::packet_computer::bits::{Format, StreamCursor};
+    use util::packet_computer::expression::compile_hex;
+
+    #[test]
+    fn test_literal() {
+        let packet = parse("D2FE28").unwrap();
+        assert_eq!(PacketNode::Literal{version:6, value:BigUint::from(2021u32), offset:0, length:21}, packet);
+        assert_eq!(TypeID::LITERAL, packet.type_id());
+        assert_eq!(None, packet.length_id());
+        assert_eq!(Ok(2021), packet.evaluate());
+    }
+
+    #[test]
+    fn test_operator() {
+        let packet = parse("38006F45291200").unwrap();
+        assert_eq!((1, TypeID::LessThan, Some(LengthId::NumberOfBits)), (packet.version(), packet.type_id(), packet.length_id()));
+        assert_eq!((49, Some(27)), (packet.bit_length(), packet.declared_length()));
+
+        let sub_packets = packet.sub_packets();
+        assert_eq!(2, sub_packets.len());
+        assert_eq!((22, 11), (sub_packets[0].offset(), sub_packets[0].bit_length()));
+        assert_eq!((33, 16), (sub_packets[1].offset(), sub_packets[1].bit_length()));
+        assert_eq!(Ok(1), packet.evaluate());
+
+        let packet = parse("EE00D40C823060").unwrap();
+        assert_eq!((Some(LengthId::NumberOfPackets), Some(3)), (packet.length_id(), packet.declared_length()));
+        assert_eq!(Ok(3), packet.evaluate());
+    }
+
+    #[test]
+    fn test_walk() {
+        let packet = parse("8A004A801A8002F478").unwrap();
+        let walk:Vec<(usize, u8)> = packet.walk().map(|(depth, packet)| (depth, packet.version())).collect();
+        assert_eq!(vec![(0, 4), (1, 1), (2, 5), (3, 6)], walk);
+        assert_eq!(16, packet.version_sum());
+
+        assert_eq!(31, parse("A0016C880162017C3686B18A3D4780").unwrap().version_sum());
+    }
+
+    #[test]
+    fn test_invalid_arity() {
+        let packet = PacketNode::Operator{version:0, type_id:TypeID::GreaterThan, length_id:LengthId::NumberOfPackets,
+                                          declared:0, offset:0, length:0, sub_packets:vec![]};
+        assert_eq!("GreaterThan packet with 0 sub packets", packet.evaluate().unwrap_err().message);
+    }
+
+    #[test]
+    fn test_strict() {
+        assert_eq!(Ok(vec![]), decode("9C0141080250320F1802104A08", Mode::Strict).map(|(_, warnings)| warnings));
+        assert_eq!(Err(DecodeError::InvalidHex{position:3, ch:'G'}), decode("8A0G4A", Mode::Strict));
+        assert_eq!(Err(DecodeError::Truncated{bit:16}), decode("D2FE", Mode::Lenient));
+
+        assert_eq!(Err(DecodeError::TrailingBits{bit:23}), decode("D2FE29", Mode::Strict));
+        assert_eq!(Err(DecodeError::Arity{offset:0, type_id:TypeID::GreaterThan, sub_packets:1}), decode(&compile_hex("gt(1)").unwrap(), Mode::Strict));
+
+        let error = SolveError::from(DecodeError::Truncated{bit:16});
+        assert_eq!((Some(1), Some(5)), (error.line, error.column));
+        assert_eq!("transmission truncated at bit 16", error.message);
+    }
+
+    #[test]
+    fn test_lenient() {
+        let (packet, warnings) = decode("D2FE29", Mode::Lenient).unwrap();
+        assert_eq!(Ok(2021), packet.evaluate());
+        assert_eq!(vec![DecodeError::TrailingBits{bit:23}], warnings);
+
+        // Sum declaring 5 bits of sub packets followed by an 11 bit literal
+        let packet = PacketNode::operator(0, TypeID::SUM, vec![PacketNode::literal(0, 1)]);
+        let mut bits = Encoder::new(LiteralStrategy::Minimal, LengthStrategy::Bits).encode_bits(&packet).unwrap();
+        for (n, bit) in bits[7..22].iter_mut().enumerate() {
+            *bit = 5 >> (14 - n) & 1 == 1;
         }
 
-        (no_bits, value)
+        let overrun = DecodeError::Overrun{offset:0, expected:5, actual:11};
+        assert_eq!(Err(overrun.clone()), decode(&to_hex(&bits), Mode::Strict));
+        let (packet, warnings) = decode(&to_hex(&bits), Mode::Lenient).unwrap();
+        assert_eq!((Ok(1), vec![overrun]), (packet.evaluate(), warnings));
+    }
+
+    #[test]
+    fn test_big_values() {
+        let packet = parse(&compile_hex("18446744073709551615 * 16 + 1").unwrap()).unwrap();
+        assert_eq!("295147905179352825841", packet.evaluate_big().unwrap().to_string());
+        assert_eq!("value of packet at bit 18 doesn't fit in 64 bits", packet.evaluate().unwrap_err().message);
+
+        let value = "340282366920938463463374607431768211457".parse::<BigUint>().unwrap();
+        let packet = parse(&encoder::encode(&PacketNode::big_literal(1, value.clone())).unwrap()).unwrap();
+        assert_eq!((Ok(value), 6 + 33 * 5), (packet.evaluate_big(), packet.bit_length()));
+        assert!(packet.evaluate().is_err());
+
+        assert_eq!(Ok(u64::MAX), parse(&compile_hex("max(18446744073709551615, 1)").unwrap()).unwrap().evaluate());
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_deep() {
+        let nested = |depth| (0..depth).fold(PacketNode::literal(0, 1), |packet, _| PacketNode::operator(0, TypeID::SUM, vec![packet]));
+
+        let packet = nested(200_000);
+        assert_eq!(Ok(1), packet.evaluate());
+        assert_eq!(Ok(BigUint::from(1u8)), packet.evaluate_big());
+        assert_eq!(format!("{}1{}", "sum(".repeat(200_000), ")".repeat(200_000)), disassembler::infix(&packet));
+
+        // Every sum takes 18 bits, the decoder stops at the first packet in too many
+        let hex = encoder::encode(&packet).unwrap();
+        assert_eq!(Err(DecodeError::TooDeep{offset:18 * (MAX_DEPTH + 1)}), decode(&hex, Mode::Lenient));
+        let error = decode_from(StreamCursor::new(hex.as_bytes(), Format::Hex), Mode::Lenient).unwrap_err();
+        assert_eq!(format!("packet at bit {} is nested deeper than {} packets", 18 * (MAX_DEPTH + 1), MAX_DEPTH), error.to_string());
+
+        let packet = nested(MAX_DEPTH);
+        let (decoded, _) = decode(&encoder::encode(&packet).unwrap(), Mode::Strict).unwrap();
+        assert_eq!(MAX_DEPTH, decoded.walk().map(|(depth, _)| depth).max().unwrap());
+        assert_eq!(Ok(1), decoded.evaluate());
+    }
+
+    #[test]
+    fn test_sources() {
+        let input = include_str!("../../input_16.txt");
+        let expected = decode(input.trim(), Mode::Strict).unwrap();
+        assert_eq!(expected, decode_from(StreamCursor::new(input.as_bytes(), Format::Hex), Mode::Strict).unwrap());
+
+        let bytes:Vec<u8> = (0..input.trim().len() / 2).map(|i| u8::from_str_radix(&input[2 * i..2 * i + 2], 16).unwrap()).collect();
+        assert_eq!(expected, decode_from(BitCursor::new(&bytes), Mode::Strict).unwrap());
+        assert_eq!(expected, decode_from(StreamCursor::new(&bytes[..], Format::Binary), Mode::Strict).unwrap());
+
+        let error = decode_from(StreamCursor::new("D2FE\n".as_bytes(), Format::Hex), Mode::Strict).unwrap_err();
+        assert_eq!(DecodeError::Truncated{bit:16}, error);
+    }
+}
@@ -0,0 +1,373 @@
+use std::borrow::Cow;
+use std::io::{ErrorKind, Read};
+use super::{DecodeError, DecodeResult};
+
+/// Source of bits for the decoder, most significant bit first.
+pub trait BitRead {
+    /// Reads `no_bits` bits, at most 64, as an unsigned number.
+    fn read_bits(&mut self, no_bits:usize) -> DecodeResult<u64>;
+
+    /// Number of bits read so far.
+    fn position(&self) -> usize;
+
+    /// Consumes the rest of the input and returns the position of its first set bit.
+    fn next_set_bit(&mut self) -> DecodeResult<Option<usize>>;
+
+    /// Whether all bits have been read.
+    fn at_end(&mut self) -> DecodeResult<bool>;
+
+    /// Skips the rest of the input, or of the line for a hex stream, if it is
+    /// all zero bits and tells whether it did. Nothing is skipped otherwise.
+    fn skip_padding(&mut self) -> DecodeResult<bool>;
+}
+
+/// Cursor over bits packed in bytes, borrowing the buffer when it can.
+#[derive(Debug, Clone)]
+pub struct BitCursor<'a> {
+    bytes:Cow<'a, [u8]>,
+    len:usize,
+    pos:usize,
+}
+
+impl<'a> BitCursor<'a> {
+    pub fn new(bytes:&'a [u8]) -> BitCursor<'a> {
+        BitCursor{len:bytes.len() * 8, bytes:Cow::Borrowed(bytes), pos:0}
+    }
+
+    /// Packs the hex digits of `line`, an odd number of digits leaves the last
+    /// 4 bits of the buffer unused.
+    pub fn from_hex(line:&str) -> DecodeResult<BitCursor<'static>> {
+        let mut bytes = Vec::with_capacity(line.len().div_ceil(2));
+        for (position, ch) in line.chars().enumerate() {
+            let digit = ch.to_digit(16).ok_or(DecodeError::InvalidHex{position, ch})? as u8;
+            if position % 2 == 0 {
+                bytes.push(digit << 4);
+            } else {
+                *bytes.last_mut().unwrap() |= digit;
+            }
+        }
+        Ok(BitCursor{len:line.chars().count() * 4, bytes:Cow::Owned(bytes), pos:0})
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn position(&self) -> usize {
+        self.pos
+    }
+
+    pub fn remaining(&self) -> usize {
+        self.len - self.pos
+    }
+
+    /// Moves to bit `pos`, which may be the end but not past it.
+    pub fn seek(&mut self, pos:usize) -> Option<()> {
+        if pos > self.len {
+            return None;
+        }
+        self.pos = pos;
+        Some(())
+    }
+
+    /// The next `no_bits` bits without moving, `None` if there aren't that many.
+    pub fn peek(&self, no_bits:usize) -> Option<u64> {
+        assert!(no_bits <= 64, "can't read more than 64 bits at once");
+        if no_bits > self.remaining() {
+            return None;
+        }
+
+        let mut value = 0u64;
+        let mut pos = self.pos;
+        let mut left = no_bits;
+        while left > 0 {
+            let available = 8 - pos % 8;
+            let take = available.min(left);
+            let chunk = (self.bytes[pos / 8] >> (available - take)) as u64 & ((1 << take) - 1);
+            value = value << take | chunk;
+            pos += take;
+            left -= take;
+        }
+        Some(value)
+    }
+
+    pub fn read(&mut self, no_bits:usize) -> Option<u64> {
+        let value = self.peek(no_bits)?;
+        self.pos += no_bits;
+        Some(value)
+    }
+}
+
+impl<'a> BitRead for BitCursor<'a> {
+    fn read_bits(&mut self, no_bits:usize) -> DecodeResult<u64> {
+        self.read(no_bits).ok_or(DecodeError::Truncated{bit:self.len})
+    }
+
+    fn position(&self) -> usize {
+        self.pos
+    }
+
+    fn next_set_bit(&mut self) -> DecodeResult<Option<usize>> {
+        let start = self.pos;
+        self.pos = self.len;
+        Ok((start..self.len).find(|pos| self.bytes[pos / 8] >> (7 - pos % 8) & 1 == 1))
+    }
+
+    fn at_end(&mut self) -> DecodeResult<bool> {
+        Ok(self.pos == self.len)
+    }
+
+    fn skip_padding(&mut self) -> DecodeResult<bool> {
+        let pos = self.pos;
+        if self.next_set_bit()?.is_some() {
+            self.pos = pos;
+            return Ok(false);
+        }
+        Ok(true)
+    }
+}
+
+/// How a stream encodes its bits.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Format {
+    /// Hex digits, whitespace is skipped.
+    Hex,
+    /// Raw bytes.
+    Binary,
+}
+
+/// Reads bits from a `Read` as they are needed, so the whole input never has
+/// to be in memory.
+pub struct StreamCursor<R> {
+    reader:R,
+    format:Format,
+    chunk:Vec<u8>,
+    chunk_pos:usize,
+    /// Bytes taken from the reader so far, for error positions.
+    consumed:usize,
+    buffer:u128,
+    buffered:usize,
+    /// Zero bits that come before the buffer, counted by `skip_padding`.
+    zeros:usize,
+    pos:usize,
+}
+
+impl<R:Read> StreamCursor<R> {
+    pub fn new(reader:R, format:Format) -> StreamCursor<R> {
+        StreamCursor{reader, format, chunk:vec![], chunk_pos:0, consumed:0, buffer:0, buffered:0, zeros:0, pos:0}
+    }
+
+    fn next_byte(&mut self) -> DecodeResult<Option<u8>> {
+        if self.chunk_pos == self.chunk.len() {
+            self.chunk.resize(8192, 0);
+            let read = loop {
+                match self.reader.read(&mut self.chunk) {
+                    Ok(read) => break read,
+                    Err(ref e) if e.kind() == ErrorKind::Interrupted => continue,
+                    Err(e) => return Err(DecodeError::Io{message:e.to_string()}),
+                }
+            };
+            self.chunk.truncate(read);
+            self.chunk_pos = 0;
+            if read == 0 {
+                return Ok(None);
+            }
+        }
+
+        self.chunk_pos += 1;
+        self.consumed += 1;
+        Ok(Some(self.chunk[self.chunk_pos - 1]))
+    }
+
+    /// Adds the bits of the next byte or hex digit to the buffer, false at the end.
+    fn fill(&mut self) -> DecodeResult<bool> {
+        loop {
+            let byte = match self.next_byte()? {
+                Some(byte) => byte,
+                None => return Ok(false),
+            };
+
+            match self.format {
+                Format::Binary => {
+                    self.buffer = self.buffer << 8 | byte as u128;
+                    self.buffered += 8;
+                }
+                Format::Hex if byte.is_ascii_whitespace() => continue,
+                Format::Hex => {
+                    let ch = byte as char;
+                    let digit = ch.to_digit(16).ok_or(DecodeError::InvalidHex{position:self.consumed - 1, ch})?;
+                    self.buffer = self.buffer << 4 | digit as u128;
+                    self.buffered += 4;
+                }
+            }
+            return Ok(true);
+        }
+    }
+}
+
+impl<R:Read> BitRead for StreamCursor<R> {
+    fn read_bits(&mut self, no_bits:usize) -> DecodeResult<u64> {
+        assert!(no_bits <= 64, "can't read more than 64 bits at once");
+        // Leading zeros don't change the value
+        let zeros = no_bits.min(self.zeros);
+        self.zeros -= zeros;
+        self.pos += zeros;
+        let no_bits = no_bits - zeros;
+
+        while self.buffered < no_bits {
+            if !self.fill()? {
+                return Err(DecodeError::Truncated{bit:self.pos + self.buffered});
+            }
+        }
+
+        // The buffer only holds the unread bits
+        self.buffered -= no_bits;
+        let value = (self.buffer >> self.buffered) as u64;
+        self.buffer &= (1 << self.buffered) - 1;
+        self.pos += no_bits;
+        Ok(value)
+    }
+
+    fn position(&self) -> usize {
+        self.pos
+    }
+
+    fn next_set_bit(&mut self) -> DecodeResult<Option<usize>> {
+        self.pos += self.zeros;
+        self.zeros = 0;
+        let mut found = None;
+        loop {
+            if found.is_none() && self.buffer != 0 {
+                found = Some(self.pos + self.buffered - (128 - self.buffer.leading_zeros() as usize));
+            }
+            self.pos += self.buffered;
+            self.buffer = 0;
+            self.buffered = 0;
+            if !self.fill()? {
+                return Ok(found);
+            }
+        }
+    }
+
+    fn at_end(&mut self) -> DecodeResult<bool> {
+        Ok(self.zeros == 0 && self.buffered == 0 && !self.fill()?)
+    }
+
+    fn skip_padding(&mut self) -> DecodeResult<bool> {
+        if self.buffer != 0 {
+            return Ok(false);
+        }
+
+        // Counts the zeros without keeping them, they are only read back if a set bit follows
+        let mut zeros = self.zeros + self.buffered;
+        self.buffered = 0;
+        loop {
+            let (value, bits) = match (self.next_byte()?, self.format) {
+                (None, _) | (Some(b'\n'), Format::Hex) => {
+                    self.pos += zeros;
+                    self.zeros = 0;
+                    return Ok(true);
+                }
+                (Some(byte), Format::Binary) => (byte as u32, 8),
+                (Some(byte), Format::Hex) if byte.is_ascii_whitespace() => continue,
+                (Some(byte), Format::Hex) => {
+                    let ch = byte as char;
+                    (ch.to_digit(16).ok_or(DecodeError::InvalidHex{position:self.consumed - 1, ch})?, 4)
+                }
+            };
+
+            if value != 0 {
+                self.buffer = value as u128;
+                self.buffered = bits;
+                self.zeros = zeros;
+                return Ok(false);
+            }
+            zeros += bits;
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cursor() {
+        let bytes = [0b1010_0000, 0xff, 0x01];
+        let mut cursor = BitCursor::new(&bytes);
+        assert_eq!((24, Some(0b101)), (cursor.len(), cursor.peek(3)));
+        assert_eq!(Some(0b1010_0000_1111), cursor.read(12));
+        assert_eq!(Some(0b1111_0000_0001), cursor.read(12));
+        assert_eq!((None, 0), (cursor.read(1), cursor.remaining()));
+
+        assert_eq!(Some(()), cursor.seek(7));
+        assert_eq!(Some(0x7f80), cursor.peek(16));
+        assert_eq!(None, cursor.seek(25));
+
+        let long = [0xde, 0xad, 0xbe, 0xef, 0x01, 0x23, 0x45, 0x67, 0x89];
+        let mut cursor = BitCursor::new(&long);
+        assert_eq!(Some(0xdeadbeef01234567), cursor.read(64));
+        cursor.seek(4);
+        assert_eq!(Some(0xeadbeef012345678), cursor.read(64));
+    }
+
+    #[test]
+    fn test_hex() {
+        let mut cursor = BitCursor::from_hex("D2FE28").unwrap();
+        assert_eq!(Ok(6), cursor.read_bits(3));
+        assert_eq!(Ok(Some(3)), cursor.next_set_bit());
+        assert_eq!((24, Ok(None)), (cursor.position(), cursor.next_set_bit()));
+
+        let mut cursor = BitCursor::from_hex("D2FE29").unwrap();
+        cursor.seek(21);
+        assert_eq!(Ok(Some(23)), cursor.next_set_bit());
+
+        let mut odd = BitCursor::from_hex("ABC").unwrap();
+        assert_eq!((12, Ok(0xabc)), (odd.len(), odd.read_bits(12)));
+        assert_eq!(Err(DecodeError::Truncated{bit:12}), odd.read_bits(1));
+        assert_eq!(Err(DecodeError::InvalidHex{position:2, ch:'x'}), BitCursor::from_hex("12x").map(|_| ()));
+    }
+
+    #[test]
+    fn test_stream() {
+        let mut stream = StreamCursor::new("D2 FE\n28\n".as_bytes(), Format::Hex);
+        assert_eq!(Ok(6), stream.read_bits(3));
+        assert_eq!(Ok(0b100_1011_1111_1000_1010), stream.read_bits(19));
+        assert_eq!((22, Ok(None)), (stream.position(), stream.next_set_bit()));
+
+        let mut stream = StreamCursor::new(&[0xde, 0xad, 0xbe, 0xef, 0x01, 0x23, 0x45, 0x67, 0x89][..], Format::Binary);
+        assert_eq!(Ok(0xd), stream.read_bits(4));
+        assert_eq!(Ok(0xeadbeef012345678), stream.read_bits(64));
+        assert_eq!(Ok(Some(68)), stream.next_set_bit());
+
+        let mut stream = StreamCursor::new("1G".as_bytes(), Format::Hex);
+        assert_eq!(Err(DecodeError::InvalidHex{position:1, ch:'G'}), stream.read_bits(8));
+        let mut stream = StreamCursor::new("1".as_bytes(), Format::Hex);
+        assert_eq!(Err(DecodeError::Truncated{bit:4}), stream.read_bits(8));
+    }
+
+    #[test]
+    fn test_skip_padding() {
+        let mut stream = StreamCursor::new("80\n0 0\n00F\n".as_bytes(), Format::Hex);
+        assert_eq!((Ok(false), Ok(1)), (stream.skip_padding(), stream.read_bits(1)));
+        assert_eq!((Ok(true), 8), (stream.skip_padding(), stream.position()));
+        assert_eq!((Ok(true), 16), (stream.skip_padding(), stream.position()));
+        assert_eq!(Ok(false), stream.skip_padding());
+        assert_eq!((16, Ok(0xf)), (stream.position(), stream.read_bits(12)));
+        assert_eq!((Ok(true), Ok(true)), (stream.skip_padding(), stream.at_end()));
+
+        let mut stream = StreamCursor::new(&[0, 0, 0x10][..], Format::Binary);
+        assert_eq!(Ok(false), stream.skip_padding());
+        assert_eq!(Ok(Some(19)), stream.next_set_bit());
+
+        let mut cursor = BitCursor::from_hex("D2FE2800").unwrap();
+        assert_eq!((Ok(false), 0), (cursor.skip_padding(), cursor.position()));
+        cursor.seek(21);
+        assert_eq!((Ok(true), 32), (cursor.skip_padding(), cursor.position()));
+    }
+}
@@ -0,0 +1,182 @@
+use num_bigint::BigUint;
+use util::error::{Result, SolveError};
+use super::{check_arity, PacketNode, TypeID};
+
+/// Semantics of an operator packet.
+pub trait Operator: Send + Sync {
+    fn name(&self) -> &'static str;
+
+    /// Value of the operator applied to the values of its sub packets.
+    fn evaluate(&self, values:&[BigUint]) -> Result<BigUint>;
+}
+
+/// The operator the puzzle defines for a type id.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Classic(pub TypeID);
+
+impl Operator for Classic {
+    fn name(&self) -> &'static str {
+        match self.0 {
+            TypeID::SUM => "sum",
+            TypeID::PRODUCT => "product",
+            TypeID::MIN => "min",
+            TypeID::MAX => "max",
+            TypeID::LITERAL => "literal",
+            TypeID::GreaterThan => "gt",
+            TypeID::LessThan => "lt",
+            TypeID::EQUAL => "eq",
+        }
+    }
+
+    fn evaluate(&self, values:&[BigUint]) -> Result<BigUint> {
+        check_arity(self.0, values.len())?;
+        match self.0 {
+            TypeID::SUM => Ok(values.iter().sum()),
+            TypeID::PRODUCT => Ok(values.iter().product()),
+            TypeID::MIN => Ok(values.iter().min().unwrap().clone()),
+            TypeID::MAX => Ok(values.iter().max().unwrap().clone()),
+            TypeID::GreaterThan => Ok(BigUint::from((values[0] > values[1]) as u8)),
+            TypeID::LessThan => Ok(BigUint::from((values[0] < values[1]) as u8)),
+            TypeID::EQUAL => Ok(BigUint::from((values[0] == values[1]) as u8)),
+            TypeID::LITERAL => Err(SolveError::new("literal packet used as operator")),
+        }
+    }
+}
+
+/// Mean of the values, rounded down.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Average;
+
+impl Operator for Average {
+    fn name(&self) -> &'static str {
+        "avg"
+    }
+
+    fn evaluate(&self, values:&[BigUint]) -> Result<BigUint> {
+        if values.is_empty() {
+            return Err(SolveError::new("average of no values"));
+        }
+        Ok(values.iter().sum::<BigUint>() / BigUint::from(values.len()))
+    }
+}
+
+/// Bitwise exclusive or of the values, 0 for no values.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Xor;
+
+impl Operator for Xor {
+    fn name(&self) -> &'static str {
+        "xor"
+    }
+
+    fn evaluate(&self, values:&[BigUint]) -> Result<BigUint> {
+        Ok(values.iter().fold(BigUint::default(), |result, value| result ^ value))
+    }
+}
+
+/// Operator semantics for each type id, type 4 is always a literal.
+pub struct Dialect {
+    name:&'static str,
+    operators:Vec<Option<Box<dyn Operator>>>,
+}
+
+impl Dialect {
+    /// Dialect with the classic operators, to be changed with `register`.
+    pub fn new(name:&'static str) -> Dialect {
+        let mut dialect = Dialect::empty(name);
+        for type_id in (0..8).filter_map(TypeID::from_bits).filter(|&type_id| type_id != TypeID::LITERAL) {
+            dialect.operators[type_id as usize] = Some(Box::new(Classic(type_id)));
+        }
+        dialect
+    }
+
+    /// Dialect without operators.
+    pub fn empty(name:&'static str) -> Dialect {
+        Dialect{name, operators:(0..8).map(|_| None).collect()}
+    }
+
+    /// The semantics of the puzzle.
+    pub fn classic() -> Dialect {
+        Dialect::new("classic")
+    }
+
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    /// Replaces the operator for `type_id`.
+    pub fn register<O:Operator + 'static>(&mut self, type_id:TypeID, operator:O) -> Result<()> {
+        if type_id == TypeID::LITERAL {
+            return Err(SolveError::new("type 4 is reserved for literals"));
+        }
+        self.operators[type_id as usize] = Some(Box::new(operator));
+        Ok(())
+    }
+
+    pub fn operator(&self, type_id:TypeID) -> Option<&dyn Operator> {
+        self.operators[type_id as usize].as_deref()
+    }
+
+    /// Value of `packet` with the operators of this dialect.
+    pub fn evaluate(&self, packet:&PacketNode) -> Result<BigUint> {
+        // The outermost missing operator is the one to report
+        let missing = packet.walk().map(|(_, packet)| packet.type_id())
+            .find(|&type_id| type_id != TypeID::LITERAL && self.operator(type_id).is_none());
+        if let Some(type_id) = missing {
+            return Err(SolveError::new(&format!("no operator for type {} in dialect {}", type_id as u8, self.name)));
+        }
+
+        packet.fold(|packet, values| match packet {
+            PacketNode::Literal{value, ..} => Ok(value.clone()),
+            PacketNode::Operator{type_id, ..} => self.operator(*type_id).unwrap().evaluate(&values),
+        })
+    }
+}
+
+impl Default for Dialect {
+    fn default() -> Dialect {
+        Dialect::classic()
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use util::packet_computer::expression::compile;
+    use util::packet_computer::parse;
+
+    #[test]
+    fn test_classic() {
+        let input = include_str!("../../../input_16.txt").trim();
+        let classic = Dialect::classic();
+        assert_eq!("110434737925", classic.evaluate(&parse(input).unwrap()).unwrap().to_string());
+        assert_eq!(Some("gt"), classic.operator(TypeID::GreaterThan).map(|operator| operator.name()));
+        assert!(classic.operator(TypeID::LITERAL).is_none());
+
+        let error = classic.evaluate(&compile("eq(1)").unwrap()).unwrap_err();
+        assert_eq!("EQUAL packet with 1 sub packets", error.message);
+    }
+
+    #[test]
+    fn test_dialects() {
+        let packet = compile("min(1, 2, 6) + eq(5, 3)").unwrap();
+
+        let mut average = Dialect::new("average");
+        average.register(TypeID::MIN, Average).unwrap();
+        let mut xor = Dialect::new("xor");
+        xor.register(TypeID::EQUAL, Xor).unwrap();
+
+        assert_eq!(BigUint::from(1u32), Dialect::classic().evaluate(&packet).unwrap());
+        assert_eq!(BigUint::from(3u32), average.evaluate(&packet).unwrap());
+        assert_eq!(BigUint::from(7u32), xor.evaluate(&packet).unwrap());
+        assert_eq!(Ok(BigUint::from(3u32)), packet.evaluate_with(&average));
+
+        assert!(xor.register(TypeID::LITERAL, Xor).is_err());
+        let error = Dialect::empty("none").evaluate(&packet).unwrap_err();
+        assert_eq!("no operator for type 0 in dialect none", error.message);
+
+        let deep = (0..200_000).fold(PacketNode::literal(0, 6), |packet, _| PacketNode::operator(0, TypeID::MIN, vec![packet]));
+        assert_eq!(BigUint::from(6u32), average.evaluate(&deep).unwrap());
+    }
+}
@@ -0,0 +1,159 @@
+use super::{LengthId, PacketNode, TypeID};
+
+/// One line per packet, indented by depth, with the bit offset, version, type
+/// and either the literal value or the length field of the operator.
+pub fn disassemble(packet:&PacketNode) -> String {
+    let mut result = String::new();
+    for (depth, packet) in packet.walk() {
+        result.push_str(&format!("{:>5}  {}v{} {:?}", packet.offset(), "  ".repeat(depth), packet.version(), packet.type_id()));
+        match packet {
+            PacketNode::Literal{value, ..} => result.push_str(&format!(" {}", value)),
+            PacketNode::Operator{length_id:LengthId::NumberOfBits, declared, ..} => {
+                result.push_str(&format!(" bits={}", declared));
+            }
+            PacketNode::Operator{length_id:LengthId::NumberOfPackets, declared, ..} => {
+                result.push_str(&format!(" packets={}", declared));
+            }
+        }
+        result.push('\n');
+    }
+    result
+}
+
+/// How tightly an operator binds, higher binds tighter.
+fn precedence(type_id:TypeID) -> u8 {
+    match type_id {
+        TypeID::GreaterThan | TypeID::LessThan | TypeID::EQUAL => 1,
+        TypeID::SUM => 2,
+        TypeID::PRODUCT => 3,
+        TypeID::MIN | TypeID::MAX | TypeID::LITERAL => 4,
+    }
+}
+
+/// How an operator is written.
+enum Form {
+    Call(&'static str),
+    Infix(&'static str),
+}
+
+fn form(type_id:TypeID, operands:usize) -> Form {
+    match type_id {
+        TypeID::MIN => Form::Call("min"),
+        TypeID::MAX => Form::Call("max"),
+        TypeID::SUM if operands < 2 => Form::Call("sum"),
+        TypeID::PRODUCT if operands < 2 => Form::Call("product"),
+        TypeID::GreaterThan if operands != 2 => Form::Call("gt"),
+        TypeID::LessThan if operands != 2 => Form::Call("lt"),
+        TypeID::EQUAL if operands != 2 => Form::Call("eq"),
+        TypeID::SUM => Form::Infix(" + "),
+        TypeID::PRODUCT => Form::Infix(" * "),
+        TypeID::GreaterThan => Form::Infix(" > "),
+        TypeID::LessThan => Form::Infix(" < "),
+        TypeID::EQUAL => Form::Infix(" == "),
+        TypeID::LITERAL => Form::Call("literal"),
+    }
+}
+
+/// Precedence of the outermost operator of the rendered packet.
+fn rendered_precedence(packet:&PacketNode) -> u8 {
+    match packet {
+        PacketNode::Operator{type_id, sub_packets, ..} => match form(*type_id, sub_packets.len()) {
+            Form::Infix(_) => precedence(*type_id),
+            Form::Call(_) => 4,
+        },
+        PacketNode::Literal{..} => 4,
+    }
+}
+
+/// Part of the expression still to write.
+enum Piece<'a> {
+    Text(&'static str),
+    Packet(&'a PacketNode),
+}
+
+/// Renders the packet as an expression, e.g. `(min(3, 7) + 12) * (5 > 2)`.
+/// Works from a stack instead of recursing, so any depth of nesting works.
+pub fn infix(packet:&PacketNode) -> String {
+    let mut result = String::new();
+    let mut stack = vec![Piece::Packet(packet)];
+    while let Some(piece) = stack.pop() {
+        let (type_id, sub_packets) = match piece {
+            Piece::Text(text) => {
+                result.push_str(text);
+                continue;
+            }
+            Piece::Packet(PacketNode::Literal{value, ..}) => {
+                result.push_str(&value.to_string());
+                continue;
+            }
+            Piece::Packet(PacketNode::Operator{type_id, sub_packets, ..}) => (*type_id, sub_packets),
+        };
+
+        // Comparisons don't chain, so an operand of the same precedence needs parentheses too
+        let own = precedence(type_id);
+        let (separator, close, parenthesize) = match form(type_id, sub_packets.len()) {
+            Form::Call(name) => {
+                result.push_str(name);
+                result.push('(');
+                (", ", ")", false)
+            }
+            Form::Infix(symbol) => (symbol, "", true),
+        };
+
+        let mut pieces = vec![];
+        for (i, sub) in sub_packets.iter().enumerate() {
+            if i > 0 {
+                pieces.push(Piece::Text(separator));
+            }
+            let inner = rendered_precedence(sub);
+            if parenthesize && (inner < own || inner == own && type_id.is_comparison()) {
+                pieces.extend([Piece::Text("("), Piece::Packet(sub), Piece::Text(")")]);
+            } else {
+                pieces.push(Piece::Packet(sub));
+            }
+        }
+        pieces.push(Piece::Text(close));
+        stack.extend(pieces.into_iter().rev());
+    }
+    result
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use util::packet_computer::parse;
+
+    #[test]
+    fn test_disassemble() {
+        let expected = "    0  v1 LessThan bits=27\n   22    v6 LITERAL 10\n   33    v2 LITERAL 20\n";
+        assert_eq!(expected, disassemble(&parse("38006F45291200").unwrap()));
+
+        let expected = "    0  v7 MAX packets=3\n   18    v2 LITERAL 1\n   29    v4 LITERAL 2\n   40    v1 LITERAL 3\n";
+        assert_eq!(expected, disassemble(&parse("EE00D40C823060").unwrap()));
+
+        // Sum declaring 5 bits of sub packets followed by an 11 bit literal
+        let expected = "    0  v0 SUM bits=5\n   22    v0 LITERAL 1\n";
+        assert_eq!(expected, disassemble(&parse("000014408").unwrap()));
+    }
+
+    #[test]
+    fn test_infix() {
+        assert_eq!("1 + 3 == 2 * 2", infix(&parse("9C0141080250320F1802104A08").unwrap()));
+        assert_eq!("min(7, 8, 9)", infix(&parse("880086C3E88112").unwrap()));
+        assert_eq!("5 < 15", infix(&parse("D8005AC2A8F0").unwrap()));
+
+        let literal = |value| PacketNode::literal(0, value);
+        let packet = PacketNode::operator(0, TypeID::PRODUCT, vec![
+            PacketNode::operator(0, TypeID::SUM, vec![PacketNode::operator(0, TypeID::MIN, vec![literal(3), literal(7)]), literal(12)]),
+            PacketNode::operator(0, TypeID::GreaterThan, vec![literal(5), literal(2)]),
+        ]);
+        assert_eq!("(min(3, 7) + 12) * (5 > 2)", infix(&packet));
+
+        let packet = PacketNode::operator(0, TypeID::EQUAL, vec![
+            PacketNode::operator(0, TypeID::LessThan, vec![literal(1), literal(2)]),
+            PacketNode::operator(0, TypeID::SUM, vec![literal(1)]),
+        ]);
+        assert_eq!("(1 < 2) == sum(1)", infix(&packet));
+    }
+}
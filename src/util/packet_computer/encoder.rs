@@ -0,0 +1,222 @@
+use num_bigint::BigUint;
+use util::error::{Result, SolveError};
+use super::{LengthId, PacketNode, TypeID};
+
+/// How many 4 bit groups a literal value is split into.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LiteralStrategy {
+    /// As few groups as the value needs.
+    Minimal,
+    /// At least this many groups, padded with leading zero groups.
+    MinGroups(usize),
+    /// The group count the literal was decoded with, if the value fits.
+    Keep,
+}
+
+/// Which length type operators are encoded with.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LengthStrategy {
+    /// The length type stored in the packet.
+    Keep,
+    Bits,
+    Packets,
+    /// Packet count unless there are too many sub packets for 11 bits.
+    Compact,
+}
+
+/// Serializes packet trees to BITS transmissions.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Encoder {
+    pub literals:LiteralStrategy,
+    pub lengths:LengthStrategy,
+}
+
+impl Default for Encoder {
+    fn default() -> Encoder {
+        Encoder{literals:LiteralStrategy::Keep, lengths:LengthStrategy::Keep}
+    }
+}
+
+/// Encodes `packet` as hex with the default strategies.
+pub fn encode(packet:&PacketNode) -> Result<String> {
+    Encoder::default().encode(packet)
+}
+
+impl Encoder {
+    pub fn new(literals:LiteralStrategy, lengths:LengthStrategy) -> Encoder {
+        Encoder{literals, lengths}
+    }
+
+    /// Hex transmission of `packet`, padded with zero bits to whole hex digits.
+    pub fn encode(&self, packet:&PacketNode) -> Result<String> {
+        let bits = self.encode_bits(packet)?;
+        Ok(to_hex(&bits))
+    }
+
+    pub fn encode_bits(&self, packet:&PacketNode) -> Result<Vec<bool>> {
+        let mut bits = vec![];
+        self.write_packet(packet, &mut bits)?;
+        Ok(bits)
+    }
+
+    /// Writes from a stack instead of recursing, so any depth of nesting works.
+    fn write_packet(&self, packet:&PacketNode, bits:&mut Vec<bool>) -> Result<()> {
+        let mut stack = vec![Step::Write(packet)];
+        while let Some(step) = stack.pop() {
+            let packet = match step {
+                Step::Write(packet) => packet,
+                Step::Length(field) => {
+                    let sub_bits = bits.len() - field - 15;
+                    if sub_bits > 0x7fff {
+                        return Err(SolveError::new(&format!("{} bits of sub packets don't fit in 15 bits", sub_bits)));
+                    }
+                    for n in 0..15 {
+                        bits[field + n] = sub_bits >> (14 - n) & 1 == 1;
+                    }
+                    continue;
+                }
+            };
+
+            if packet.version() > 7 {
+                return Err(SolveError::new(&format!("version {} doesn't fit in 3 bits", packet.version())));
+            }
+            push_bits(bits, packet.version() as u64, 3);
+            push_bits(bits, packet.type_id() as u64, 3);
+
+            match packet {
+                PacketNode::Literal{value, length, ..} => {
+                    let digits = value.to_radix_be(16);
+                    let groups = self.literal_groups(value, *length);
+                    let padding = groups - digits.len();
+                    for (i, &digit) in ::std::iter::repeat_n(&0, padding).chain(digits.iter()).enumerate() {
+                        bits.push(i + 1 < groups);
+                        push_bits(bits, digit as u64, 4);
+                    }
+                }
+                PacketNode::Operator{type_id, length_id, sub_packets, ..} => {
+                    if *type_id == TypeID::LITERAL {
+                        return Err(SolveError::new("operator packet with the literal type"));
+                    }
+
+                    match self.length_id(*length_id, sub_packets.len()) {
+                        LengthId::NumberOfBits => {
+                            // Filled in once the sub packets are written
+                            bits.push(false);
+                            stack.push(Step::Length(bits.len()));
+                            push_bits(bits, 0, 15);
+                        }
+                        LengthId::NumberOfPackets => {
+                            if sub_packets.len() > 0x7ff {
+                                return Err(SolveError::new(&format!("{} sub packets don't fit in 11 bits", sub_packets.len())));
+                            }
+                            bits.push(true);
+                            push_bits(bits, sub_packets.len() as u64, 11);
+                        }
+                    }
+                    stack.extend(sub_packets.iter().rev().map(Step::Write));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn literal_groups(&self, value:&BigUint, length:usize) -> usize {
+        let minimal = (value.bits() as usize).div_ceil(4).max(1);
+        match self.literals {
+            LiteralStrategy::Minimal => minimal,
+            LiteralStrategy::MinGroups(groups) => groups.max(minimal),
+            // A decoded literal has a 6 bit header and 5 bits per group
+            LiteralStrategy::Keep if length > 6 && (length - 6).is_multiple_of(5) => ((length - 6) / 5).max(minimal),
+            LiteralStrategy::Keep => minimal,
+        }
+    }
+
+    fn length_id(&self, stored:LengthId, sub_packets:usize) -> LengthId {
+        match self.lengths {
+            LengthStrategy::Keep => stored,
+            LengthStrategy::Bits => LengthId::NumberOfBits,
+            LengthStrategy::Packets => LengthId::NumberOfPackets,
+            LengthStrategy::Compact if sub_packets > 0x7ff => LengthId::NumberOfBits,
+            LengthStrategy::Compact => LengthId::NumberOfPackets,
+        }
+    }
+}
+
+/// Work left for `Encoder::write_packet`.
+enum Step<'a> {
+    Write(&'a PacketNode),
+    /// Sets the 15 bit length field at this position to the bits written after it.
+    Length(usize),
+}
+
+fn push_bits(bits:&mut Vec<bool>, value:u64, no_bits:usize) {
+    bits.extend((0..no_bits).rev().map(|n| value >> n & 1 == 1));
+}
+
+/// Hex digits of `bits`, the last digit is padded with zero bits.
+pub fn to_hex(bits:&[bool]) -> String {
+    bits.chunks(4)
+        .map(|chunk| {
+            let digit = (0..4).fold(0, |digit, n| digit << 1 | chunk.get(n).cloned().unwrap_or(false) as u32);
+            std::char::from_digit(digit, 16).unwrap().to_ascii_uppercase()
+        })
+        .collect()
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use util::packet_computer::parse;
+
+    const EXAMPLES:[&str; 15] = ["D2FE28", "38006F45291200", "EE00D40C823060", "8A004A801A8002F478",
+        "620080001611562C8802118E34", "C0015000016115A2E0802F182340", "A0016C880162017C3686B18A3D4780",
+        "C200B40A82", "04005AC33890", "880086C3E88112", "CE00C43D881120", "D8005AC2A8F0", "F600BC2D8F",
+        "9C005AC2F8F0", "9C0141080250320F1802104A08"];
+
+    #[test]
+    fn test_round_trip() {
+        let input = include_str!("../../../input_16.txt").trim();
+        for hex in EXAMPLES.iter().chain([input].iter()) {
+            let packet = parse(hex).unwrap();
+            let encoded = encode(&packet).unwrap();
+
+            assert!(hex.starts_with(encoded.trim_end_matches('0')), "{}", hex);
+            assert_eq!(packet, parse(&encoded).unwrap());
+        }
+    }
+
+    #[test]
+    fn test_strategies() {
+        let packet = PacketNode::operator(1, TypeID::SUM, vec![PacketNode::literal(2, 5), PacketNode::literal(3, 300)]);
+        let strategies = [LiteralStrategy::Minimal, LiteralStrategy::MinGroups(4), LiteralStrategy::Keep];
+        let lengths = [LengthStrategy::Keep, LengthStrategy::Bits, LengthStrategy::Packets, LengthStrategy::Compact];
+
+        for &literals in strategies.iter() {
+            for &length in lengths.iter() {
+                let decoded = parse(&Encoder::new(literals, length).encode(&packet).unwrap()).unwrap();
+                assert_eq!(Ok(305), decoded.evaluate());
+                assert_eq!(packet.version_sum(), decoded.version_sum());
+            }
+        }
+
+        let encoder = Encoder::new(LiteralStrategy::MinGroups(4), LengthStrategy::Bits);
+        let decoded = parse(&encoder.encode(&packet).unwrap()).unwrap();
+        assert_eq!(Some(LengthId::NumberOfBits), decoded.length_id());
+        assert_eq!(26, decoded.sub_packets()[0].bit_length());
+
+        assert_eq!("D2FE28", encode(&PacketNode::literal(6, 2021)).unwrap());
+        assert_eq!("D0A", Encoder::new(LiteralStrategy::Minimal, LengthStrategy::Keep).encode(&PacketNode::literal(6, 5)).unwrap());
+    }
+
+    #[test]
+    fn test_invalid() {
+        assert!(encode(&PacketNode::literal(8, 1)).is_err());
+        assert!(encode(&PacketNode::operator(0, TypeID::LITERAL, vec![])).is_err());
+
+        let many = PacketNode::operator(0, TypeID::SUM, vec![PacketNode::literal(0, 1); 0x800]);
+        assert!(Encoder::new(LiteralStrategy::Minimal, LengthStrategy::Packets).encode(&many).is_err());
+        let decoded = parse(&Encoder::new(LiteralStrategy::Minimal, LengthStrategy::Compact).encode(&many).unwrap()).unwrap();
+        assert_eq!((Some(LengthId::NumberOfBits), Ok(0x800)), (decoded.length_id(), decoded.evaluate()));
+    }
+}
@@ -0,0 +1,258 @@
+use std::fmt;
+use num_bigint::BigUint;
+use util::error::{Result, SolveError};
+use super::encoder;
+use super::{PacketNode, TypeID, MAX_DEPTH};
+
+/// Compiles an expression such as `sum(1, 2, max(3, 4)) > 5` to a packet tree.
+///
+/// Numbers become literals, `+` and `*` become sum and product packets and
+/// `>`, `<` and `==` comparisons. The functions `sum`, `product`, `min`, `max`,
+/// `gt`, `lt` and `eq` take any number of arguments. All packets get version 0.
+/// Parentheses and calls nest at most `MAX_DEPTH` deep.
+pub fn compile(source:&str) -> Result<PacketNode> {
+    let mut parser = Parser{tokens:tokenize(source)?, pos:0, end:source.chars().count() + 1, depth:0};
+    let packet = parser.comparison()?;
+    match parser.peek() {
+        None => Ok(packet),
+        Some((column, token)) => Err(SolveError::at(1, column, &format!("unexpected '{}'", token))),
+    }
+}
+
+/// Compiles `source` and encodes it as a hex transmission.
+pub fn compile_hex(source:&str) -> Result<String> {
+    encoder::encode(&compile(source)?)
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Number(BigUint),
+    Name(String),
+    Symbol(&'static str),
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f:&mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Token::Number(value) => write!(f, "{}", value),
+            Token::Name(name) => write!(f, "{}", name),
+            Token::Symbol(symbol) => write!(f, "{}", symbol),
+        }
+    }
+}
+
+const SYMBOLS:[&str; 8] = ["==", "+", "*", "<", ">", "(", ")", ","];
+
+/// Tokens with their 1 based column.
+fn tokenize(source:&str) -> Result<Vec<(usize, Token)>> {
+    let chars:Vec<char> = source.chars().collect();
+    let mut tokens = vec![];
+    let mut pos = 0;
+    while pos < chars.len() {
+        let start = pos;
+        let ch = chars[pos];
+        if ch.is_whitespace() {
+            pos += 1;
+            continue;
+        }
+
+        if ch.is_ascii_digit() || ch.is_ascii_alphabetic() {
+            while pos < chars.len() && (chars[pos].is_ascii_alphanumeric() || chars[pos] == '_') {
+                pos += 1;
+            }
+            let word:String = chars[start..pos].iter().collect();
+            let token = if ch.is_ascii_digit() {
+                Token::Number(word.parse().map_err(|_| SolveError::at(1, start + 1, &format!("invalid number '{}'", word)))?)
+            } else {
+                Token::Name(word)
+            };
+            tokens.push((start + 1, token));
+            continue;
+        }
+
+        let rest:String = chars[pos..].iter().take(2).collect();
+        match SYMBOLS.iter().find(|symbol| rest.starts_with(*symbol)) {
+            Some(symbol) => {
+                tokens.push((start + 1, Token::Symbol(symbol)));
+                pos += symbol.len();
+            }
+            None => return Err(SolveError::at(1, start + 1, &format!("unexpected character '{}'", ch))),
+        }
+    }
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens:Vec<(usize, Token)>,
+    pos:usize,
+    /// Column reported for errors at the end of the source.
+    end:usize,
+    /// Parentheses and calls the parser is in.
+    depth:usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<(usize, Token)> {
+        self.tokens.get(self.pos).cloned()
+    }
+
+    fn next(&mut self) -> Result<(usize, Token)> {
+        let token = self.peek().ok_or_else(|| SolveError::at(1, self.end, "unexpected end of expression"))?;
+        self.pos += 1;
+        Ok(token)
+    }
+
+    fn accept(&mut self, symbol:&str) -> bool {
+        match self.peek() {
+            Some((_, Token::Symbol(found))) if found == symbol => {
+                self.pos += 1;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    fn expect(&mut self, symbol:&'static str) -> Result<()> {
+        let (column, token) = self.next()?;
+        if token != Token::Symbol(symbol) {
+            return Err(SolveError::at(1, column, &format!("expected '{}', found '{}'", symbol, token)));
+        }
+        Ok(())
+    }
+
+    fn comparison(&mut self) -> Result<PacketNode> {
+        let left = self.operands("+", TypeID::SUM)?;
+        let type_id = match self.peek() {
+            Some((_, Token::Symbol(">"))) => TypeID::GreaterThan,
+            Some((_, Token::Symbol("<"))) => TypeID::LessThan,
+            Some((_, Token::Symbol("=="))) => TypeID::EQUAL,
+            _ => return Ok(left),
+        };
+        self.pos += 1;
+
+        let right = self.operands("+", TypeID::SUM)?;
+        if let Some((column, Token::Symbol(symbol))) = self.peek() {
+            if [">", "<", "=="].contains(&symbol) {
+                return Err(SolveError::at(1, column, "comparisons don't chain, add parentheses"));
+            }
+        }
+        Ok(PacketNode::operator(0, type_id, vec![left, right]))
+    }
+
+    /// Operands joined by `symbol`, more than one become a `type_id` packet.
+    fn operands(&mut self, symbol:&str, type_id:TypeID) -> Result<PacketNode> {
+        let mut operands = vec![self.operand(type_id)?];
+        while self.accept(symbol) {
+            operands.push(self.operand(type_id)?);
+        }
+
+        if operands.len() == 1 {
+            Ok(operands.pop().unwrap())
+        } else {
+            Ok(PacketNode::operator(0, type_id, operands))
+        }
+    }
+
+    fn operand(&mut self, type_id:TypeID) -> Result<PacketNode> {
+        match type_id {
+            TypeID::SUM => self.operands("*", TypeID::PRODUCT),
+            _ => self.atom(),
+        }
+    }
+
+    /// Runs `f` for the inside of the parentheses or call at `column`.
+    fn nested<F:FnOnce(&mut Parser) -> Result<PacketNode>>(&mut self, column:usize, f:F) -> Result<PacketNode> {
+        if self.depth == MAX_DEPTH {
+            return Err(SolveError::at(1, column, &format!("expression nested deeper than {}", MAX_DEPTH)));
+        }
+        self.depth += 1;
+        let packet = f(self);
+        self.depth -= 1;
+        packet
+    }
+
+    fn atom(&mut self) -> Result<PacketNode> {
+        let (column, token) = self.next()?;
+        match token {
+            Token::Number(value) => Ok(PacketNode::big_literal(0, value)),
+            Token::Symbol("(") => self.nested(column, |parser| {
+                let packet = parser.comparison()?;
+                parser.expect(")")?;
+                Ok(packet)
+            }),
+            Token::Name(name) => {
+                let type_id = match name.as_str() {
+                    "sum" => TypeID::SUM,
+                    "product" => TypeID::PRODUCT,
+                    "min" => TypeID::MIN,
+                    "max" => TypeID::MAX,
+                    "gt" => TypeID::GreaterThan,
+                    "lt" => TypeID::LessThan,
+                    "eq" => TypeID::EQUAL,
+                    _ => return Err(SolveError::at(1, column, &format!("unknown function '{}'", name))),
+                };
+
+                self.nested(column, |parser| {
+                    parser.expect("(")?;
+                    let mut args = vec![];
+                    if !parser.accept(")") {
+                        args.push(parser.comparison()?);
+                        while parser.accept(",") {
+                            args.push(parser.comparison()?);
+                        }
+                        parser.expect(")")?;
+                    }
+                    Ok(PacketNode::operator(0, type_id, args))
+                })
+            }
+            token => Err(SolveError::at(1, column, &format!("unexpected '{}'", token))),
+        }
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use util::packet_computer::disassembler::infix;
+    use util::packet_computer::parse;
+
+    #[test]
+    fn test_compile() {
+        assert_eq!(Ok(1), compile("sum(1, 2, max(3, 4)) > 5").unwrap().evaluate());
+        assert_eq!(Ok(19), compile("(min(3, 7) + 12) * (5 > 2) + 2 * 2").unwrap().evaluate());
+        assert_eq!(Ok(1), compile("1 + 3 == 2 * 2").unwrap().evaluate());
+        assert_eq!("(min(3, 7) + 12) * (5 > 2)", infix(&compile("(min(3,7)+12)*(5>2)").unwrap()));
+
+        let hex = compile_hex("1 + 3 == 2 * 2").unwrap();
+        assert_eq!(Ok(1), parse(&hex).unwrap().evaluate());
+        assert_eq!("2021", infix(&parse(&compile_hex("2021").unwrap()).unwrap()));
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let input = include_str!("../../../input_16.txt").trim();
+        let packet = compile(&infix(&parse(input).unwrap())).unwrap();
+        assert_eq!(Ok(110434737925), parse(&encoder::encode(&packet).unwrap()).unwrap().evaluate());
+    }
+
+    #[test]
+    fn test_errors() {
+        let error = |source| {
+            let error = compile(source).unwrap_err();
+            (error.column.unwrap(), error.message)
+        };
+
+        assert_eq!((7, "comparisons don't chain, add parentheses".to_string()), error("1 < 2 < 3"));
+        assert_eq!((1, "unknown function 'avg'".to_string()), error("avg(1, 2)"));
+        assert_eq!((3, "unexpected character '-'".to_string()), error("1 - 2"));
+        assert_eq!((6, "unexpected end of expression".to_string()), error("1 + ("));
+        assert_eq!((3, "unexpected '2'".to_string()), error("1 2"));
+        assert_eq!((1, "invalid number '12ab'".to_string()), error("12ab + 1"));
+
+        let deep = format!("{}1{}", "(".repeat(200_000), ")".repeat(200_000));
+        assert_eq!((MAX_DEPTH + 1, format!("expression nested deeper than {}", MAX_DEPTH)), error(&deep));
+        let deep = format!("{}1{}", "sum(".repeat(MAX_DEPTH), ")".repeat(MAX_DEPTH));
+        assert_eq!(Ok(1), compile(&deep).unwrap().evaluate());
+    }
+}
@@ -0,0 +1,257 @@
+use std::panic::{self, AssertUnwindSafe};
+use num_bigint::BigUint;
+use util::error::{Result, SolveError};
+use util::random::Rng;
+use super::bits::{Format, StreamCursor};
+use super::encoder::{encode, to_hex, Encoder};
+use super::{decode, decode_from, LengthId, Mode, PacketComputer, PacketNode, TypeID};
+
+/// Shape of the trees a `Generator` builds.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Limits {
+    /// Operators below this depth only get literals as sub packets.
+    pub max_depth:usize,
+    /// Most sub packets of an operator, comparisons always get two.
+    pub max_fan_out:usize,
+    /// Most hex digits of a literal value.
+    pub max_literal_digits:usize,
+}
+
+impl Default for Limits {
+    fn default() -> Limits {
+        Limits{max_depth:4, max_fan_out:4, max_literal_digits:20}
+    }
+}
+
+/// Builds random valid packet trees, the same seed gives the same trees.
+#[derive(Debug, Clone)]
+pub struct Generator {
+    pub limits:Limits,
+    rng:Rng,
+}
+
+impl Generator {
+    pub fn new(seed:u64, limits:Limits) -> Generator {
+        Generator{limits, rng:Rng::new(seed)}
+    }
+
+    fn below(&mut self, n:usize) -> usize {
+        self.rng.below(n)
+    }
+
+    pub fn packet(&mut self) -> PacketNode {
+        self.node(0)
+    }
+
+    fn node(&mut self, depth:usize) -> PacketNode {
+        let version = self.below(8) as u8;
+        if depth >= self.limits.max_depth || self.below(3) == 0 {
+            let digits:Vec<u8> = (0..1 + self.below(self.limits.max_literal_digits)).map(|_| self.below(16) as u8).collect();
+            return PacketNode::big_literal(version, BigUint::from_radix_be(&digits, 16).unwrap());
+        }
+
+        let type_id = TypeID::from_bits([0, 1, 2, 3, 5, 6, 7][self.below(7)]).unwrap();
+        let no_packets = if type_id.is_comparison() { 2 } else { 1 + self.below(self.limits.max_fan_out) };
+        let sub_packets:Vec<PacketNode> = (0..no_packets).map(|_| self.node(depth + 1)).collect();
+
+        let sub_bits:usize = sub_packets.iter().map(encoded_bits).sum();
+        let (length_id, declared) = if self.below(2) == 0 && sub_bits <= 0x7fff {
+            (LengthId::NumberOfBits, sub_bits)
+        } else {
+            (LengthId::NumberOfPackets, sub_packets.len())
+        };
+        PacketNode::Operator{version, type_id, length_id, declared, offset:0, length:0, sub_packets}
+    }
+
+    /// Truncations that cut into the packet and `flips` random bit flips of
+    /// the transmission `packet` encodes to.
+    pub fn mutations(&mut self, packet:&PacketNode, flips:usize) -> Result<Vec<Mutation>> {
+        let bits = encoded_bits(packet);
+        let hex_bits = encode(packet)?.len() * 4;
+        let mut mutations:Vec<Mutation> = (0..bits.div_ceil(4)).map(Mutation::Truncate).collect();
+        mutations.extend((0..flips).map(|_| Mutation::FlipBit(self.below(hex_bits))));
+        Ok(mutations)
+    }
+}
+
+/// Bits the default encoder writes for a packet that wasn't decoded.
+fn encoded_bits(packet:&PacketNode) -> usize {
+    match packet {
+        PacketNode::Literal{value, ..} => 6 + 5 * (value.bits() as usize).div_ceil(4).max(1),
+        PacketNode::Operator{length_id, sub_packets, ..} => {
+            let length = if *length_id == LengthId::NumberOfBits { 15 } else { 11 };
+            7 + length + sub_packets.iter().map(encoded_bits).sum::<usize>()
+        }
+    }
+}
+
+/// Damage done to a hex transmission.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Mutation {
+    /// Keeps only this many hex digits.
+    Truncate(usize),
+    /// Inverts the bit at this position.
+    FlipBit(usize),
+}
+
+impl Mutation {
+    pub fn apply(&self, hex:&str) -> String {
+        match *self {
+            Mutation::Truncate(digits) => hex.chars().take(digits).collect(),
+            Mutation::FlipBit(bit) => {
+                let mut chars:Vec<char> = hex.chars().collect();
+                let digit = chars[bit / 4].to_digit(16).unwrap() ^ 8 >> (bit % 4);
+                chars[bit / 4] = std::char::from_digit(digit, 16).unwrap().to_ascii_uppercase();
+                chars.into_iter().collect()
+            }
+        }
+    }
+}
+
+/// Straightforward evaluation to check the decoder against, `None` when an
+/// operator has the wrong number of sub packets.
+pub fn reference_value(packet:&PacketNode) -> Option<BigUint> {
+    let (type_id, sub_packets) = match packet {
+        PacketNode::Literal{value, ..} => return Some(value.clone()),
+        PacketNode::Operator{type_id, sub_packets, ..} => (*type_id, sub_packets),
+    };
+
+    let values = sub_packets.iter().map(reference_value).collect::<Option<Vec<BigUint>>>()?;
+    if values.is_empty() || type_id.is_comparison() && values.len() != 2 {
+        return None;
+    }
+
+    match type_id {
+        TypeID::SUM => Some(values.iter().fold(BigUint::from(0u8), |sum, value| sum + value)),
+        TypeID::PRODUCT => Some(values.iter().fold(BigUint::from(1u8), |product, value| product * value)),
+        TypeID::MIN => values.iter().min().cloned(),
+        TypeID::MAX => values.iter().max().cloned(),
+        TypeID::GreaterThan => Some(BigUint::from((values[0] > values[1]) as u8)),
+        TypeID::LessThan => Some(BigUint::from((values[0] < values[1]) as u8)),
+        TypeID::EQUAL => Some(BigUint::from((values[0] == values[1]) as u8)),
+        TypeID::LITERAL => None,
+    }
+}
+
+/// Runs `f`, turning a panic into an error about `hex`.
+fn guard<T, F:FnOnce() -> T>(hex:&str, f:F) -> Result<T> {
+    panic::catch_unwind(AssertUnwindSafe(f))
+        .map_err(|_| SolveError::new(&format!("decoder panicked on {}", hex)))
+}
+
+/// Encodes `packet` and checks that decoding it, from a string and from a
+/// stream, gives back the same tree and the value of `reference_value`.
+pub fn check(packet:&PacketNode) -> Result<()> {
+    let hex = encode(packet)?;
+    let fail = |what:&str| Err(SolveError::new(&format!("{} differs for {}", what, hex)));
+
+    let decoded = guard(&hex, || decode(&hex, Mode::Strict))??.0;
+    let streamed = guard(&hex, || decode_from(StreamCursor::new(hex.as_bytes(), Format::Hex), Mode::Strict))??.0;
+    if encode(&decoded)? != hex || decoded != streamed {
+        return fail("decoded tree");
+    }
+    if decoded.version_sum() != packet.version_sum() {
+        return fail("version sum");
+    }
+
+    let expected = reference_value(packet).ok_or_else(|| SolveError::new("generated an invalid packet"))?;
+    if decoded.evaluate_big()? != expected {
+        return fail("value");
+    }
+
+    // Only fails when a value doesn't fit in 64 bits
+    let run = guard(&hex, || PacketComputer::with_mode(&hex, Mode::Strict)?.run())?;
+    if run.is_ok_and(|value| BigUint::from(value) != expected) {
+        return fail("64 bit value");
+    }
+    Ok(())
+}
+
+/// Checks that the decoder handles `mutation` of the transmission of
+/// `packet` without panicking. Truncated transmissions and flipped padding
+/// bits must be rejected, any other mutation that strict decoding accepts
+/// must encode back to the mutated transmission.
+pub fn check_mutation(packet:&PacketNode, mutation:Mutation) -> Result<()> {
+    let original = encode(packet)?;
+    let hex = mutation.apply(&original);
+
+    let lenient = guard(&hex, || decode(&hex, Mode::Lenient))?;
+    if let Ok((decoded, _)) = &lenient {
+        guard(&hex, || decoded.evaluate_big())?.ok();
+    }
+
+    let must_fail = match mutation {
+        Mutation::Truncate(_) => true,
+        Mutation::FlipBit(bit) => bit >= encoded_bits(packet),
+    };
+    let accepted = match guard(&hex, || decode(&hex, Mode::Strict))? {
+        Ok((decoded, _)) => decoded,
+        Err(_) => return Ok(()),
+    };
+
+    let encoded = to_hex(&Encoder::default().encode_bits(&accepted)?);
+    if must_fail || format!("{:0<width$}", encoded, width = hex.len()) != hex {
+        return Err(SolveError::new(&format!("{:?} of {} accepted as {}", mutation, original, encoded)));
+    }
+    Ok(())
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_generator() {
+        let limits = Limits{max_depth:2, max_fan_out:3, max_literal_digits:2};
+        let packets:Vec<PacketNode> = (0..50).map(|_| Generator::new(7, limits).packet()).collect();
+        assert!(packets.windows(2).all(|pair| pair[0] == pair[1]));
+
+        let mut generator = Generator::new(7, limits);
+        for _ in 0..200 {
+            let packet = generator.packet();
+            for (depth, node) in packet.walk() {
+                assert!(depth <= limits.max_depth);
+                assert!(node.sub_packets().len() <= limits.max_fan_out);
+                if let PacketNode::Literal{value, ..} = node {
+                    assert!(value.bits() <= 8);
+                }
+            }
+            assert_eq!(encoded_bits(&packet), Encoder::default().encode_bits(&packet).unwrap().len());
+        }
+    }
+
+    #[test]
+    fn test_reference() {
+        let literal = |value| PacketNode::literal(0, value);
+        assert_eq!(Some(BigUint::from(6u8)), reference_value(&PacketNode::operator(0, TypeID::PRODUCT, vec![literal(2), literal(3)])));
+        assert_eq!(None, reference_value(&PacketNode::operator(0, TypeID::MIN, vec![])));
+        assert_eq!(None, reference_value(&PacketNode::operator(0, TypeID::EQUAL, vec![literal(1)])));
+    }
+
+    #[test]
+    fn test_differential() {
+        for seed in 0..20 {
+            let mut generator = Generator::new(seed, Limits::default());
+            for _ in 0..25 {
+                let packet = generator.packet();
+                check(&packet).unwrap();
+            }
+        }
+    }
+
+    #[test]
+    fn test_mutations() {
+        let mut generator = Generator::new(2021, Limits{max_depth:3, ..Limits::default()});
+        for _ in 0..100 {
+            let packet = generator.packet();
+            for mutation in generator.mutations(&packet, 16).unwrap() {
+                check_mutation(&packet, mutation).unwrap();
+            }
+        }
+
+        assert_eq!("D2FE2", Mutation::Truncate(5).apply("D2FE28"));
+        assert_eq!("D2FE29", Mutation::FlipBit(23).apply("D2FE28"));
+        assert!(check_mutation(&PacketNode::literal(6, 2021), Mutation::FlipBit(22)).is_ok());
+    }
+}
@@ -0,0 +1,219 @@
+use std::io::BufRead;
+use std::mem;
+use num_bigint::BigUint;
+use util::error::{Result, SolveError};
+use super::bits::BitRead;
+use super::{decode, DecodeError, DecodeResult, Mode, PacketComputer, PacketNode};
+
+/// One top-level packet of a stream of transmissions.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Transmission {
+    /// Position in the stream, counting from 0.
+    pub index:usize,
+    /// Line the transmission was on when reading by line.
+    pub line:Option<usize>,
+    pub packet:PacketNode,
+    /// Problems found in lenient mode.
+    pub warnings:Vec<DecodeError>,
+}
+
+impl Transmission {
+    pub fn value(&self) -> Result<BigUint> {
+        self.packet.evaluate_big()
+    }
+
+    pub fn version_sum(&self) -> u64 {
+        self.packet.version_sum()
+    }
+}
+
+/// Iterator over the top-level packets of a bit stream where every packet is
+/// padded with zero bits to a multiple of `alignment` bits. Zero bits up to
+/// the end of the stream, or of the line in a hex stream, are padding as well
+/// and alignment starts over on the next line. Stops after the first error,
+/// the stream can't be trusted after that.
+pub struct Transmissions<R> {
+    computer:PacketComputer<R>,
+    alignment:usize,
+    /// Position alignment counts from.
+    start:usize,
+    index:usize,
+    done:bool,
+}
+
+/// Packets in `reader`, padded to whole bytes like the puzzle transmissions.
+pub fn transmissions<R:BitRead>(reader:R, mode:Mode) -> Transmissions<R> {
+    transmissions_aligned(reader, mode, 8)
+}
+
+pub fn transmissions_aligned<R:BitRead>(reader:R, mode:Mode, alignment:usize) -> Transmissions<R> {
+    Transmissions{computer:PacketComputer::from_reader(reader, mode), alignment:alignment.max(1), start:0, index:0, done:false}
+}
+
+impl<R:BitRead> Transmissions<R> {
+    /// Skips zeros to the end of the line or stream, the next line is aligned from its start.
+    fn skip_padding(&mut self) -> DecodeResult<bool> {
+        let skipped = self.computer.reader.skip_padding()?;
+        if skipped {
+            self.start = self.computer.reader.position();
+        }
+        Ok(skipped)
+    }
+
+    fn next_packet(&mut self) -> DecodeResult<Option<Transmission>> {
+        self.skip_padding()?;
+        if self.computer.reader.at_end()? {
+            return Ok(None);
+        }
+
+        let packet = self.computer.process_packet()?;
+
+        // Padding may be cut short at the end of the stream or line
+        let end = self.computer.reader.position() - self.start;
+        let mut padding = (self.alignment - end % self.alignment) % self.alignment;
+        while padding > 0 && !self.skip_padding()? {
+            if self.computer.read_bits(1)? == 1 {
+                let bit = self.computer.reader.position() - 1;
+                self.computer.report(DecodeError::TrailingBits{bit})?;
+            }
+            padding -= 1;
+        }
+
+        let warnings = mem::take(&mut self.computer.warnings);
+        self.index += 1;
+        Ok(Some(Transmission{index:self.index - 1, line:None, packet, warnings}))
+    }
+}
+
+impl<R:BitRead> Iterator for Transmissions<R> {
+    type Item = Result<Transmission>;
+
+    fn next(&mut self) -> Option<Result<Transmission>> {
+        if self.done {
+            return None;
+        }
+
+        match self.next_packet() {
+            Ok(Some(transmission)) => Some(Ok(transmission)),
+            Ok(None) => {
+                self.done = true;
+                None
+            }
+            Err(e) => {
+                self.done = true;
+                Some(Err(e.into()))
+            }
+        }
+    }
+}
+
+/// Iterator over hex transmissions given one per line, blank lines are
+/// skipped. A bad line doesn't stop the iteration.
+pub struct TransmissionLines<B> {
+    input:B,
+    mode:Mode,
+    index:usize,
+    line:usize,
+}
+
+pub fn transmission_lines<B:BufRead>(input:B, mode:Mode) -> TransmissionLines<B> {
+    TransmissionLines{input, mode, index:0, line:0}
+}
+
+impl<B:BufRead> Iterator for TransmissionLines<B> {
+    type Item = Result<Transmission>;
+
+    fn next(&mut self) -> Option<Result<Transmission>> {
+        let mut text = String::new();
+        loop {
+            text.clear();
+            self.line += 1;
+            match self.input.read_line(&mut text) {
+                Ok(0) => return None,
+                Ok(_) if text.trim().is_empty() => continue,
+                Ok(_) => break,
+                Err(e) => return Some(Err(SolveError::on_line(self.line, &format!("can't read transmission: {}", e)))),
+            }
+        }
+
+        self.index += 1;
+        let index = self.index - 1;
+        let line = self.line;
+        Some(match decode(text.trim(), self.mode) {
+            Ok((packet, warnings)) => Ok(Transmission{index, line:Some(line), packet, warnings}),
+            Err(e) => Err(SolveError::at(line, e.column(), &e.to_string())),
+        })
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use util::packet_computer::bits::{BitCursor, Format, StreamCursor};
+
+    const LOG:&str = "D2FE28\n38006F45291200\n\nEE00D40C823060\n9C0141080250320F1802104A08\n";
+
+    #[test]
+    fn test_stream() {
+        let results:Vec<(usize, u64, String)> = transmissions(StreamCursor::new(LOG.as_bytes(), Format::Hex), Mode::Strict)
+            .map(|transmission| transmission.unwrap())
+            .map(|transmission| (transmission.index, transmission.version_sum(), transmission.value().unwrap().to_string()))
+            .collect();
+        assert_eq!(vec![(0, 6, "2021".to_string()), (1, 9, "1".to_string()), (2, 14, "3".to_string()), (3, 20, "1".to_string())], results);
+
+        let offsets:Vec<usize> = transmissions(BitCursor::from_hex("D2FE28EE00D40C823060").unwrap(), Mode::Strict)
+            .map(|transmission| transmission.unwrap().packet.offset())
+            .collect();
+        assert_eq!(vec![0, 24], offsets);
+    }
+
+    #[test]
+    fn test_padding() {
+        let values = |hex:&str| -> Vec<String> {
+            transmissions(StreamCursor::new(hex.as_bytes(), Format::Hex), Mode::Strict)
+                .map(|transmission| transmission.unwrap().value().unwrap().to_string())
+                .collect()
+        };
+        assert_eq!(vec!["2021"], values("D2FE2800\n"));
+        assert_eq!(vec!["2021", "2021"], values("D2FE2800\n\nD2FE28"));
+        // Alignment starts over at the odd line
+        assert_eq!(vec!["5", "2021"], values("D0A\nD2FE28\n"));
+
+        let offsets:Vec<usize> = transmissions(BitCursor::from_hex("D2FE2800000000").unwrap(), Mode::Strict)
+            .map(|transmission| transmission.unwrap().packet.offset())
+            .collect();
+        assert_eq!(vec![0], offsets);
+    }
+
+    #[test]
+    fn test_stream_errors() {
+        let mut packets = transmissions(BitCursor::from_hex("D2FE29D2FE28").unwrap(), Mode::Lenient);
+        assert_eq!(vec![DecodeError::TrailingBits{bit:23}], packets.next().unwrap().unwrap().warnings);
+        assert_eq!(24, packets.next().unwrap().unwrap().packet.offset());
+        assert!(packets.next().is_none());
+
+        let results:Vec<bool> = transmissions(BitCursor::from_hex("D2FE28D2FE").unwrap(), Mode::Strict)
+            .map(|transmission| transmission.is_ok())
+            .collect();
+        assert_eq!(vec![true, false], results);
+
+        let results:Vec<bool> = transmissions(BitCursor::from_hex("D2FE29D2FE28").unwrap(), Mode::Strict)
+            .map(|transmission| transmission.is_ok())
+            .collect();
+        assert_eq!(vec![false], results);
+    }
+
+    #[test]
+    fn test_lines() {
+        let results:Vec<Result<Transmission>> = transmission_lines("D2FE28\nD2XE28\n\nC200B40A82\n".as_bytes(), Mode::Strict).collect();
+        assert_eq!(3, results.len());
+        assert_eq!(Some(1), results[0].as_ref().unwrap().line);
+
+        let error = results[1].as_ref().unwrap_err();
+        assert_eq!((Some(2), Some(3)), (error.line, error.column));
+
+        let last = results[2].as_ref().unwrap();
+        assert_eq!((2, Some(4), Ok(BigUint::from(3u32))), (last.index, last.line, last.value()));
+    }
+}
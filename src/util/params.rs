@@ -0,0 +1,120 @@
+use std::str::FromStr;
+use super::error::{Result, SolveError};
+
+/// Description of one parameter with its current value.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Param {
+    pub name:&'static str,
+    pub value:String,
+    pub description:&'static str,
+}
+
+/// Typed parameters of a solver, `Default` gives the values from the puzzle.
+pub trait Params: Default + 'static {
+    fn list(&self) -> Vec<Param>;
+
+    fn set(&mut self, name:&str, value:&str) -> Result<()>;
+}
+
+/// For solvers without parameters.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NoParams;
+
+impl Params for NoParams {
+    fn list(&self) -> Vec<Param> {
+        vec![]
+    }
+
+    fn set(&mut self, name:&str, _value:&str) -> Result<()> {
+        Err(unknown(name, self))
+    }
+}
+
+/// Error for a parameter name `params` doesn't have.
+pub fn unknown<P:Params>(name:&str, params:&P) -> SolveError {
+    let names:Vec<&str> = params.list().iter().map(|param| param.name).collect();
+    if names.is_empty() {
+        SolveError::new(&format!("unknown parameter '{}', there are no parameters", name))
+    } else {
+        SolveError::new(&format!("unknown parameter '{}', expected one of: {}", name, names.join(", ")))
+    }
+}
+
+pub fn parse_value<T:FromStr>(name:&str, value:&str) -> Result<T> {
+    value.parse().map_err(|_| SolveError::new(&format!("invalid value '{}' for parameter {}", value, name)))
+}
+
+/// Value shown for a parameter whose default differs between the parts.
+pub fn per_part<T:ToString>(value:&Option<T>, part1:T, part2:T) -> String {
+    match value {
+        Some(value) => value.to_string(),
+        None => format!("{} / {}", part1.to_string(), part2.to_string()),
+    }
+}
+
+/// Splits a `name=value` assignment.
+pub fn parse_assignment(arg:&str) -> Result<(String, String)> {
+    match arg.find('=') {
+        Some(pos) if pos > 0 => Ok((arg[..pos].to_string(), arg[pos + 1..].to_string())),
+        _ => Err(SolveError::new(&format!("expected name=value, found '{}'", arg))),
+    }
+}
+
+/// Defaults of `P` with `assignments` applied in order.
+pub fn with_assignments<P:Params>(assignments:&[(String, String)]) -> Result<P> {
+    let mut params = P::default();
+    for (name, value) in assignments {
+        params.set(name, value)?;
+    }
+    Ok(params)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Default)]
+    struct Steps {
+        steps:Option<u32>,
+    }
+
+    impl Params for Steps {
+        fn list(&self) -> Vec<Param> {
+            vec![Param{name:"steps", value:per_part(&self.steps, 10, 40), description:"number of steps"}]
+        }
+
+        fn set(&mut self, name:&str, value:&str) -> Result<()> {
+            match name {
+                "steps" => self.steps = Some(parse_value(name, value)?),
+                _ => return Err(unknown(name, self)),
+            }
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_assignments() {
+        assert_eq!(Ok(("steps".to_string(), "500".to_string())), parse_assignment("steps=500"));
+        assert_eq!(Ok(("a".to_string(), "".to_string())), parse_assignment("a="));
+        assert!(parse_assignment("=5").is_err());
+        assert!(parse_assignment("steps").is_err());
+
+        let assignments = vec![("steps".to_string(), "500".to_string())];
+        let steps:Steps = with_assignments(&assignments).unwrap();
+        assert_eq!(Some(500), steps.steps);
+        assert_eq!("500", steps.list()[0].value);
+        assert_eq!("10 / 40", Steps::default().list()[0].value);
+    }
+
+    #[test]
+    fn test_errors() {
+        let error = with_assignments::<Steps>(&[("rounds".to_string(), "5".to_string())]).err().unwrap();
+        assert_eq!("unknown parameter 'rounds', expected one of: steps", error.message);
+
+        let error = with_assignments::<Steps>(&[("steps".to_string(), "x".to_string())]).err().unwrap();
+        assert_eq!("invalid value 'x' for parameter steps", error.message);
+
+        assert!(NoParams.set("steps", "1").is_err());
+    }
+}
@@ -0,0 +1,54 @@
+/// Xorshift generator for test data and generated inputs, the same seed gives
+/// the same numbers. Not for anything that needs good randomness.
+#[derive(Debug, Clone)]
+pub struct Rng {
+    state:u64,
+}
+
+impl Rng {
+    pub fn new(seed:u64) -> Rng {
+        // xorshift gets stuck on 0
+        const MIX:u64 = 0x9e37_79b9_7f4a_7c15;
+        Rng{state:if seed == MIX { MIX } else { seed ^ MIX }}
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state ^= self.state << 13;
+        self.state ^= self.state >> 7;
+        self.state ^= self.state << 17;
+        self.state
+    }
+
+    /// Random number in `0..n`.
+    pub fn below(&mut self, n:usize) -> usize {
+        (self.next_u64() % n.max(1) as u64) as usize
+    }
+
+    /// Random number in `lo..=hi`.
+    pub fn range(&mut self, lo:i64, hi:i64) -> i64 {
+        lo + (self.next_u64() % (hi - lo + 1) as u64) as i64
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rng() {
+        let numbers = |seed| -> Vec<u64> {
+            let mut rng = Rng::new(seed);
+            (0..10).map(|_| rng.next_u64()).collect()
+        };
+        assert_eq!(numbers(1), numbers(1));
+        assert_ne!(numbers(1), numbers(2));
+        assert!(numbers(0x9e37_79b9_7f4a_7c15).iter().any(|&n| n != 0));
+
+        let mut rng = Rng::new(7);
+        assert!((0..1000).map(|_| rng.below(3)).all(|n| n < 3));
+        let values:Vec<i64> = (0..1000).map(|_| rng.range(-2, 2)).collect();
+        assert!(values.iter().all(|n| (-2..=2).contains(n)));
+        assert!(values.contains(&-2) && values.contains(&2));
+    }
+}
@@ -0,0 +1,134 @@
+use super::solver::Puzzle;
+
+/// All known solvers. A day can have several solvers registered, the first one
+/// registered for a day is the default.
+pub struct Registry {
+    puzzles:Vec<Box<dyn Puzzle>>,
+}
+
+impl Registry {
+    pub fn new() -> Registry {
+        Registry{puzzles:vec![]}
+    }
+
+    pub fn register<P:Puzzle + 'static>(&mut self, puzzle:P) {
+        self.puzzles.push(Box::new(puzzle));
+    }
+
+    pub fn get(&self, day:u8) -> Option<&dyn Puzzle> {
+        self.iter().find(|puzzle| puzzle.day() == day)
+    }
+
+    pub fn find(&self, name:&str) -> Option<&dyn Puzzle> {
+        self.iter().find(|puzzle| puzzle.name().eq_ignore_ascii_case(name))
+    }
+
+    pub fn variants(&self, day:u8) -> Vec<&dyn Puzzle> {
+        self.iter().filter(|puzzle| puzzle.day() == day).collect()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item=&dyn Puzzle> {
+        self.puzzles.iter().map(|puzzle| puzzle.as_ref())
+    }
+
+    pub fn days(&self) -> Vec<u8> {
+        let mut days:Vec<u8> = self.iter().map(|puzzle| puzzle.day()).collect();
+        days.sort_unstable();
+        days.dedup();
+        days
+    }
+
+    /// Days where the default solver is a real implementation.
+    pub fn implemented_days(&self) -> Vec<u8> {
+        self.days().into_iter()
+            .filter(|day| !self.get(*day).unwrap().is_stub())
+            .collect()
+    }
+
+    /// Days where the default solver only returns a placeholder.
+    pub fn stubbed_days(&self) -> Vec<u8> {
+        self.days().into_iter()
+            .filter(|day| self.get(*day).unwrap().is_stub())
+            .collect()
+    }
+}
+
+impl Default for Registry {
+    fn default() -> Registry {
+        Registry::new()
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use util::error::Result;
+    use util::params::NoParams;
+    use util::registry;
+    use util::solver::Solver;
+    use util::Part;
+
+    struct Variant;
+
+    impl Solver for Variant {
+        type Input = usize;
+
+        type Params = NoParams;
+
+        fn name(&self) -> &'static str {
+            "Sonar Sweep (length)"
+        }
+
+        fn day(&self) -> u8 {
+            1
+        }
+
+        fn parse(&self, input:&str) -> Result<usize> {
+            Ok(input.lines().count())
+        }
+
+        fn part1(&self, input:&usize, _:&NoParams) -> Result<String> {
+            Ok(input.to_string())
+        }
+
+        fn part2(&self, input:&usize, _:&NoParams) -> Result<String> {
+            Ok(input.to_string())
+        }
+    }
+
+    #[test]
+    fn test_all_days() {
+        let registry = registry();
+        assert_eq!((1..=25).collect::<Vec<u8>>(), registry.days());
+        assert_eq!(vec![23, 24], registry.stubbed_days());
+        assert_eq!(23, registry.implemented_days().len());
+    }
+
+    #[test]
+    fn test_examples() {
+        let registry = registry();
+        for puzzle in registry.iter() {
+            for example in puzzle.examples() {
+                for &part in [Part::Part1, Part::Part2].iter() {
+                    if let Some(answer) = example.answer(part) {
+                        assert_eq!(Ok(answer.to_string()), puzzle.solve(example.input, part), "day {}, {}", puzzle.day(), part);
+                    }
+                }
+            }
+        }
+        assert!(!registry.get(1).unwrap().examples().is_empty());
+    }
+
+    #[test]
+    fn test_variants() {
+        let mut registry = registry();
+        registry.register(Variant);
+
+        assert_eq!("Sonar Sweep", registry.get(1).unwrap().name());
+        assert_eq!(2, registry.variants(1).len());
+
+        let variant = registry.find("sonar sweep (length)").unwrap();
+        assert_eq!("3", variant.solve("1\n2\n3", Part::Part2).unwrap());
+        assert!(registry.find("Sonar Sweep (bits)").is_none());
+    }
+}
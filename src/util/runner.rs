@@ -0,0 +1,579 @@
+use std::cmp::Reverse;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fs;
+use std::panic::{self, AssertUnwindSafe};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, RecvTimeoutError, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+use super::error::{Result, SolveError};
+use super::json;
+use super::registry::Registry;
+use super::solver::Puzzle;
+use super::Part;
+
+/// Outcome of solving one part.
+pub struct PartRun {
+    pub part:Part,
+    pub answer:Result<String>,
+    pub duration:Duration,
+    /// The solver didn't finish within the timeout, `answer` holds an error.
+    pub timed_out:bool,
+}
+
+/// Outcome of running both parts of one day, the input is parsed once.
+pub struct DayRun {
+    pub day:u8,
+    pub name:&'static str,
+    pub is_stub:bool,
+    /// Hash of the input, `None` when it couldn't be read.
+    pub input_hash:Option<String>,
+    pub parse_duration:Duration,
+    pub parts:Vec<PartRun>,
+}
+
+impl DayRun {
+    pub fn total(&self) -> Duration {
+        self.parse_duration + self.parts.iter().map(|part| part.duration).sum::<Duration>()
+    }
+
+    pub fn is_ok(&self) -> bool {
+        self.parts.iter().all(|part| part.answer.is_ok())
+    }
+
+    /// One JSON object per part.
+    pub fn to_json(&self) -> Vec<String> {
+        self.parts.iter()
+            .map(|part| {
+                let status = match &part.answer {
+                    Err(_) if part.timed_out => "timeout",
+                    Err(_) => "error",
+                    Ok(_) if self.is_stub => "stub",
+                    Ok(_) => "ok",
+                };
+
+                json::Object::new()
+                    .number("day", self.day)
+                    .number("part", part.part.number())
+                    .string("solver", self.name)
+                    .string("status", status)
+                    .optional("answer", part.answer.as_ref().ok().map(|answer| answer.as_str()))
+                    .optional("error", part.answer.as_ref().err().map(|e| e.to_string()).as_deref())
+                    .number("parse_us", self.parse_duration.as_micros())
+                    .number("duration_us", part.duration.as_micros())
+                    .optional("input_hash", self.input_hash.as_deref())
+                    .to_string()
+            })
+            .collect()
+    }
+}
+
+/// FNV-1a hash of the input as 16 hex digits, used to tell input sets apart.
+pub fn input_hash(input:&str) -> String {
+    let hash = input.bytes().fold(0xcbf29ce484222325u64, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x100000001b3)
+    });
+    format!("{:016x}", hash)
+}
+
+pub fn input_path(dir:&Path, day:u8) -> PathBuf {
+    dir.join(format!("input_{:02}.txt", day))
+}
+
+pub fn read_input(dir:&Path, day:u8) -> Result<String> {
+    let path = input_path(dir, day);
+    fs::read_to_string(&path).map_err(|e| {
+        SolveError::new(&format!("can't read {}: {}", path.display(), e)).with_day(day)
+    })
+}
+
+/// Parses `input` and solves both parts, timing each step.
+pub fn run_day(puzzle:&dyn Puzzle, input:&str) -> DayRun {
+    run_parts(puzzle, input, &[Part::Part1, Part::Part2], &[])
+}
+
+/// Parses `input` once and solves `parts` with the `params` assignments, timing each step.
+pub fn run_parts(puzzle:&dyn Puzzle, input:&str, parts:&[Part], params:&[(String, String)]) -> DayRun {
+    let start = Instant::now();
+    let parsed = puzzle.parse_input(input);
+    let parse_duration = start.elapsed();
+
+    let parts = parts.iter()
+        .map(|&part| {
+            let start = Instant::now();
+            let answer = match &parsed {
+                Ok(parsed) => puzzle.solve_input_with(parsed.as_ref(), part, params),
+                Err(e) => Err(e.clone().with_part(part)),
+            };
+            PartRun{part, answer, duration:start.elapsed(), timed_out:false}
+        })
+        .collect();
+
+    DayRun{day:puzzle.day(), name:puzzle.name(), is_stub:puzzle.is_stub(),
+           input_hash:Some(input_hash(input)), parse_duration, parts}
+}
+
+/// Runs the default solver of every day against the `input_NN.txt` files in `dir`.
+pub fn run_all(registry:&Registry, dir:&Path) -> Vec<DayRun> {
+    registry.days().into_iter()
+        .map(|day| {
+            let puzzle = registry.get(day).unwrap();
+            match read_input(dir, day) {
+                Ok(input) => run_day(puzzle, &input),
+                Err(e) => failed(puzzle, e),
+            }
+        })
+        .collect()
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct RunConfig {
+    pub workers:usize,
+    /// Wall time limit for parsing and solving one part.
+    pub timeout:Option<Duration>,
+}
+
+impl Default for RunConfig {
+    fn default() -> RunConfig {
+        let workers = thread::available_parallelism().map(|n| n.get()).unwrap_or(1);
+        RunConfig{workers, timeout:None}
+    }
+}
+
+/// Like `run_all`, but the days are parsed and solved on `config.workers`
+/// threads. Runs are returned in day order.
+///
+/// A parse or part that exceeds the timeout is reported as timed out and its
+/// worker is abandoned: there is no way to stop a thread, so it keeps running
+/// in the background until the solver returns and a new worker takes its
+/// place. Parts of that day still to solve are parsed again by another worker.
+pub fn run_all_parallel(registry:Arc<Registry>, dir:&Path, config:RunConfig) -> Vec<DayRun> {
+    let inputs:Vec<(u8, Result<Arc<String>>)> = registry.days().into_iter()
+        .map(|day| (day, read_input(dir, day).map(Arc::new)))
+        .collect();
+
+    // Index of the input and parts of every job, jobs are added on timeouts
+    let mut jobs:Vec<(usize, Vec<Part>)> = vec![];
+    let queue:Queue = Arc::new(Mutex::new(VecDeque::new()));
+    for (i, (day, input)) in inputs.iter().enumerate() {
+        if let Ok(input) = input {
+            let parts = vec![Part::Part1, Part::Part2];
+            queue.lock().unwrap().push_back(Job{id:jobs.len(), day:*day, parts:parts.clone(), input:Arc::clone(input)});
+            jobs.push((i, parts));
+        }
+    }
+
+    let (sender, receiver) = mpsc::channel();
+    let mut workers = config.workers.max(1).min(jobs.len());
+    for worker in 0..workers {
+        spawn_worker(worker, &registry, &queue, &sender);
+    }
+
+    let mut parse_durations = vec![Duration::default(); inputs.len()];
+    let mut parts:Vec<Vec<PartRun>> = inputs.iter().map(|_| vec![]).collect();
+    let mut parsed = vec![false; jobs.len()];
+    let mut solved = vec![0; jobs.len()];
+    let mut pending = jobs.len() * 2;
+
+    // Job and start of the current step of every worker that is running one
+    let mut busy:HashMap<usize, (usize, Instant)> = HashMap::new();
+    let mut abandoned:HashSet<usize> = HashSet::new();
+    while pending > 0 {
+        let deadline = config.timeout.and_then(|timeout| busy.values().map(|&(_, start)| start + timeout).min());
+        let received = match deadline {
+            Some(deadline) => receiver.recv_timeout(deadline.saturating_duration_since(Instant::now())),
+            None => receiver.recv().map_err(|_| RecvTimeoutError::Disconnected),
+        };
+
+        match received {
+            Ok((worker, _, _)) if abandoned.contains(&worker) => {}
+            Ok((worker, job, progress)) => {
+                busy.insert(worker, (job, Instant::now()));
+                let input = jobs[job].0;
+                match progress {
+                    Progress::Started => {}
+                    Progress::Parsed(duration) => {
+                        parsed[job] = true;
+                        parse_durations[input] = parse_durations[input].max(duration);
+                    }
+                    Progress::Solved(run) => {
+                        parts[input].push(run);
+                        pending -= 1;
+                        solved[job] += 1;
+                        if solved[job] == jobs[job].1.len() {
+                            busy.remove(&worker);
+                        }
+                    }
+                }
+            }
+            Err(RecvTimeoutError::Timeout) => {
+                let now = Instant::now();
+                let timeout = config.timeout.unwrap();
+                let expired:Vec<usize> = busy.iter()
+                    .filter(|(_, &(_, start))| now >= start + timeout)
+                    .map(|(&worker, _)| worker)
+                    .collect();
+
+                for worker in expired {
+                    let (job, start) = busy.remove(&worker).unwrap();
+                    abandoned.insert(worker);
+
+                    // A parse that hangs takes every part of the job with it
+                    let (input, ref job_parts) = jobs[job];
+                    let day = inputs[input].0;
+                    let remaining = job_parts[solved[job]..].to_vec();
+                    let hanging = if parsed[job] { 1 } else { remaining.len() };
+                    for &part in &remaining[..hanging] {
+                        let message = format!("timed out after {}", format_duration(now - start));
+                        let answer = Err(SolveError::new(&message).with_day(day).with_part(part));
+                        parts[input].push(PartRun{part, answer, duration:now - start, timed_out:true});
+                        pending -= 1;
+                    }
+
+                    if hanging < remaining.len() {
+                        let rest = remaining[hanging..].to_vec();
+                        let input_text = Arc::clone(inputs[input].1.as_ref().unwrap());
+                        queue.lock().unwrap().push_back(Job{id:jobs.len(), day, parts:rest.clone(), input:input_text});
+                        jobs.push((input, rest));
+                        parsed.push(false);
+                        solved.push(0);
+                    }
+
+                    spawn_worker(workers, &registry, &queue, &sender);
+                    workers += 1;
+                }
+            }
+            // The coordinator holds a sender, so this doesn't happen
+            Err(RecvTimeoutError::Disconnected) => break,
+        }
+    }
+
+    inputs.into_iter()
+        .zip(parts)
+        .zip(parse_durations)
+        .map(|(((day, input), mut parts), parse_duration)| {
+            let puzzle = registry.get(day).unwrap();
+            match input {
+                Ok(input) => {
+                    parts.sort_by_key(|part| part.part.number());
+                    DayRun{day, name:puzzle.name(), is_stub:puzzle.is_stub(), input_hash:Some(input_hash(&input)),
+                           parse_duration, parts}
+                }
+                Err(e) => failed(puzzle, e),
+            }
+        })
+        .collect()
+}
+
+/// Parts of one day for a worker, parsed once for all of them.
+struct Job {
+    id:usize,
+    day:u8,
+    parts:Vec<Part>,
+    input:Arc<String>,
+}
+
+type Queue = Arc<Mutex<VecDeque<Job>>>;
+
+/// What a worker reports about its job, the start of the next step for the timeout.
+enum Progress {
+    Started,
+    Parsed(Duration),
+    Solved(PartRun),
+}
+
+/// Takes jobs from `queue` until it is empty, reporting each step with the
+/// worker number and the job id.
+fn spawn_worker(worker:usize, registry:&Arc<Registry>, queue:&Queue, sender:&Sender<(usize, usize, Progress)>) {
+    let (registry, queue, sender) = (Arc::clone(registry), Arc::clone(queue), sender.clone());
+    thread::spawn(move || loop {
+        let next = queue.lock().unwrap().pop_front();
+        let job = match next {
+            Some(job) => job,
+            None => break,
+        };
+        // Nobody is listening anymore once the run is over
+        let send = |progress| sender.send((worker, job.id, progress)).is_ok();
+        if !send(Progress::Started) {
+            break;
+        }
+
+        let puzzle = registry.get(job.day).unwrap();
+        let start = Instant::now();
+        let parsed = guard(|| puzzle.parse_input(&job.input));
+        send(Progress::Parsed(start.elapsed()));
+
+        for &part in &job.parts {
+            let start = Instant::now();
+            let answer = match &parsed {
+                Ok(parsed) => guard(|| puzzle.solve_input_with(parsed.as_ref(), part, &[])),
+                Err(e) => Err(e.clone()),
+            };
+            let answer = answer.map_err(|e| e.with_day(job.day).with_part(part));
+            send(Progress::Solved(PartRun{part, answer, duration:start.elapsed(), timed_out:false}));
+        }
+    });
+}
+
+/// Runs `f`, turning a panic into an error.
+fn guard<T, F:FnOnce() -> Result<T>>(f:F) -> Result<T> {
+    panic::catch_unwind(AssertUnwindSafe(f)).unwrap_or_else(|_| Err(SolveError::new("solver panicked")))
+}
+
+fn failed(puzzle:&dyn Puzzle, error:SolveError) -> DayRun {
+    let parts = [Part::Part1, Part::Part2].iter()
+        .map(|&part| PartRun{part, answer:Err(error.clone().with_part(part)), duration:Duration::default(), timed_out:false})
+        .collect();
+
+    DayRun{day:puzzle.day(), name:puzzle.name(), is_stub:puzzle.is_stub(),
+           input_hash:None, parse_duration:Duration::default(), parts}
+}
+
+pub fn format_duration(duration:Duration) -> String {
+    let micros = duration.as_secs_f64() * 1e6;
+    if micros < 1000.0 {
+        format!("{:.1} us", micros)
+    } else if micros < 1e6 {
+        format!("{:.2} ms", micros / 1e3)
+    } else {
+        format!("{:.2} s", micros / 1e6)
+    }
+}
+
+/// Short form of an answer that fits in a table cell.
+fn format_answer(part:&PartRun, width:usize) -> String {
+    let answer = match &part.answer {
+        Ok(answer) if answer.contains('\n') => format!("<{} lines>", answer.trim_end().lines().count()),
+        Ok(answer) => answer.clone(),
+        Err(_) if part.timed_out => "timeout".to_string(),
+        Err(_) => "error".to_string(),
+    };
+
+    if answer.chars().count() > width {
+        let mut short:String = answer.chars().take(width - 3).collect();
+        short.push_str("...");
+        short
+    } else {
+        answer
+    }
+}
+
+/// Prints one row per day followed by the total and the `slowest` slowest days.
+pub fn print_report(runs:&[DayRun], slowest:usize) {
+    println!("{:>3}  {:<28} {:>10}  {:<16} {:>10}  {:<16} {:>10}  {:>10}",
+             "day", "name", "parse", "part 1", "time", "part 2", "time", "total");
+
+    for run in runs {
+        let name = if run.is_stub { format!("{} (stub)", run.name) } else { run.name.to_string() };
+        let mut row = format!("{:>3}  {:<28} {:>10}", run.day, name, format_duration(run.parse_duration));
+        for part in &run.parts {
+            row.push_str(&format!("  {:<16} {:>10}", format_answer(part, 16), format_duration(part.duration)));
+        }
+        row.push_str(&format!("  {:>10}", format_duration(run.total())));
+        println!("{}", row);
+    }
+
+    let total:Duration = runs.iter().map(|run| run.total()).sum();
+    println!("total: {}", format_duration(total));
+
+    for run in runs.iter().filter(|run| !run.is_ok()) {
+        for part in &run.parts {
+            if let Err(e) = &part.answer {
+                println!("error: {}", e);
+            }
+        }
+    }
+
+    let mut sorted:Vec<&DayRun> = runs.iter().collect();
+    sorted.sort_by_key(|run| Reverse(run.total()));
+
+    println!("slowest days:");
+    for run in sorted.iter().take(slowest) {
+        let share = if total.as_nanos() > 0 {
+            run.total().as_secs_f64() * 100.0 / total.as_secs_f64()
+        } else {
+            0.0
+        };
+        println!("{:>3}  {:<28} {:>10}  {:>5.1}%", run.day, run.name, format_duration(run.total()), share);
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::Path;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use util::params::NoParams;
+    use util::registry;
+    use util::solver::Solver;
+
+    /// Day 1 solver where part 2 never finishes in time.
+    struct Slow;
+
+    impl Solver for Slow {
+        type Input = ();
+
+        type Params = NoParams;
+
+        fn name(&self) -> &'static str {
+            "Slow"
+        }
+
+        fn day(&self) -> u8 {
+            1
+        }
+
+        fn parse(&self, _:&str) -> Result<()> {
+            Ok(())
+        }
+
+        fn part1(&self, _:&(), _:&NoParams) -> Result<String> {
+            Ok("1".to_string())
+        }
+
+        fn part2(&self, _:&(), _:&NoParams) -> Result<String> {
+            thread::sleep(Duration::from_secs(10));
+            Ok("2".to_string())
+        }
+    }
+
+    #[test]
+    fn test_run_day() {
+        let registry = registry();
+        let run = run_day(registry.get(1).unwrap(), "199\n200\n208\n210\n200\n207\n240\n269\n260\n263");
+
+        assert_eq!(1, run.day);
+        assert!(run.is_ok());
+        assert_eq!(Ok("7".to_string()), run.parts[0].answer);
+        assert_eq!(Ok("5".to_string()), run.parts[1].answer);
+
+        let run = run_day(registry.get(1).unwrap(), "199\nx");
+        assert!(!run.is_ok());
+        assert_eq!(Some(Part::Part2), run.parts[1].answer.clone().unwrap_err().part);
+    }
+
+    #[test]
+    fn test_run_all_missing_input() {
+        let registry = registry();
+        let runs = run_all(&registry, Path::new("no_such_dir"));
+
+        assert_eq!(25, runs.len());
+        assert!(runs.iter().all(|run| !run.is_ok()));
+    }
+
+    #[test]
+    fn test_run_all_parallel() {
+        let config = RunConfig{workers:4, timeout:None};
+        let runs = run_all_parallel(Arc::new(registry()), Path::new("."), config);
+        let expected = run_all(&registry(), Path::new("."));
+
+        assert_eq!(expected.iter().map(|run| run.day).collect::<Vec<u8>>(), runs.iter().map(|run| run.day).collect::<Vec<u8>>());
+        for (run, expected) in runs.iter().zip(expected.iter()) {
+            let answers:Vec<_> = run.parts.iter().map(|part| part.answer.clone()).collect();
+            let expected_answers:Vec<_> = expected.parts.iter().map(|part| part.answer.clone()).collect();
+            assert_eq!(expected_answers, answers, "day {}", run.day);
+        }
+
+        let runs = run_all_parallel(Arc::new(registry()), Path::new("no_such_dir"), config);
+        assert_eq!(25, runs.len());
+        assert!(runs.iter().all(|run| !run.is_ok()));
+    }
+
+    #[test]
+    fn test_timeout() {
+        let mut registry = Registry::new();
+        registry.register(Slow);
+        let config = RunConfig{workers:2, timeout:Some(Duration::from_millis(200))};
+
+        let start = Instant::now();
+        let runs = run_all_parallel(Arc::new(registry), Path::new("."), config);
+        assert!(start.elapsed() < Duration::from_secs(5));
+
+        let parts = &runs[0].parts;
+        assert_eq!(Ok("1".to_string()), parts[0].answer);
+        assert!(!parts[0].timed_out && parts[1].timed_out);
+        assert!(parts[1].answer.clone().unwrap_err().message.starts_with("timed out after"));
+        assert!(runs[0].to_json()[1].contains("\"status\":\"timeout\""));
+    }
+
+    static STUCK_PARSES:AtomicUsize = AtomicUsize::new(0);
+
+    /// Day 1 solver that counts its parses, part 1 never finishes in time.
+    struct Stuck;
+
+    impl Solver for Stuck {
+        type Input = ();
+
+        type Params = NoParams;
+
+        fn name(&self) -> &'static str {
+            "Stuck"
+        }
+
+        fn day(&self) -> u8 {
+            1
+        }
+
+        fn parse(&self, _:&str) -> Result<()> {
+            STUCK_PARSES.fetch_add(1, Ordering::SeqCst);
+            Ok(())
+        }
+
+        fn part1(&self, _:&(), _:&NoParams) -> Result<String> {
+            thread::sleep(Duration::from_secs(10));
+            Ok("1".to_string())
+        }
+
+        fn part2(&self, _:&(), _:&NoParams) -> Result<String> {
+            Ok("2".to_string())
+        }
+    }
+
+    #[test]
+    fn test_timeout_requeue() {
+        let mut registry = Registry::new();
+        registry.register(Stuck);
+        let config = RunConfig{workers:1, timeout:Some(Duration::from_millis(200))};
+
+        let runs = run_all_parallel(Arc::new(registry), Path::new("."), config);
+        let parts = &runs[0].parts;
+        assert!(parts[0].timed_out);
+        assert_eq!(Ok("2".to_string()), parts[1].answer);
+        // Once for both parts, once more for the part left after the timeout
+        assert_eq!(2, STUCK_PARSES.load(Ordering::SeqCst));
+    }
+
+    #[test]
+    fn test_json() {
+        let registry = registry();
+        let run = run_parts(registry.get(1).unwrap(), "199\n200\n208", &[Part::Part2], &[]);
+        let json = run.to_json();
+
+        assert_eq!(1, json.len());
+        assert!(json[0].starts_with("{\"day\":1,\"part\":2,\"solver\":\"Sonar Sweep\",\"status\":\"ok\",\"answer\":\"0\",\"error\":null,"));
+        assert!(json[0].ends_with(&format!("\"input_hash\":\"{}\"}}", input_hash("199\n200\n208"))));
+
+        let run = run_day(registry.get(1).unwrap(), "x");
+        assert!(run.to_json()[0].contains("\"status\":\"error\",\"answer\":null,\"error\":\"day 1, part 1, line 1, column 1: invalid value 'x'\""));
+    }
+
+    #[test]
+    fn test_input_hash() {
+        assert_eq!("cbf29ce484222325", input_hash(""));
+        assert_eq!("af63ac4c86019afc", input_hash("1"));
+    }
+
+    #[test]
+    fn test_format() {
+        assert_eq!("12.0 us", format_duration(Duration::from_micros(12)));
+        assert_eq!("1.50 ms", format_duration(Duration::from_micros(1500)));
+        assert_eq!("2.00 s", format_duration(Duration::from_secs(2)));
+
+        let part = PartRun{part:Part::Part1, answer:Ok("123456789".to_string()), duration:Duration::default(), timed_out:false};
+        assert_eq!("12345...", format_answer(&part, 8));
+        let part = PartRun{part:Part::Part2, answer:Ok("#.\n.#\n".to_string()), duration:Duration::default(), timed_out:false};
+        assert_eq!("<2 lines>", format_answer(&part, 16));
+    }
+}
@@ -0,0 +1,126 @@
+use std::any::Any;
+use super::error::Result;
+use super::params::{self, Param, Params};
+use super::Part;
+
+/// Example input from the puzzle description with the answers given there.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Example {
+    pub input:&'static str,
+    pub part1:Option<&'static str>,
+    pub part2:Option<&'static str>,
+}
+
+impl Example {
+    pub fn answer(&self, part:Part) -> Option<&'static str> {
+        match part {
+            Part::Part1 => self.part1,
+            Part::Part2 => self.part2,
+        }
+    }
+}
+
+/// A solution for one day of the calendar.
+///
+/// The input is parsed once and shared between both parts. Solvers are shared
+/// between the threads of the parallel runner.
+pub trait Solver: Send + Sync {
+    type Input: 'static;
+
+    type Params: Params;
+
+    fn name(&self) -> &'static str;
+
+    fn day(&self) -> u8;
+
+    /// Stubbed solvers only return a placeholder answer.
+    fn is_stub(&self) -> bool {
+        false
+    }
+
+    fn examples(&self) -> Vec<Example> {
+        vec![]
+    }
+
+    fn parse(&self, input:&str) -> Result<Self::Input>;
+
+    fn part1(&self, input:&Self::Input, params:&Self::Params) -> Result<String>;
+
+    fn part2(&self, input:&Self::Input, params:&Self::Params) -> Result<String>;
+}
+
+/// Object safe view of a `Solver`, this is what the registry stores.
+///
+/// Errors returned from here carry the day, and the part when solving.
+pub trait Puzzle: Send + Sync {
+    fn name(&self) -> &'static str;
+
+    fn day(&self) -> u8;
+
+    fn is_stub(&self) -> bool;
+
+    fn examples(&self) -> Vec<Example>;
+
+    /// Parameters with their default values.
+    fn params(&self) -> Vec<Param>;
+
+    fn parse_input(&self, input:&str) -> Result<Box<dyn Any>>;
+
+    /// Solves with the default parameters changed by the `name`, `value` pairs
+    /// in `params`.
+    fn solve_input_with(&self, input:&dyn Any, part:Part, params:&[(String, String)]) -> Result<String>;
+
+    fn solve_input(&self, input:&dyn Any, part:Part) -> Result<String> {
+        self.solve_input_with(input, part, &[])
+    }
+
+    fn solve_with(&self, input:&str, part:Part, params:&[(String, String)]) -> Result<String> {
+        let parsed = self.parse_input(input).map_err(|e| e.with_part(part))?;
+        self.solve_input_with(parsed.as_ref(), part, params)
+    }
+
+    fn solve(&self, input:&str, part:Part) -> Result<String> {
+        self.solve_with(input, part, &[])
+    }
+}
+
+impl<S:Solver> Puzzle for S {
+    fn name(&self) -> &'static str {
+        Solver::name(self)
+    }
+
+    fn day(&self) -> u8 {
+        Solver::day(self)
+    }
+
+    fn is_stub(&self) -> bool {
+        Solver::is_stub(self)
+    }
+
+    fn examples(&self) -> Vec<Example> {
+        Solver::examples(self)
+    }
+
+    fn params(&self) -> Vec<Param> {
+        S::Params::default().list()
+    }
+
+    fn parse_input(&self, input:&str) -> Result<Box<dyn Any>> {
+        match self.parse(input) {
+            Ok(parsed) => Ok(Box::new(parsed)),
+            Err(e) => Err(e.with_day(self.day())),
+        }
+    }
+
+    fn solve_input_with(&self, input:&dyn Any, part:Part, params:&[(String, String)]) -> Result<String> {
+        let input = input.downcast_ref::<S::Input>()
+            .expect("input was parsed by another solver");
+
+        let result = params::with_assignments::<S::Params>(params).and_then(|params| match part {
+            Part::Part1 => self.part1(input, &params),
+            Part::Part2 => self.part2(input, &params),
+        });
+
+        result.map_err(|e| e.with_day(self.day()).with_part(part))
+    }
+}
@@ -0,0 +1,169 @@
+use std::fs;
+use std::path::Path;
+use super::error::{Result, SolveError};
+use super::registry::Registry;
+use super::Part;
+
+/// Expected answer for one day and part on a given input file.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Entry {
+    pub day:u8,
+    pub part:Part,
+    pub input:String,
+    pub answer:String,
+}
+
+/// Answers manifest, one tab separated `<day> <part> <input file> <answer>` entry
+/// per line. Newlines and backslashes in answers are escaped as `\n` and `\\`,
+/// lines starting with `#` are comments.
+#[derive(Debug, Default, PartialEq)]
+pub struct Manifest {
+    pub entries:Vec<Entry>,
+}
+
+impl Manifest {
+    pub fn load(path:&Path) -> Result<Manifest> {
+        let text = fs::read_to_string(path).map_err(|e| {
+            SolveError::new(&format!("can't read {}: {}", path.display(), e))
+        })?;
+        Manifest::parse(&text)
+    }
+
+    pub fn parse(text:&str) -> Result<Manifest> {
+        let mut entries = vec![];
+        for (i, line) in text.lines().enumerate() {
+            if line.trim().is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let fields:Vec<&str> = line.splitn(4, '\t').collect();
+            if fields.len() != 4 {
+                return Err(SolveError::on_line(i + 1, "expected '<day>\\t<part>\\t<input>\\t<answer>'"));
+            }
+
+            let day = fields[0].parse::<u8>()
+                .map_err(|_| SolveError::at(i + 1, 1, &format!("invalid day '{}'", fields[0])))?;
+            let part = fields[1].parse::<u8>().ok()
+                .and_then(Part::from_number)
+                .ok_or_else(|| SolveError::at(i + 1, fields[0].len() + 2, &format!("invalid part '{}'", fields[1])))?;
+
+            entries.push(Entry{day, part, input:fields[2].to_string(), answer:unescape(fields[3])});
+        }
+        Ok(Manifest{entries})
+    }
+
+    pub fn get(&self, day:u8, part:Part) -> Vec<&Entry> {
+        self.entries.iter().filter(|entry| entry.day == day && entry.part == part).collect()
+    }
+}
+
+fn unescape(answer:&str) -> String {
+    let mut result = String::new();
+    let mut chars = answer.chars();
+    while let Some(ch) = chars.next() {
+        if ch != '\\' {
+            result.push(ch);
+            continue;
+        }
+
+        match chars.next() {
+            Some('n') => result.push('\n'),
+            Some(other) => result.push(other),
+            None => result.push('\\'),
+        }
+    }
+    result
+}
+
+pub fn escape(answer:&str) -> String {
+    answer.replace('\\', "\\\\").replace('\n', "\\n")
+}
+
+#[derive(Debug, PartialEq)]
+pub enum Status {
+    Pass,
+    Fail(String),
+    Error(SolveError),
+    /// No entry in the manifest for this day and part.
+    Missing,
+}
+
+pub struct Check {
+    pub day:u8,
+    pub part:Part,
+    pub solver:&'static str,
+    pub input:Option<String>,
+    pub status:Status,
+}
+
+/// Runs every registered solver against the manifest entries for its day, input
+/// files are relative to `dir`.
+pub fn verify(registry:&Registry, manifest:&Manifest, dir:&Path) -> Vec<Check> {
+    let mut checks = vec![];
+    for puzzle in registry.iter() {
+        for &part in [Part::Part1, Part::Part2].iter() {
+            let entries = manifest.get(puzzle.day(), part);
+            if entries.is_empty() {
+                checks.push(Check{day:puzzle.day(), part, solver:puzzle.name(), input:None, status:Status::Missing});
+            }
+
+            for entry in entries {
+                let path = dir.join(&entry.input);
+                let status = match fs::read_to_string(&path) {
+                    Ok(input) => match puzzle.solve(&input, part) {
+                        Ok(ref answer) if *answer == entry.answer => Status::Pass,
+                        Ok(answer) => Status::Fail(answer),
+                        Err(e) => Status::Error(e),
+                    },
+                    Err(e) => Status::Error(SolveError::new(&format!("can't read {}: {}", path.display(), e))),
+                };
+                checks.push(Check{day:puzzle.day(), part, solver:puzzle.name(), input:Some(entry.input.clone()), status});
+            }
+        }
+    }
+    checks
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use util::registry;
+
+    #[test]
+    fn test_parse() {
+        let manifest = Manifest::parse("# day\tpart\tinput\tanswer\n1\t2\tinput_01.txt\t1739\n\n13\t2\ta.txt\t #\\n# \\\\\n").unwrap();
+
+        assert_eq!(2, manifest.entries.len());
+        assert_eq!(Entry{day:1, part:Part::Part2, input:"input_01.txt".to_string(), answer:"1739".to_string()}, manifest.entries[0]);
+        assert_eq!(" #\n# \\", manifest.entries[1].answer);
+        assert_eq!("#\\n# \\\\", escape("#\n# \\"));
+
+        let error = Manifest::parse("1\t2\tinput_01.txt\t1\n1\t3\tinput_01.txt\t1").unwrap_err();
+        assert_eq!((Some(2), Some(3)), (error.line, error.column));
+        assert!(Manifest::parse("1 2 input_01.txt 1").is_err());
+    }
+
+    #[test]
+    fn test_verify() {
+        let manifest = Manifest::parse("1\t1\tinput_01.txt\t1715\n1\t2\tinput_01.txt\t1\n2\t1\tno_such_file.txt\t1").unwrap();
+        let checks = verify(&registry(), &manifest, Path::new("."));
+
+        let status = |day, part| &checks.iter().find(|check| check.day == day && check.part == part).unwrap().status;
+        assert_eq!(&Status::Pass, status(1, Part::Part1));
+        assert_eq!(&Status::Fail("1739".to_string()), status(1, Part::Part2));
+        assert!(matches!(status(2, Part::Part1), Status::Error(_)));
+        assert_eq!(&Status::Missing, status(3, Part::Part1));
+        assert_eq!(50, checks.len());
+    }
+
+    #[test]
+    fn test_manifest() {
+        let registry = registry();
+        let manifest = Manifest::load(Path::new("answers.tsv")).unwrap();
+
+        for day in registry.implemented_days() {
+            assert!(!manifest.get(day, Part::Part1).is_empty(), "day {}", day);
+        }
+    }
+}